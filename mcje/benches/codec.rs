@@ -0,0 +1,169 @@
+use std::{borrow::Cow, collections::BTreeMap, io::Read};
+
+use bytes::BytesMut;
+use criterion::{criterion_group, criterion_main, BatchSize, Criterion, Throughput};
+use flate2::read::ZlibEncoder;
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use tokio_util::codec::{Decoder, Encoder};
+
+use iokum_mcje::{
+    codec::{Codec, Compression, Frame},
+    packet::s2c::game::{GamePacket, LevelChunkPacketData, LightUpdatePacketData},
+    types::Nbt,
+    Encode,
+};
+
+const CHUNKS: usize = 64;
+
+/// Chunk packets with palette-like block data and light arrays, which compress
+/// about as well as the ones sent by vanilla servers.
+fn chunks() -> Vec<Vec<u8>> {
+    let mut rng = StdRng::seed_from_u64(0);
+    (0..CHUNKS as i32)
+        .map(|x| {
+            let palette: Vec<u8> = (0..8).map(|_| rng.gen()).collect();
+            let buffer = (0..24 * 4096 / 2)
+                .map(|_| palette[rng.gen_range(0..palette.len())])
+                .collect::<Vec<_>>();
+            // sky light is either full or empty for each section
+            let sky_updates = (0..26)
+                .map(|_| Cow::Owned(vec![if rng.gen_bool(0.5) { 0xFF } else { 0x00 }; 2048]))
+                .collect();
+            let block_updates = (0..26).map(|_| Cow::Owned(vec![0; 2048])).collect();
+            let packet = GamePacket::LevelChunkWithLight {
+                x,
+                z: 0,
+                chunk_data: LevelChunkPacketData {
+                    heightmaps: Nbt(serde_value::Value::Map(BTreeMap::new())),
+                    buffer: Cow::Owned(buffer),
+                    block_entities_data: vec![],
+                },
+                light_data: LightUpdatePacketData {
+                    trust_edges: true,
+                    sky_y_mask: vec![0x3FFFFFF],
+                    block_y_mask: vec![0x3FFFFFF],
+                    empty_sky_y_mask: vec![],
+                    empty_block_y_mask: vec![],
+                    sky_updates,
+                    block_updates,
+                },
+            };
+            let mut data = vec![];
+            packet.encode(&mut data).unwrap();
+            data
+        })
+        .collect()
+}
+
+fn codec() -> Codec {
+    let mut codec = Codec::default();
+    codec.enable_compression(Compression::default(), 256);
+    codec
+}
+
+fn frames(chunks: &[Vec<u8>]) -> Vec<Frame> {
+    let mut codec = codec();
+    let mut data = BytesMut::new();
+    for chunk in chunks {
+        codec.encode(chunk.as_slice(), &mut data).unwrap();
+    }
+    let mut frames = vec![];
+    while let Some(frame) = codec.decode(&mut data).unwrap() {
+        frames.push(frame);
+    }
+    frames
+}
+
+fn encode(criterion: &mut Criterion) {
+    let chunks = chunks();
+    let mut group = criterion.benchmark_group("encode");
+    group.throughput(Throughput::Bytes(
+        chunks.iter().map(Vec::len).sum::<usize>() as u64,
+    ));
+    group.bench_function("codec", |bencher| {
+        let mut codec = codec();
+        let mut data = BytesMut::new();
+        bencher.iter(|| {
+            data.clear();
+            for chunk in &chunks {
+                codec.encode(chunk.as_slice(), &mut data).unwrap();
+            }
+        })
+    });
+    // a new encoder and buffer for each packet
+    group.bench_function("fresh", |bencher| {
+        bencher.iter(|| {
+            for chunk in &chunks {
+                let mut compressed_data = Vec::new();
+                ZlibEncoder::new(chunk.as_slice(), Compression::default())
+                    .read_to_end(&mut compressed_data)
+                    .unwrap();
+            }
+        })
+    });
+    group.finish();
+}
+
+fn decompress(criterion: &mut Criterion) {
+    let chunks = chunks();
+    let frames = frames(&chunks);
+    let mut group = criterion.benchmark_group("decompress");
+    group.throughput(Throughput::Bytes(
+        chunks.iter().map(Vec::len).sum::<usize>() as u64,
+    ));
+    group.bench_function("codec", |bencher| {
+        let mut codec = codec();
+        let mut data = vec![];
+        bencher.iter(|| {
+            for frame in &frames {
+                data.clear();
+                codec.decompress(frame, &mut data).unwrap();
+            }
+        })
+    });
+    group.bench_function("fresh", |bencher| {
+        bencher.iter(|| {
+            for frame in &frames {
+                frame.decompress().unwrap();
+            }
+        })
+    });
+    group.finish();
+}
+
+fn pass_through(criterion: &mut Criterion) {
+    let chunks = chunks();
+    let frames = frames(&chunks);
+    let mut group = criterion.benchmark_group("pass_through");
+    group.throughput(Throughput::Bytes(
+        chunks.iter().map(Vec::len).sum::<usize>() as u64,
+    ));
+    // same compression threshold on both sides
+    group.bench_function("verbatim", |bencher| {
+        let mut codec = codec();
+        let mut data = BytesMut::new();
+        bencher.iter(|| {
+            data.clear();
+            for frame in &frames {
+                codec.encode(frame, &mut data).unwrap();
+            }
+        })
+    });
+    group.bench_function("recompress", |bencher| {
+        let mut codec = Codec::default();
+        codec.enable_compression(Compression::default(), 512);
+        bencher.iter_batched_ref(
+            BytesMut::new,
+            |data| {
+                for frame in &frames {
+                    codec.encode(frame, data).unwrap();
+                }
+            },
+            BatchSize::SmallInput,
+        )
+    });
+    group.finish();
+}
+
+criterion_group!(benches, encode, decompress, pass_through);
+criterion_main!(benches);
@@ -10,16 +10,16 @@ use tokio::net::{TcpListener, TcpStream};
 use tokio_util::codec::Framed;
 use uuid::Uuid;
 
-use mojang_session_api::{
-    apis::{configuration::Configuration, default_api::join_server},
-    models::JoinServerRequest,
-};
-use staxmcje::{
+use iokum_mcje::{
     codec::Codec,
     packet::{c2s, s2c},
     types::Intention,
     Decode, Encode, Error,
 };
+use mojang_session_api::{
+    apis::{configuration::Configuration, default_api::join_server},
+    models::JoinServerRequest,
+};
 
 #[derive(Parser, Clone)]
 #[command(about)]
@@ -63,7 +63,7 @@ async fn handle(
         access_token,
         selected_profile,
     }: Arguments,
-) -> staxmcje::Result<()> {
+) -> iokum_mcje::Result<()> {
     // create wrapped (client) socket
     let mut socket = Framed::new(socket, Codec::default());
     // receive intention packet
@@ -131,7 +131,8 @@ async fn handle(
                 }
                 Intention::Login => {
                     // forward c2s hello packet
-                    let packet = next(&mut socket).await?.decode()?;
+                    let packet = next(&mut socket).await?;
+                    let packet = packet.decode()?;
                     if matches!(packet, c2s::LoginPacket::Hello { .. }) {
                         encode_and_send(&mut remote_socket, &packet).await;
                     } else {
@@ -232,7 +233,7 @@ async fn handle(
                                 let packet = packet.unwrap();
                                 if let Ok(packet) = packet.decode::<s2c::GamePacket>() {
                                     println!(">>");
-                                    println!("{:?}", packet)
+                                    println!("{:?}", packet);
                                     encode_and_send(&mut socket, &packet).await;
                                 }
                             }
@@ -250,7 +251,7 @@ async fn handle(
 struct Packet(Vec<u8>);
 
 impl Packet {
-    fn decode<'a, T: Decode<'a>>(&'a self) -> staxmcje::Result<T> {
+    fn decode<'a, T: Decode<'a>>(&'a self) -> iokum_mcje::Result<T> {
         T::decode(&mut self.0.as_slice())
     }
 }
@@ -258,14 +259,15 @@ impl Packet {
 async fn encode_and_send(socket: &mut Framed<TcpStream, Codec>, packet: &impl Encode) {
     let mut data = vec![];
     packet.encode(&mut data).unwrap();
-    socket.send(&data).await.unwrap();
+    socket.send(data.as_slice()).await.unwrap();
 }
 
-async fn next(socket: &mut Framed<TcpStream, Codec>) -> staxmcje::Result<Packet> {
+async fn next(socket: &mut Framed<TcpStream, Codec>) -> iokum_mcje::Result<Packet> {
     socket
         .next()
         .await
         .ok_or(Error::UnexpectedEnd)
         .flatten()
+        .and_then(|frame| frame.decompress())
         .map(Packet)
 }
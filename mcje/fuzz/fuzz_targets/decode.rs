@@ -0,0 +1,25 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+
+use iokum_mcje::{
+    packet::{c2s, s2c},
+    Decode,
+};
+
+// the first byte selects the decoder, decoding must fail gracefully
+fuzz_target!(|data: &[u8]| {
+    let Some((&selector, mut data)) = data.split_first() else {
+        return;
+    };
+    let data = &mut data;
+    let _ = match selector % 7 {
+        0 => c2s::HandshakePacket::decode(data).map(drop),
+        1 => c2s::StatusPacket::decode(data).map(drop),
+        2 => c2s::LoginPacket::decode(data).map(drop),
+        3 => c2s::GamePacket::decode(data).map(drop),
+        4 => s2c::StatusPacket::decode(data).map(drop),
+        5 => s2c::LoginPacket::decode(data).map(drop),
+        _ => s2c::GamePacket::decode(data).map(drop),
+    };
+});
@@ -0,0 +1,20 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+
+use iokum_mcje::{
+    arbitrary::{assert_round_trip, Unstructured},
+    packet::{Direction, State},
+};
+
+fuzz_target!(|data: &[u8]| {
+    let mut input = Unstructured::new(data);
+    let Ok(direction) = input.choose(&[Direction::C2s, Direction::S2c]) else {
+        return;
+    };
+    let Ok(state) = input.choose(&[State::Handshake, State::Status, State::Login, State::Game])
+    else {
+        return;
+    };
+    let _ = assert_round_trip(*direction, *state, &mut input);
+});
@@ -0,0 +1,323 @@
+use std::{borrow::Cow, collections::BTreeMap, hash::Hash};
+
+use glam::{DVec3, IVec3, Quat, Vec3};
+use indexmap::IndexMap;
+use uuid::Uuid;
+
+pub use ::arbitrary::{Result, Unstructured};
+pub use iokum_mcje_derive::Arbitrary;
+
+use crate::{
+    packet::{c2s, s2c, Direction, State},
+    types::{EntityData, Json, Nbt, TrailingBytes, User, UserProperty},
+    Decode, Encode,
+};
+
+/// Generates arbitrary values from unstructured data, which are encodable
+/// unless a length is exceeded.
+pub trait Arbitrary: Sized {
+    fn arbitrary(input: &mut Unstructured) -> Result<Self>;
+}
+
+macro_rules! impl_arbitrary {
+    ($($type:ty),*) => {
+        $(
+            impl Arbitrary for $type {
+                fn arbitrary(input: &mut Unstructured) -> Result<Self> {
+                    input.arbitrary()
+                }
+            }
+        )*
+    };
+}
+
+impl_arbitrary!(bool, u8, i8, u16, i16, u32, i32, u64, i64, f32, f64, String);
+
+impl Arbitrary for Uuid {
+    fn arbitrary(input: &mut Unstructured) -> Result<Self> {
+        Ok(Uuid::from_u128(input.arbitrary()?))
+    }
+}
+
+// interpreted as BlockPos
+impl Arbitrary for IVec3 {
+    fn arbitrary(input: &mut Unstructured) -> Result<Self> {
+        Ok(IVec3::new(
+            input.int_in_range(-0x2000000..=0x1FFFFFF)?,
+            input.int_in_range(-0x800..=0x7FF)?,
+            input.int_in_range(-0x2000000..=0x1FFFFFF)?,
+        ))
+    }
+}
+
+impl Arbitrary for Vec3 {
+    fn arbitrary(input: &mut Unstructured) -> Result<Self> {
+        Ok(Vec3::new(
+            input.arbitrary()?,
+            input.arbitrary()?,
+            input.arbitrary()?,
+        ))
+    }
+}
+
+impl Arbitrary for DVec3 {
+    fn arbitrary(input: &mut Unstructured) -> Result<Self> {
+        Ok(DVec3::new(
+            input.arbitrary()?,
+            input.arbitrary()?,
+            input.arbitrary()?,
+        ))
+    }
+}
+
+impl Arbitrary for Quat {
+    fn arbitrary(input: &mut Unstructured) -> Result<Self> {
+        Ok(Quat::from_xyzw(
+            input.arbitrary()?,
+            input.arbitrary()?,
+            input.arbitrary()?,
+            input.arbitrary()?,
+        ))
+    }
+}
+
+impl<T: Arbitrary> Arbitrary for Option<T> {
+    fn arbitrary(input: &mut Unstructured) -> Result<Self> {
+        Ok(if input.arbitrary()? {
+            Some(T::arbitrary(input)?)
+        } else {
+            None
+        })
+    }
+}
+
+impl<T: Arbitrary> Arbitrary for Box<T> {
+    fn arbitrary(input: &mut Unstructured) -> Result<Self> {
+        Ok(Box::new(T::arbitrary(input)?))
+    }
+}
+
+impl<T: Arbitrary + Clone> Arbitrary for Cow<'_, T> {
+    fn arbitrary(input: &mut Unstructured) -> Result<Self> {
+        Ok(Cow::Owned(T::arbitrary(input)?))
+    }
+}
+
+impl Arbitrary for Cow<'_, str> {
+    fn arbitrary(input: &mut Unstructured) -> Result<Self> {
+        Ok(Cow::Owned(input.arbitrary()?))
+    }
+}
+
+impl Arbitrary for Cow<'_, [u8]> {
+    fn arbitrary(input: &mut Unstructured) -> Result<Self> {
+        Ok(Cow::Owned(input.arbitrary()?))
+    }
+}
+
+// continues while there is data left, which keeps the values small
+impl<T: Arbitrary> Arbitrary for Vec<T> {
+    fn arbitrary(input: &mut Unstructured) -> Result<Self> {
+        let mut values = vec![];
+        while input.arbitrary()? {
+            values.push(T::arbitrary(input)?);
+        }
+        Ok(values)
+    }
+}
+
+impl<T: Arbitrary, const N: usize> Arbitrary for [T; N] {
+    fn arbitrary(input: &mut Unstructured) -> Result<Self> {
+        std::array::try_from_fn(|_| T::arbitrary(input))
+    }
+}
+
+macro_rules! impl_arbitrary_tuple {
+    ($($type:ident),*) => {
+        impl<$($type: Arbitrary),*> Arbitrary for ($($type,)*) {
+            #[allow(unused_variables)]
+            fn arbitrary(input: &mut Unstructured) -> Result<Self> {
+                Ok(($($type::arbitrary(input)?,)*))
+            }
+        }
+    };
+}
+
+impl_arbitrary_tuple!();
+impl_arbitrary_tuple!(A, B);
+impl_arbitrary_tuple!(A, B, C);
+
+impl<K: Arbitrary + Hash + Eq, V: Arbitrary> Arbitrary for IndexMap<K, V> {
+    fn arbitrary(input: &mut Unstructured) -> Result<Self> {
+        let mut map = IndexMap::new();
+        while input.arbitrary()? {
+            map.insert(K::arbitrary(input)?, V::arbitrary(input)?);
+        }
+        Ok(map)
+    }
+}
+
+impl<const N: usize> Arbitrary for TrailingBytes<'_, N> {
+    fn arbitrary(input: &mut Unstructured) -> Result<Self> {
+        let length = input.int_in_range(0..=N.min(input.len()))?;
+        Ok(Self(Cow::Owned(input.bytes(length)?.to_vec())))
+    }
+}
+
+/// Rejects values which are nested too deep to be deserialized again.
+impl<T: Arbitrary + serde::Serialize> Arbitrary for Json<T> {
+    fn arbitrary(input: &mut Unstructured) -> Result<Self> {
+        let value = T::arbitrary(input)?;
+        serde_json::to_string(&value)
+            .ok()
+            .and_then(|json| serde_json::from_str::<serde_json::Value>(&json).ok())
+            .ok_or(::arbitrary::Error::IncorrectFormat)?;
+        Ok(Self(value))
+    }
+}
+
+impl<T: Arbitrary> Arbitrary for Nbt<T> {
+    fn arbitrary(input: &mut Unstructured) -> Result<Self> {
+        Ok(Self(T::arbitrary(input)?))
+    }
+}
+
+/// Generates compounds, as any other value can't be the root of NBT.
+impl Arbitrary for serde_value::Value {
+    fn arbitrary(input: &mut Unstructured) -> Result<Self> {
+        fn value(input: &mut Unstructured, depth: usize) -> Result<serde_value::Value> {
+            use serde_value::Value;
+
+            Ok(match input.choose_index(if depth < 4 { 8 } else { 7 })? {
+                0 => Value::I8(input.arbitrary()?),
+                1 => Value::I16(input.arbitrary()?),
+                2 => Value::I32(input.arbitrary()?),
+                3 => Value::I64(input.arbitrary()?),
+                4 => Value::F32(input.arbitrary()?),
+                5 => Value::F64(input.arbitrary()?),
+                6 => Value::String(input.arbitrary()?),
+                _ => compound(input, depth + 1)?,
+            })
+        }
+
+        fn compound(input: &mut Unstructured, depth: usize) -> Result<serde_value::Value> {
+            let mut compound = BTreeMap::new();
+            while input.arbitrary()? {
+                compound.insert(
+                    serde_value::Value::String(input.arbitrary()?),
+                    value(input, depth)?,
+                );
+            }
+            Ok(serde_value::Value::Map(compound))
+        }
+
+        compound(input, 0)
+    }
+}
+
+impl Arbitrary for User {
+    fn arbitrary(input: &mut Unstructured) -> Result<Self> {
+        Ok(User {
+            id: Arbitrary::arbitrary(input)?,
+            name: Arbitrary::arbitrary(input)?,
+            properties: Arbitrary::arbitrary(input)?,
+        })
+    }
+}
+
+impl Arbitrary for UserProperty {
+    fn arbitrary(input: &mut Unstructured) -> Result<Self> {
+        Ok(UserProperty {
+            name: Arbitrary::arbitrary(input)?,
+            value: Arbitrary::arbitrary(input)?,
+            signature: Arbitrary::arbitrary(input)?,
+        })
+    }
+}
+
+/// The index 0xFF terminates entity data.
+impl Arbitrary for EntityData {
+    fn arbitrary(input: &mut Unstructured) -> Result<Self> {
+        let mut fields = IndexMap::new();
+        while input.arbitrary()? {
+            fields.insert(input.int_in_range(0..=0xFE)?, Arbitrary::arbitrary(input)?);
+        }
+        Ok(Self(fields))
+    }
+}
+
+/// Generates an arbitrary packet for the direction and state, and asserts
+/// that encoding it, decoding it and encoding it again results in the same
+/// bytes of the encoded length.
+///
+/// Returns an error if the input doesn't suffice to generate a packet.
+pub fn assert_round_trip(
+    direction: Direction,
+    state: State,
+    input: &mut Unstructured,
+) -> Result<()> {
+    macro_rules! assert_round_trip {
+        ($type:ty) => {{
+            let packet = <$type as Arbitrary>::arbitrary(input)?;
+            let mut data = vec![];
+            packet
+                .encode(&mut data)
+                .unwrap_or_else(|error| panic!("{error:?}: {packet:?}"));
+            assert_eq!(packet.encoded_len().unwrap(), data.len(), "{packet:?}");
+            let mut remaining = data.as_slice();
+            let decoded = <$type>::decode(&mut remaining)
+                .unwrap_or_else(|error| panic!("{error:?}: {packet:?}"));
+            assert!(remaining.is_empty(), "not fully decoded: {packet:?}");
+            let mut reencoded = vec![];
+            decoded.encode(&mut reencoded).unwrap();
+            assert_eq!(data, reencoded, "{packet:?}");
+        }};
+    }
+
+    match (direction, state) {
+        (Direction::C2s, State::Handshake) => assert_round_trip!(c2s::HandshakePacket),
+        (Direction::C2s, State::Status) => assert_round_trip!(c2s::StatusPacket),
+        (Direction::C2s, State::Login) => assert_round_trip!(c2s::LoginPacket),
+        (Direction::C2s, State::Game) => assert_round_trip!(c2s::GamePacket),
+        (Direction::S2c, State::Handshake) => {}
+        (Direction::S2c, State::Status) => assert_round_trip!(s2c::StatusPacket),
+        (Direction::S2c, State::Login) => assert_round_trip!(s2c::LoginPacket),
+        (Direction::S2c, State::Game) => assert_round_trip!(s2c::GamePacket),
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+
+    use crate::{
+        arbitrary::{assert_round_trip, Unstructured},
+        packet::{Direction, State},
+    };
+
+    #[test]
+    fn round_trip() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let mut data = vec![0; 4096];
+        for direction in [Direction::C2s, Direction::S2c] {
+            for state in [State::Handshake, State::Status, State::Login, State::Game] {
+                // there are no client bound handshake packets
+                if (direction, state) == (Direction::S2c, State::Handshake) {
+                    continue;
+                }
+                let mut round_trips = 0;
+                for _ in 0..2000 {
+                    rng.fill(data.as_mut_slice());
+                    if assert_round_trip(direction, state, &mut Unstructured::new(&data)).is_ok() {
+                        round_trips += 1;
+                    }
+                }
+                assert!(
+                    round_trips >= 1000,
+                    "{round_trips} round trips for {direction:?} {state:?}"
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,177 @@
+use std::{
+    fs::File,
+    io::{BufReader, BufWriter, ErrorKind, Read, Write},
+    path::Path,
+    time::{Instant, SystemTime, UNIX_EPOCH},
+};
+
+use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
+
+use crate::{
+    packet::{Direction, State},
+    types::VarI64,
+    Decode, Encode, Error, Result, PROTOCOL_VERSION,
+};
+
+const MAGIC: [u8; 4] = *b"MCJC";
+const FORMAT_VERSION: u8 = 1;
+
+/// Header of a capture, which is followed by records, each prefixed by its
+/// length as unsigned int.
+#[derive(Clone, Debug)]
+pub struct CaptureHeader {
+    pub protocol_version: i32,
+    /// Unix time the capture has been started at, in milliseconds.
+    pub start: i64,
+}
+
+/// Packet data after decryption and decompression, starting with the packet
+/// id.
+#[derive(Encode, Decode, Clone, Debug)]
+pub struct CaptureRecord {
+    /// Time since the start of the capture, in microseconds.
+    #[using(VarI64)]
+    pub time: i64,
+    pub direction: Direction,
+    pub state: State,
+    pub data: Vec<u8>,
+}
+
+pub struct CaptureWriter {
+    output: Box<dyn Write + Send>,
+    start: Instant,
+    buffer: Vec<u8>,
+}
+
+impl CaptureWriter {
+    /// Writes the header, with the protocol version implemented by this crate.
+    pub fn new(mut output: impl Write + Send + 'static) -> Result<Self> {
+        output.write_all(&MAGIC)?;
+        output.write_u8(FORMAT_VERSION)?;
+        output.write_i32::<BigEndian>(PROTOCOL_VERSION)?;
+        output.write_i64::<BigEndian>(
+            SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap()
+                .as_millis() as i64,
+        )?;
+        Ok(Self {
+            output: Box::new(output),
+            start: Instant::now(),
+            buffer: vec![],
+        })
+    }
+
+    pub fn create(path: impl AsRef<Path>) -> Result<Self> {
+        Self::new(BufWriter::new(File::create(path)?))
+    }
+
+    pub fn write(&mut self, direction: Direction, state: State, data: &[u8]) -> Result<()> {
+        self.buffer.clear();
+        VarI64(self.start.elapsed().as_micros() as i64).encode(&mut self.buffer)?;
+        direction.encode(&mut self.buffer)?;
+        state.encode(&mut self.buffer)?;
+        data.encode(&mut self.buffer)?;
+        self.output
+            .write_u32::<BigEndian>(self.buffer.len() as u32)?;
+        self.output.write_all(&self.buffer)?;
+        Ok(())
+    }
+
+    pub fn flush(&mut self) -> Result<()> {
+        self.output.flush()?;
+        Ok(())
+    }
+}
+
+pub struct CaptureReader<R> {
+    input: R,
+    header: CaptureHeader,
+    buffer: Vec<u8>,
+}
+
+impl<R: Read> CaptureReader<R> {
+    /// Reads and validates the header.
+    pub fn new(mut input: R) -> Result<Self> {
+        let mut magic = [0; 4];
+        input.read_exact(&mut magic)?;
+        if magic != MAGIC {
+            return Err(Error::Unexpected);
+        }
+        let format_version = input.read_u8()?;
+        if format_version != FORMAT_VERSION {
+            return Err(Error::UnknownVariant(format_version as i32));
+        }
+        let header = CaptureHeader {
+            protocol_version: input.read_i32::<BigEndian>()?,
+            start: input.read_i64::<BigEndian>()?,
+        };
+        Ok(Self {
+            input,
+            header,
+            buffer: vec![],
+        })
+    }
+
+    pub fn header(&self) -> &CaptureHeader {
+        &self.header
+    }
+}
+
+impl CaptureReader<BufReader<File>> {
+    pub fn open(path: impl AsRef<Path>) -> Result<Self> {
+        Self::new(BufReader::new(File::open(path)?))
+    }
+}
+
+impl<R: Read> Iterator for CaptureReader<R> {
+    type Item = Result<CaptureRecord>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let length = match self.input.read_u32::<BigEndian>() {
+            Ok(length) => length as usize,
+            // captures of aborted connections may end anywhere
+            Err(error) if error.kind() == ErrorKind::UnexpectedEof => return None,
+            Err(error) => return Some(Err(error.into())),
+        };
+        self.buffer.resize(length, 0);
+        if let Err(error) = self.input.read_exact(&mut self.buffer) {
+            return match error.kind() {
+                ErrorKind::UnexpectedEof => None,
+                _ => Some(Err(error.into())),
+            };
+        }
+        Some(CaptureRecord::decode(&mut self.buffer.as_slice()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        capture::{CaptureReader, CaptureWriter},
+        packet::{Direction, State},
+        PROTOCOL_VERSION,
+    };
+
+    #[test]
+    fn write_and_read() {
+        let path = std::env::temp_dir().join(format!("{}.mcjc", uuid::Uuid::new_v4()));
+        let mut writer = CaptureWriter::create(&path).unwrap();
+        writer
+            .write(Direction::C2s, State::Handshake, &[0x00, 0x01])
+            .unwrap();
+        writer.write(Direction::S2c, State::Game, &[0x23]).unwrap();
+        drop(writer);
+
+        let reader = CaptureReader::open(&path).unwrap();
+        assert_eq!(reader.header().protocol_version, PROTOCOL_VERSION);
+        let records = reader.collect::<Result<Vec<_>, _>>().unwrap();
+        std::fs::remove_file(path).unwrap();
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].direction, Direction::C2s);
+        assert_eq!(records[0].state, State::Handshake);
+        assert_eq!(records[0].data, [0x00, 0x01]);
+        assert_eq!(records[1].state, State::Game);
+        assert!(records[0].time <= records[1].time);
+    }
+}
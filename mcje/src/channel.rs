@@ -0,0 +1,438 @@
+use std::{collections::HashMap, io::Write};
+
+use crate::{
+    packet::{c2s, s2c},
+    types::TrailingBytes,
+    Decode, Encode, Error, Result,
+};
+
+/// Payload of a plugin message channel, which is sent using custom payload
+/// packets, or custom query packets while logging in.
+pub trait Payload: Encode + for<'a> Decode<'a> {
+    const IDENTIFIER: &'static str;
+}
+
+type Handler<C> = Box<dyn Fn(&mut C, &[u8]) -> Result<()> + Send + Sync>;
+type QueryHandler<C> = Box<dyn Fn(&mut C, &[u8]) -> Result<Option<Vec<u8>>> + Send + Sync>;
+
+/// Dispatches plugin messages to the handlers subscribed to their channel,
+/// handlers get access to a context of type `C`, e.g. the connection state.
+pub struct ChannelRegistry<C> {
+    handlers: HashMap<String, Handler<C>>,
+    query_handlers: HashMap<String, QueryHandler<C>>,
+}
+
+impl<C> Default for ChannelRegistry<C> {
+    fn default() -> Self {
+        Self {
+            handlers: Default::default(),
+            query_handlers: Default::default(),
+        }
+    }
+}
+
+impl<C> ChannelRegistry<C> {
+    /// Subscribes to custom payloads of the channel of `P`, replacing the
+    /// previous handler.
+    pub fn register<P: Payload>(
+        &mut self,
+        handler: impl Fn(&mut C, P) -> Result<()> + Send + Sync + 'static,
+    ) {
+        self.register_raw(P::IDENTIFIER, move |context, mut data| {
+            handler(context, P::decode(&mut data)?)
+        });
+    }
+
+    pub fn register_raw(
+        &mut self,
+        identifier: impl Into<String>,
+        handler: impl Fn(&mut C, &[u8]) -> Result<()> + Send + Sync + 'static,
+    ) {
+        self.handlers.insert(identifier.into(), Box::new(handler));
+    }
+
+    /// Subscribes to login queries of the channel of `P`, the returned value is
+    /// sent as response, or an unsuccessful response if none is returned.
+    pub fn register_query<P: Payload, R: Encode>(
+        &mut self,
+        handler: impl Fn(&mut C, P) -> Result<Option<R>> + Send + Sync + 'static,
+    ) {
+        self.query_handlers.insert(
+            P::IDENTIFIER.to_string(),
+            Box::new(move |context, mut data| {
+                let Some(response) = handler(context, P::decode(&mut data)?)? else {
+                    return Ok(None);
+                };
+                let mut data = vec![];
+                response.encode(&mut data)?;
+                Ok(Some(data))
+            }),
+        );
+    }
+
+    /// Identifiers of all subscribed channels, as announced using
+    /// [`Register`].
+    pub fn identifiers(&self) -> impl Iterator<Item = &str> {
+        self.handlers.keys().map(String::as_str)
+    }
+
+    /// Dispatches a custom payload, returns false if nobody is subscribed to
+    /// the channel.
+    pub fn handle(&self, context: &mut C, identifier: &str, data: &[u8]) -> Result<bool> {
+        match self.handlers.get(identifier) {
+            Some(handler) => {
+                handler(context, data)?;
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+
+    /// Dispatches custom payload packets in both directions, returns false if
+    /// the packet is not a custom payload or nobody is subscribed to the
+    /// channel.
+    pub fn handle_c2s(&self, context: &mut C, packet: &c2s::GamePacket) -> Result<bool> {
+        match packet {
+            c2s::GamePacket::CustomPayload { identifier, data } => {
+                self.handle(context, identifier, &data.0)
+            }
+            _ => Ok(false),
+        }
+    }
+
+    pub fn handle_s2c(&self, context: &mut C, packet: &s2c::GamePacket) -> Result<bool> {
+        match packet {
+            s2c::GamePacket::CustomPayload { identifier, data } => {
+                self.handle(context, identifier, &data.0)
+            }
+            _ => Ok(false),
+        }
+    }
+
+    /// Answers a login query, queries of channels nobody is subscribed to are
+    /// answered unsuccessfully like the vanilla client does.
+    pub fn handle_query(
+        &self,
+        context: &mut C,
+        transaction_id: i32,
+        identifier: &str,
+        data: &[u8],
+    ) -> Result<c2s::LoginPacket<'static>> {
+        let data = match self.query_handlers.get(identifier) {
+            Some(handler) => handler(context, data)?,
+            None => None,
+        };
+        Ok(c2s::LoginPacket::CustomQuery {
+            transaction_id,
+            data: data.map(|data| TrailingBytes(data.into())),
+        })
+    }
+}
+
+/// Tracks login queries sent by the server, as responses only carry the
+/// transaction id.
+#[derive(Default)]
+pub struct LoginQueries {
+    next_transaction_id: i32,
+    pending: HashMap<i32, String>,
+}
+
+impl LoginQueries {
+    pub fn request<P: Payload>(&mut self, payload: &P) -> Result<s2c::LoginPacket<'static>> {
+        let mut data = vec![];
+        payload.encode(&mut data)?;
+        Ok(self.request_raw(P::IDENTIFIER, data))
+    }
+
+    pub fn request_raw(
+        &mut self,
+        identifier: impl Into<String>,
+        data: Vec<u8>,
+    ) -> s2c::LoginPacket<'static> {
+        let transaction_id = self.next_transaction_id;
+        self.next_transaction_id = self.next_transaction_id.wrapping_add(1);
+        let identifier = identifier.into();
+        self.pending.insert(transaction_id, identifier.clone());
+        s2c::LoginPacket::CustomQuery {
+            transaction_id,
+            identifier,
+            data: TrailingBytes(data.into()),
+        }
+    }
+
+    /// Matches the response to its request and returns the channel identifier
+    /// of the request, fails for unknown transaction ids.
+    pub fn response(&mut self, transaction_id: i32) -> Result<String> {
+        self.pending
+            .remove(&transaction_id)
+            .ok_or(Error::UnknownTransaction(transaction_id))
+    }
+
+    /// Decodes the response to a request of the channel of `P`, none if the
+    /// client didn't understand the request. The request stays pending if it
+    /// was sent on another channel.
+    pub fn decode_response<'a, P: Payload, R: Decode<'a>>(
+        &mut self,
+        transaction_id: i32,
+        mut data: Option<&'a [u8]>,
+    ) -> Result<Option<R>> {
+        match self.pending.get(&transaction_id) {
+            Some(identifier) if identifier == P::IDENTIFIER => {
+                self.pending.remove(&transaction_id);
+            }
+            Some(_) => return Err(Error::Unexpected),
+            None => return Err(Error::UnknownTransaction(transaction_id)),
+        }
+        data.as_mut().map(R::decode).transpose()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.pending.is_empty()
+    }
+}
+
+/// Creates a custom payload packet for the given payload.
+pub fn c2s_custom_payload<P: Payload>(payload: &P) -> Result<c2s::GamePacket<'static>> {
+    let mut data = vec![];
+    payload.encode(&mut data)?;
+    Ok(c2s::GamePacket::CustomPayload {
+        identifier: P::IDENTIFIER.to_string(),
+        data: TrailingBytes(data.into()),
+    })
+}
+
+pub fn s2c_custom_payload<P: Payload>(payload: &P) -> Result<s2c::GamePacket<'static>> {
+    let mut data = vec![];
+    payload.encode(&mut data)?;
+    Ok(s2c::GamePacket::CustomPayload {
+        identifier: P::IDENTIFIER.to_string(),
+        data: TrailingBytes(data.into()),
+    })
+}
+
+/// Name of the client or server software, shown in the debug screen.
+#[derive(Encode, Decode, Clone, Debug, PartialEq)]
+pub struct Brand(pub String);
+
+impl Payload for Brand {
+    const IDENTIFIER: &'static str = "minecraft:brand";
+}
+
+/// Announces the channels the sender is subscribed to.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Register(pub Vec<String>);
+
+impl Payload for Register {
+    const IDENTIFIER: &'static str = "minecraft:register";
+}
+
+impl Encode for Register {
+    fn encode(&self, output: &mut impl Write) -> Result<()> {
+        output.write_all(self.0.join("\0").as_bytes())?;
+        Ok(())
+    }
+}
+
+impl Decode<'_> for Register {
+    fn decode(input: &mut &'_ [u8]) -> Result<Self> {
+        Ok(Self(decode_identifiers(input)?))
+    }
+}
+
+/// Announces the channels the sender is no longer subscribed to.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Unregister(pub Vec<String>);
+
+impl Payload for Unregister {
+    const IDENTIFIER: &'static str = "minecraft:unregister";
+}
+
+impl Encode for Unregister {
+    fn encode(&self, output: &mut impl Write) -> Result<()> {
+        output.write_all(self.0.join("\0").as_bytes())?;
+        Ok(())
+    }
+}
+
+impl Decode<'_> for Unregister {
+    fn decode(input: &mut &'_ [u8]) -> Result<Self> {
+        Ok(Self(decode_identifiers(input)?))
+    }
+}
+
+fn decode_identifiers(input: &mut &[u8]) -> Result<Vec<String>> {
+    let identifiers = std::str::from_utf8(input)?
+        .split('\0')
+        .filter(|identifier| !identifier.is_empty())
+        .map(str::to_string)
+        .collect();
+    *input = &input[input.len()..];
+    Ok(identifiers)
+}
+
+/// Message of the BungeeCord plugin messaging channel, which consists of the
+/// subchannel followed by subchannel-specific data.
+#[derive(Clone, Debug, PartialEq)]
+pub struct BungeeCord {
+    pub subchannel: String,
+    pub data: Vec<u8>,
+}
+
+impl BungeeCord {
+    /// Creates a message only consisting of string arguments, e.g. `Connect`
+    /// with the target server.
+    pub fn new(subchannel: impl Into<String>, arguments: &[&str]) -> Result<Self> {
+        let mut data = vec![];
+        for argument in arguments {
+            JavaUtf(argument.to_string()).encode(&mut data)?;
+        }
+        Ok(Self {
+            subchannel: subchannel.into(),
+            data,
+        })
+    }
+
+    /// Decodes the data as string arguments.
+    pub fn arguments(&self) -> Result<Vec<String>> {
+        let mut input = self.data.as_slice();
+        let mut arguments = vec![];
+        while !input.is_empty() {
+            arguments.push(JavaUtf::decode(&mut input)?.0);
+        }
+        Ok(arguments)
+    }
+}
+
+impl Payload for BungeeCord {
+    const IDENTIFIER: &'static str = "bungeecord:main";
+}
+
+impl Encode for BungeeCord {
+    fn encode(&self, output: &mut impl Write) -> Result<()> {
+        JavaUtf(self.subchannel.clone()).encode(output)?;
+        output.write_all(&self.data)?;
+        Ok(())
+    }
+}
+
+impl Decode<'_> for BungeeCord {
+    fn decode(input: &mut &'_ [u8]) -> Result<Self> {
+        let subchannel = JavaUtf::decode(input)?.0;
+        let data = input.to_vec();
+        *input = &input[input.len()..];
+        Ok(Self { subchannel, data })
+    }
+}
+
+/// String as written by Java's `DataOutput.writeUTF`, prefixed by its length as
+/// unsigned short. Modified UTF-8 is treated as UTF-8, which only differs for
+/// NUL and supplementary characters.
+#[derive(Clone, Debug, PartialEq)]
+pub struct JavaUtf(pub String);
+
+impl Encode for JavaUtf {
+    fn encode(&self, output: &mut impl Write) -> Result<()> {
+        let bytes = self.0.as_bytes();
+        if bytes.len() > u16::MAX as usize {
+            return Err(Error::InvalidLength);
+        }
+        (bytes.len() as u16).encode(output)?;
+        output.write_all(bytes)?;
+        Ok(())
+    }
+}
+
+impl Decode<'_> for JavaUtf {
+    fn decode(input: &mut &'_ [u8]) -> Result<Self> {
+        let length = u16::decode(input)? as usize;
+        if input.len() < length {
+            return Err(Error::UnexpectedEnd);
+        }
+        let (bytes, input_) = input.split_at(length);
+        *input = input_;
+        Ok(Self(std::str::from_utf8(bytes)?.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        channel::{BungeeCord, ChannelRegistry, LoginQueries, Payload, Register},
+        packet::{c2s, s2c},
+        Decode, Encode, Error,
+    };
+
+    #[test]
+    fn register_and_query() {
+        let mut registry = ChannelRegistry::<Vec<String>>::default();
+        registry.register(|context, payload: Register| {
+            context.extend(payload.0);
+            Ok(())
+        });
+        registry.register_query(|_, payload: BungeeCord| Ok(Some(payload)));
+
+        let mut data = vec![];
+        Register(vec!["a:b".to_string(), "c:d".to_string()])
+            .encode(&mut data)
+            .unwrap();
+        let mut context = vec![];
+        assert!(registry
+            .handle(&mut context, Register::IDENTIFIER, &data)
+            .unwrap());
+        assert!(!registry.handle(&mut context, "e:f", &data).unwrap());
+        assert_eq!(context, ["a:b", "c:d"]);
+
+        let mut queries = LoginQueries::default();
+        let message = BungeeCord::new("Connect", &["lobby"]).unwrap();
+        let s2c::LoginPacket::CustomQuery {
+            transaction_id,
+            identifier,
+            data,
+        } = queries.request(&message).unwrap()
+        else {
+            unreachable!()
+        };
+        let c2s::LoginPacket::CustomQuery {
+            transaction_id,
+            data,
+        } = registry
+            .handle_query(&mut context, transaction_id, &identifier, &data.0)
+            .unwrap()
+        else {
+            unreachable!()
+        };
+        let response = queries
+            .decode_response::<BungeeCord, BungeeCord>(
+                transaction_id,
+                data.as_ref().map(|data| data.0.as_ref()),
+            )
+            .unwrap()
+            .unwrap();
+        assert_eq!(response.arguments().unwrap(), ["lobby"]);
+        assert!(queries.is_empty());
+        assert!(matches!(
+            queries.response(transaction_id),
+            Err(Error::UnknownTransaction(_))
+        ));
+        assert!(BungeeCord::decode(&mut [0, 5, b'a'].as_slice()).is_err());
+    }
+
+    #[test]
+    fn mismatched_response() {
+        let mut queries = LoginQueries::default();
+        let s2c::LoginPacket::CustomQuery { transaction_id, .. } =
+            queries.request_raw("a:b", vec![])
+        else {
+            unreachable!()
+        };
+
+        // answering with the wrong channel keeps the request pending
+        assert!(matches!(
+            queries.decode_response::<BungeeCord, BungeeCord>(transaction_id, None),
+            Err(Error::Unexpected)
+        ));
+        assert!(!queries.is_empty());
+        assert_eq!(queries.response(transaction_id).unwrap(), "a:b");
+        assert!(queries.is_empty());
+    }
+}
@@ -0,0 +1,195 @@
+//! Compression backends, flate2 is used by default, which can use zlib-ng
+//! with the `zlib-ng` feature, and libdeflate with the `libdeflate` feature.
+
+use flate2::Compression;
+
+use crate::{Error, Result};
+
+/// Compression state, which is reused for all packets of a connection.
+pub struct Compressor {
+    #[cfg(not(feature = "libdeflate"))]
+    compress: flate2::Compress,
+    #[cfg(feature = "libdeflate")]
+    compressor: libdeflater::Compressor,
+}
+
+/// Decompression state, which is reused for all packets of a connection.
+pub struct Decompressor {
+    #[cfg(not(feature = "libdeflate"))]
+    decompress: flate2::Decompress,
+    #[cfg(feature = "libdeflate")]
+    decompressor: libdeflater::Decompressor,
+}
+
+#[cfg(not(feature = "libdeflate"))]
+impl Compressor {
+    pub fn new(compression: Compression) -> Self {
+        Self {
+            compress: flate2::Compress::new(compression, true),
+        }
+    }
+
+    /// Compresses the data, and appends it to the output.
+    pub fn compress(&mut self, input: &[u8], output: &mut Vec<u8>) -> Result<()> {
+        self.compress.reset();
+        loop {
+            output.reserve(input.len() / 2 + 64);
+            let consumed = self.compress.total_in() as usize;
+            match self
+                .compress
+                .compress_vec(&input[consumed..], output, flate2::FlushCompress::Finish)
+                .map_err(std::io::Error::from)?
+            {
+                flate2::Status::StreamEnd => return Ok(()),
+                flate2::Status::Ok | flate2::Status::BufError => {}
+            }
+        }
+    }
+}
+
+#[cfg(not(feature = "libdeflate"))]
+impl Decompressor {
+    pub fn new() -> Self {
+        Self {
+            decompress: flate2::Decompress::new(true),
+        }
+    }
+
+    /// Decompresses the data, which has to decompress to exactly `length`
+    /// bytes, and appends it to the output.
+    pub fn decompress(&mut self, input: &[u8], length: usize, output: &mut Vec<u8>) -> Result<()> {
+        let offset = output.len();
+        self.decompress.reset(true);
+        // one byte more detects data which decompresses to more than announced
+        output.reserve(length + 1);
+        match self
+            .decompress
+            .decompress_vec(input, output, flate2::FlushDecompress::Finish)
+        {
+            Ok(flate2::Status::StreamEnd) if self.decompress.total_out() as usize == length => {
+                Ok(())
+            }
+            Ok(_) => {
+                output.truncate(offset);
+                Err(Error::InvalidLength)
+            }
+            Err(error) => {
+                output.truncate(offset);
+                Err(std::io::Error::from(error).into())
+            }
+        }
+    }
+}
+
+#[cfg(feature = "libdeflate")]
+impl Compressor {
+    pub fn new(compression: Compression) -> Self {
+        let level =
+            libdeflater::CompressionLvl::new(compression.level() as i32).unwrap_or_default();
+        Self {
+            compressor: libdeflater::Compressor::new(level),
+        }
+    }
+
+    /// Compresses the data, and appends it to the output.
+    pub fn compress(&mut self, input: &[u8], output: &mut Vec<u8>) -> Result<()> {
+        let offset = output.len();
+        output.resize(offset + self.compressor.zlib_compress_bound(input.len()), 0);
+        let length = self
+            .compressor
+            .zlib_compress(input, &mut output[offset..])
+            .map_err(|_| Error::InvalidLength)?;
+        output.truncate(offset + length);
+        Ok(())
+    }
+}
+
+#[cfg(feature = "libdeflate")]
+impl Decompressor {
+    pub fn new() -> Self {
+        Self {
+            decompressor: libdeflater::Decompressor::new(),
+        }
+    }
+
+    /// Decompresses the data, which has to decompress to exactly `length`
+    /// bytes, and appends it to the output.
+    pub fn decompress(&mut self, input: &[u8], length: usize, output: &mut Vec<u8>) -> Result<()> {
+        let offset = output.len();
+        output.resize(offset + length, 0);
+        match self
+            .decompressor
+            .zlib_decompress(input, &mut output[offset..])
+        {
+            Ok(decompressed_length) if decompressed_length == length => Ok(()),
+            Ok(_) | Err(libdeflater::DecompressionError::InsufficientSpace) => {
+                output.truncate(offset);
+                Err(Error::InvalidLength)
+            }
+            Err(error) => {
+                output.truncate(offset);
+                Err(std::io::Error::new(std::io::ErrorKind::InvalidData, error).into())
+            }
+        }
+    }
+}
+
+impl Default for Decompressor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use flate2::Compression;
+
+    use crate::{
+        codec::compression::{Compressor, Decompressor},
+        Error,
+    };
+
+    #[test]
+    fn decompress() {
+        let data = [0x01; 300];
+        let mut compressed = vec![];
+        Compressor::new(Compression::default())
+            .compress(&data, &mut compressed)
+            .unwrap();
+
+        // the decompressed data is appended
+        let mut decompressor = Decompressor::new();
+        let mut output = vec![0x02];
+        decompressor
+            .decompress(&compressed, data.len(), &mut output)
+            .unwrap();
+        assert_eq!(output[0], 0x02);
+        assert_eq!(output[1..], data);
+
+        // and nothing is left behind on errors
+        let mut output = vec![0x02];
+        for length in [data.len() - 1, data.len() + 1] {
+            assert!(matches!(
+                decompressor.decompress(&compressed, length, &mut output),
+                Err(Error::InvalidLength)
+            ));
+            assert_eq!(output, [0x02]);
+        }
+        let mut corrupted = compressed.clone();
+        corrupted[4] ^= 0xFF;
+        assert!(decompressor
+            .decompress(&corrupted, data.len(), &mut output)
+            .is_err());
+        assert_eq!(output, [0x02]);
+        assert!(decompressor
+            .decompress(&compressed[..compressed.len() / 2], data.len(), &mut output)
+            .is_err());
+        assert_eq!(output, [0x02]);
+
+        // the state can be reused afterwards
+        decompressor
+            .decompress(&compressed, data.len(), &mut output)
+            .unwrap();
+        assert_eq!(output[1..], data);
+    }
+}
@@ -0,0 +1,319 @@
+use std::io::Read;
+
+use aes::{
+    cipher::{BlockDecryptMut, BlockEncryptMut, KeyIvInit},
+    Aes128,
+};
+use bytes::{Buf, BufMut, Bytes, BytesMut};
+use cfb8::{Decryptor, Encryptor};
+use flate2::read::ZlibDecoder;
+pub use flate2::Compression;
+use tokio_util::codec::{Decoder, Encoder};
+
+pub use compression::{Compressor, Decompressor};
+
+use crate::{
+    types::{decode_length, VarI21, VarI32},
+    Decode, Encode, Error, Result,
+};
+
+mod compression;
+
+/// Maximum length of packets, after decompression.
+pub const MAX_PACKET_LENGTH: usize = 8 * 1024 * 1024;
+
+/// Maximum length of frames, as their length is a 3 byte VarInt.
+pub const MAX_FRAME_LENGTH: usize = (1 << 21) - 1;
+
+#[derive(Default)]
+pub struct Codec {
+    encryptor: Option<Encryptor<Aes128>>,
+    decryptor: Option<Decryptor<Aes128>>,
+    decrypted_bytes: usize,
+
+    compression: Compression,
+    compression_threshold: Option<u16>,
+    // created on first use, and reused for all packets
+    compressor: Option<Compressor>,
+    decompressor: Option<Decompressor>,
+    buffer: Vec<u8>,
+}
+
+impl Codec {
+    pub fn enable_encryption(&mut self, key: &[u8]) {
+        self.encryptor = Some(Encryptor::new_from_slices(key, key).unwrap());
+        self.decryptor = Some(Decryptor::new_from_slices(key, key).unwrap());
+        self.decrypted_bytes = 0;
+    }
+
+    pub fn enable_compression(&mut self, compression: Compression, compression_threshold: u16) {
+        self.compression = compression;
+        self.compression_threshold = Some(compression_threshold);
+        self.compressor = None;
+    }
+
+    /// Appends the packet data of the frame to the output, decompressing it if
+    /// needed.
+    pub fn decompress(&mut self, frame: &Frame, output: &mut Vec<u8>) -> Result<()> {
+        if !frame.is_compressed() {
+            output.extend_from_slice(&frame.data);
+            return Ok(());
+        }
+        self.decompressor
+            .get_or_insert_with(Decompressor::new)
+            .decompress(&frame.data, frame.decompressed_data_length, output)
+    }
+
+    fn encrypt(&mut self, dst: &mut BytesMut, offset: usize) {
+        if let Some(encryptor) = &mut self.encryptor {
+            encryptor.encrypt_blocks_mut(unsafe { std::mem::transmute(&mut dst[offset..]) });
+        }
+    }
+}
+
+/// A received frame, of which only the packet id has been decoded. The data is
+/// kept compressed, so that it can be sent again without recompressing it.
+#[derive(Clone, Debug)]
+pub struct Frame {
+    id: i32,
+    compression_threshold: Option<u16>,
+    decompressed_data_length: usize,
+    data: Bytes,
+}
+
+impl Frame {
+    pub fn id(&self) -> i32 {
+        self.id
+    }
+
+    pub fn is_compressed(&self) -> bool {
+        self.decompressed_data_length != 0
+    }
+
+    /// Returns the packet data, decompressing it if needed. Prefer
+    /// [`Codec::decompress`], which reuses the decompression state.
+    pub fn decompress(&self) -> Result<Vec<u8>> {
+        if !self.is_compressed() {
+            return Ok(self.data.to_vec());
+        }
+        let mut data = vec![];
+        Decompressor::new().decompress(&self.data, self.decompressed_data_length, &mut data)?;
+        Ok(data)
+    }
+}
+
+impl Encoder<&[u8]> for Codec {
+    type Error = Error;
+
+    fn encode(&mut self, item: &[u8], dst: &mut BytesMut) -> Result<()> {
+        if item.len() > MAX_PACKET_LENGTH {
+            return Err(Error::InvalidLength);
+        }
+
+        let offset = dst.len();
+        match self.compression_threshold {
+            Some(compression_threshold) if item.len() > compression_threshold as usize => {
+                // compressed into the scratch buffer, as the length of the compressed data
+                // has to be written first
+                self.buffer.clear();
+                self.compressor
+                    .get_or_insert_with(|| Compressor::new(self.compression))
+                    .compress(item, &mut self.buffer)?;
+
+                let data_length = VarI32(item.len() as i32);
+                let frame_length = data_length.len() + self.buffer.len();
+                if frame_length > MAX_FRAME_LENGTH {
+                    return Err(Error::InvalidLength);
+                }
+                let mut writer = dst.writer();
+                VarI32(frame_length as i32).encode(&mut writer)?;
+                data_length.encode(&mut writer)?;
+                dst.extend_from_slice(&self.buffer);
+            }
+            Some(_) => {
+                // a data length of zero marks uncompressed packets
+                let frame_length = 1 + item.len();
+                if frame_length > MAX_FRAME_LENGTH {
+                    return Err(Error::InvalidLength);
+                }
+                let mut writer = dst.writer();
+                VarI32(frame_length as i32).encode(&mut writer)?;
+                VarI32(0).encode(&mut writer)?;
+                dst.extend_from_slice(item);
+            }
+            None => {
+                if item.len() > MAX_FRAME_LENGTH {
+                    return Err(Error::InvalidLength);
+                }
+                VarI32(item.len() as i32).encode(&mut dst.writer())?;
+                dst.extend_from_slice(item);
+            }
+        }
+
+        // Encrypt written bytes
+        self.encrypt(dst, offset);
+
+        Ok(())
+    }
+}
+
+/// Re-emits the frame as received if both sides use the same compression
+/// threshold, otherwise the data is decompressed and encoded again.
+impl Encoder<&Frame> for Codec {
+    type Error = Error;
+
+    fn encode(&mut self, item: &Frame, dst: &mut BytesMut) -> Result<()> {
+        if item.compression_threshold != self.compression_threshold {
+            let mut data = vec![];
+            self.decompress(item, &mut data)?;
+            return self.encode(data.as_slice(), dst);
+        }
+
+        let offset = dst.len();
+        let mut writer = dst.writer();
+        if self.compression_threshold.is_some() {
+            let decompressed_data_length = VarI32(item.decompressed_data_length as i32);
+            VarI32((decompressed_data_length.len() + item.data.len()) as i32)
+                .encode(&mut writer)?;
+            decompressed_data_length.encode(&mut writer)?;
+        } else {
+            VarI32(item.data.len() as i32).encode(&mut writer)?;
+        }
+        dst.extend_from_slice(&item.data);
+
+        // Encrypt written bytes
+        self.encrypt(dst, offset);
+
+        Ok(())
+    }
+}
+
+impl Decoder for Codec {
+    type Item = Frame;
+    type Error = Error;
+
+    fn decode(&mut self, src: &mut BytesMut) -> Result<Option<Self::Item>> {
+        // Decrypt all not yet decrypted bytes
+        if let Some(decryptor) = &mut self.decryptor {
+            decryptor.decrypt_blocks_mut(unsafe {
+                std::mem::transmute(&mut src[self.decrypted_bytes..])
+            });
+            self.decrypted_bytes = src.len();
+        }
+
+        let mut data = &src[..];
+        match VarI21::decode(&mut data) {
+            Ok(data_length) => {
+                // The length is not necessarily encoded in its shortest form by other
+                // implementations.
+                let data_length_length = src.len() - data.len();
+                if data.len() >= data_length.0 as usize {
+                    data = &data[..data_length.0 as usize];
+
+                    let decompressed_data_length = if self.compression_threshold.is_some() {
+                        decode_length(&mut data, MAX_PACKET_LENGTH)?
+                    } else {
+                        0
+                    };
+                    let id = if decompressed_data_length != 0 {
+                        // only the first bytes are decompressed, which contain the id
+                        let mut head = Vec::with_capacity(5);
+                        ZlibDecoder::new(data).take(5).read_to_end(&mut head)?;
+                        VarI32::decode(&mut head.as_slice())?.0
+                    } else {
+                        VarI32::decode(&mut &data[..])?.0
+                    };
+
+                    // Advance, and correct decrypted bytes
+                    let length = data.len();
+                    src.advance(data_length_length + data_length.0 as usize - length);
+                    let data = src.split_to(length).freeze();
+                    if self.decryptor.is_some() {
+                        self.decrypted_bytes = src.len()
+                    }
+
+                    Ok(Some(Frame {
+                        id,
+                        compression_threshold: self.compression_threshold,
+                        decompressed_data_length,
+                        data,
+                    }))
+                } else {
+                    Ok(None)
+                }
+            }
+            Err(error) => {
+                if data.len() >= 3 {
+                    Err(error)
+                } else {
+                    Ok(None)
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::BytesMut;
+    use tokio_util::codec::{Decoder, Encoder};
+
+    use crate::{
+        codec::{Codec, Compression, MAX_FRAME_LENGTH},
+        packet::{s2c, PacketName},
+        types::{Component, Json},
+        Encode,
+    };
+
+    #[test]
+    fn pass_through() {
+        let packet = s2c::GamePacket::SystemChat {
+            content: Json(Component::Literal("a".repeat(512))),
+            overlay: false,
+        };
+        let mut data = vec![];
+        packet.encode(&mut data).unwrap();
+
+        let mut codec = Codec::default();
+        codec.enable_compression(Compression::default(), 256);
+        let mut sent = BytesMut::new();
+        codec.encode(data.as_slice(), &mut sent).unwrap();
+        let frame = codec.decode(&mut sent.clone()).unwrap().unwrap();
+        assert!(frame.is_compressed());
+        assert_eq!(frame.id(), packet.id());
+        assert_eq!(frame.decompress().unwrap(), data);
+
+        // the frame is re-emitted as is with the same threshold
+        let mut resent = BytesMut::new();
+        codec.encode(&frame, &mut resent).unwrap();
+        assert_eq!(resent, sent);
+
+        // and encoded again otherwise
+        let mut codec = Codec::default();
+        let mut resent = BytesMut::new();
+        codec.encode(&frame, &mut resent).unwrap();
+        let frame = codec.decode(&mut resent).unwrap().unwrap();
+        assert!(!frame.is_compressed());
+        assert_eq!(frame.decompress().unwrap(), data);
+    }
+
+    #[test]
+    fn frame_length() {
+        // lengths are written in their shortest form
+        let mut codec = Codec::default();
+        let mut sent = BytesMut::new();
+        codec.encode([0x01, 0x02].as_slice(), &mut sent).unwrap();
+        assert_eq!(sent, [0x02, 0x01, 0x02].as_slice());
+
+        codec.enable_compression(Compression::default(), 256);
+        let mut sent = BytesMut::new();
+        codec.encode([0x01; 200].as_slice(), &mut sent).unwrap();
+        assert_eq!(sent[..3], [0xC9, 0x01, 0x00]);
+        assert_eq!(sent.len(), 3 + 200);
+
+        // frames which don't fit into a 3 byte VarInt are rejected
+        let mut codec = Codec::default();
+        let data = vec![0; MAX_FRAME_LENGTH + 1];
+        assert!(codec.encode(data.as_slice(), &mut BytesMut::new()).is_err());
+    }
+}
@@ -0,0 +1,166 @@
+use std::{net::SocketAddr, time::Duration};
+
+use futures::{SinkExt, StreamExt};
+use tokio::{
+    io::{AsyncRead, AsyncWrite},
+    net::{TcpListener, TcpStream},
+};
+use tokio_util::codec::Framed;
+
+use crate::{
+    capture::CaptureWriter,
+    codec::{Codec, Frame},
+    packet::{Direction, State},
+    Decode, Encode, Error, Result,
+};
+
+/// Delay before accepting again after accepting failed.
+const ACCEPT_BACKOFF: Duration = Duration::from_millis(100);
+
+/// Accepts the next connection and disables Nagle's algorithm. Errors don't
+/// stop accepting: connections which fail before being set up are skipped, and
+/// failing to accept, e.g. because all file descriptors are in use, is retried
+/// after a short delay.
+///
+/// This is cancel safe, and therefore can be used in `tokio::select!`.
+pub async fn accept(listener: &TcpListener) -> (TcpStream, SocketAddr) {
+    loop {
+        match listener.accept().await {
+            Ok((socket, address)) => {
+                // fails if the peer already reset the connection
+                if socket.set_nodelay(true).is_ok() {
+                    return (socket, address);
+                }
+            }
+            Err(_) => tokio::time::sleep(ACCEPT_BACKOFF).await,
+        }
+    }
+}
+
+/// Packet-level wrapper around a framed stream, used by both sides of a
+/// connection.
+pub struct Connection<S = TcpStream> {
+    framed: Framed<S, Codec>,
+    buffer: Vec<u8>,
+    capture: Option<Capture>,
+}
+
+struct Capture {
+    writer: CaptureWriter,
+    received: Direction,
+    state: State,
+}
+
+impl<S: AsyncRead + AsyncWrite + Unpin> Connection<S> {
+    pub fn new(stream: S) -> Self {
+        Self {
+            framed: Framed::new(stream, Codec::default()),
+            buffer: Vec::new(),
+            capture: None,
+        }
+    }
+
+    /// Records all packets from now on, `received` is the direction of the
+    /// received packets. The capture is stopped if writing fails.
+    pub fn capture(&mut self, writer: CaptureWriter, received: Direction, state: State) {
+        self.capture = Some(Capture {
+            writer,
+            received,
+            state,
+        });
+    }
+
+    /// Updates the state recorded in the capture, if any.
+    pub fn set_state(&mut self, state: State) {
+        if let Some(capture) = &mut self.capture {
+            capture.state = state;
+        }
+    }
+
+    pub fn get_ref(&self) -> &S {
+        self.framed.get_ref()
+    }
+
+    pub fn codec(&self) -> &Codec {
+        self.framed.codec()
+    }
+
+    pub fn codec_mut(&mut self) -> &mut Codec {
+        self.framed.codec_mut()
+    }
+
+    /// Encodes and sends a packet.
+    pub async fn send(&mut self, packet: &impl Encode) -> Result<()> {
+        self.buffer.clear();
+        packet.encode(&mut self.buffer)?;
+        record(&mut self.capture, true, &self.buffer);
+        self.framed.send(self.buffer.as_slice()).await
+    }
+
+    /// Sends already encoded packet data.
+    pub async fn send_raw(&mut self, data: &[u8]) -> Result<()> {
+        record(&mut self.capture, true, data);
+        self.framed.send(data).await
+    }
+
+    /// Sends a received frame, without recompressing it if possible.
+    pub async fn send_frame(&mut self, frame: &Frame) -> Result<()> {
+        if self.capture.is_some() {
+            let mut data = vec![];
+            self.framed.codec_mut().decompress(frame, &mut data)?;
+            record(&mut self.capture, true, &data);
+        }
+        self.framed.send(frame).await
+    }
+
+    /// Receives the next packet, fails with [`Error::UnexpectedEnd`] if the
+    /// stream has been closed.
+    pub async fn receive(&mut self) -> Result<Packet> {
+        let frame = self.framed.next().await.ok_or(Error::UnexpectedEnd)??;
+        let mut data = vec![];
+        self.framed.codec_mut().decompress(&frame, &mut data)?;
+        record(&mut self.capture, false, &data);
+        Ok(Packet(data))
+    }
+
+    /// Receives the next frame without decompressing it, fails with
+    /// [`Error::UnexpectedEnd`] if the stream has been closed.
+    pub async fn receive_frame(&mut self) -> Result<Frame> {
+        let frame = self.framed.next().await.ok_or(Error::UnexpectedEnd)??;
+        if self.capture.is_some() {
+            let mut data = vec![];
+            self.framed.codec_mut().decompress(&frame, &mut data)?;
+            record(&mut self.capture, false, &data);
+        }
+        Ok(frame)
+    }
+
+    pub fn into_inner(self) -> S {
+        self.framed.into_inner()
+    }
+}
+
+fn record(capture: &mut Option<Capture>, sent: bool, data: &[u8]) {
+    if let Some(Capture {
+        writer,
+        received,
+        state,
+    }) = capture
+    {
+        let direction = if sent { received.opposite() } else { *received };
+        if writer.write(direction, *state, data).is_err() {
+            *capture = None;
+        }
+    }
+}
+
+/// Received packet data, which is decoded on demand as the expected packet
+/// type depends on the connection state.
+#[derive(Clone, Debug)]
+pub struct Packet(pub Vec<u8>);
+
+impl Packet {
+    pub fn decode<'a, T: Decode<'a>>(&'a self) -> Result<T> {
+        T::decode(&mut self.0.as_slice())
+    }
+}
@@ -0,0 +1,225 @@
+use std::{io::Write, net::IpAddr};
+
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use uuid::Uuid;
+
+use crate::{
+    packet::c2s,
+    types::{TrailingBytes, User, UserProperty, VarI32},
+    Decode, Encode, Error, Result,
+};
+
+/// Channel of the login query used by Velocity's modern forwarding.
+pub const VELOCITY_CHANNEL: &str = "velocity:player_info";
+
+/// Highest supported version of Velocity's modern forwarding, which doesn't
+/// include chat session data.
+pub const VELOCITY_VERSION: u8 = 1;
+
+/// How the proxy forwards the real address and profile of a player to the
+/// backend server, which has to be configured the same way on both sides.
+#[derive(Clone, Debug, Default)]
+pub enum Forwarding {
+    /// The backend server sees the proxy's address and an offline profile.
+    #[default]
+    None,
+    /// BungeeCord's legacy forwarding, which appends the player info to the
+    /// host name in the handshake, and is therefore not secured.
+    BungeeCord,
+    /// Velocity's modern forwarding, which sends the player info in response
+    /// to a login query, signed with a secret shared between proxy and backend
+    /// server.
+    Velocity { secret: Vec<u8> },
+}
+
+/// Real address and profile of a player connected through a proxy.
+#[derive(Clone, Debug)]
+pub struct ForwardedPlayer {
+    pub address: IpAddr,
+    pub user: User,
+}
+
+impl Forwarding {
+    /// Client side, returns the host name to send in the handshake.
+    pub fn host_name(&self, host_name: &str, player: &ForwardedPlayer) -> Result<String> {
+        match self {
+            Forwarding::BungeeCord => player.to_bungeecord(host_name),
+            _ => Ok(host_name.to_string()),
+        }
+    }
+
+    /// Client side, returns the response to a login query, none if the query
+    /// is not related to forwarding.
+    pub fn respond(
+        &self,
+        player: &ForwardedPlayer,
+        transaction_id: i32,
+        identifier: &str,
+        data: &[u8],
+    ) -> Result<Option<c2s::LoginPacket<'static>>> {
+        let Forwarding::Velocity { secret } = self else {
+            return Ok(None);
+        };
+        if identifier != VELOCITY_CHANNEL {
+            return Ok(None);
+        }
+        // the server requests its highest supported version, which is optional
+        let version = data
+            .first()
+            .map_or(VELOCITY_VERSION, |&version| version.min(VELOCITY_VERSION));
+        Ok(Some(c2s::LoginPacket::CustomQuery {
+            transaction_id,
+            data: Some(TrailingBytes(player.to_velocity(secret, version)?.into())),
+        }))
+    }
+}
+
+impl ForwardedPlayer {
+    /// Appends the player info to the host name, separated by NUL.
+    pub fn to_bungeecord(&self, host_name: &str) -> Result<String> {
+        let mut host_name = format!("{host_name}\0{}\0{}", self.address, self.user.id.simple());
+        if !self.user.properties.is_empty() {
+            host_name.push('\0');
+            host_name.push_str(&serde_json::to_string(&self.user.properties)?);
+        }
+        Ok(host_name)
+    }
+
+    /// Splits the player info from the host name, the name of the player is
+    /// not forwarded, and has to be taken from the login.
+    pub fn from_bungeecord(host_name: &str, name: String) -> Result<(String, Self)> {
+        let mut fields = host_name.split('\0');
+        let host_name = fields.next().unwrap_or_default().to_string();
+        let address = fields
+            .next()
+            .and_then(|address| address.parse().ok())
+            .ok_or(Error::Unexpected)?;
+        let id = fields
+            .next()
+            .and_then(|id| Uuid::try_parse(id).ok())
+            .ok_or(Error::Unexpected)?;
+        let properties = match fields.next() {
+            Some(properties) => serde_json::from_str::<Vec<UserProperty>>(properties)?,
+            None => vec![],
+        };
+        Ok((
+            host_name,
+            Self {
+                address,
+                user: User {
+                    id,
+                    name,
+                    properties,
+                },
+            },
+        ))
+    }
+
+    /// Encodes the player info and prepends the signature.
+    pub fn to_velocity(&self, secret: &[u8], version: u8) -> Result<Vec<u8>> {
+        let mut data = vec![];
+        VarI32(version as i32).encode(&mut data)?;
+        self.address.to_string().encode(&mut data)?;
+        self.user.encode(&mut data)?;
+
+        let mut mac = Hmac::<Sha256>::new_from_slice(secret).unwrap();
+        mac.update(&data);
+        let mut output = mac.finalize().into_bytes().to_vec();
+        output.write_all(&data)?;
+        Ok(output)
+    }
+
+    /// Verifies the signature and decodes the player info.
+    pub fn from_velocity(secret: &[u8], mut input: &[u8]) -> Result<Self> {
+        if input.len() < 32 {
+            return Err(Error::UnexpectedEnd);
+        }
+        let (signature, data) = input.split_at(32);
+        let mut mac = Hmac::<Sha256>::new_from_slice(secret).unwrap();
+        mac.update(data);
+        mac.verify_slice(signature)
+            .map_err(|_| Error::InvalidSignature)?;
+        input = data;
+
+        let version = VarI32::decode(&mut input)?.0;
+        if version < 1 {
+            return Err(Error::UnknownVariant(version));
+        }
+        let address = String::decode(&mut input)?
+            .parse()
+            .map_err(|_| Error::Unexpected)?;
+        // later versions append chat session data, which is not needed
+        let user = User::decode(&mut input)?;
+        Ok(Self { address, user })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use uuid::Uuid;
+
+    use crate::{
+        forwarding::{ForwardedPlayer, Forwarding, VELOCITY_CHANNEL},
+        packet::c2s,
+        types::{User, UserProperty},
+        Error,
+    };
+
+    fn player() -> ForwardedPlayer {
+        ForwardedPlayer {
+            address: "127.0.0.1".parse().unwrap(),
+            user: User {
+                id: Uuid::new_v4(),
+                name: "player".to_string(),
+                properties: vec![UserProperty {
+                    name: "textures".to_string(),
+                    value: "value".to_string(),
+                    signature: Some("signature".to_string()),
+                }],
+            },
+        }
+    }
+
+    #[test]
+    fn bungeecord() {
+        let player = player();
+        let host_name = Forwarding::BungeeCord
+            .host_name("localhost", &player)
+            .unwrap();
+        let (host_name, forwarded_player) =
+            ForwardedPlayer::from_bungeecord(&host_name, "player".to_string()).unwrap();
+        assert_eq!(host_name, "localhost");
+        assert_eq!(forwarded_player.address, player.address);
+        assert_eq!(forwarded_player.user.id, player.user.id);
+        assert_eq!(forwarded_player.user.properties.len(), 1);
+        assert!(ForwardedPlayer::from_bungeecord("localhost", "player".to_string()).is_err());
+    }
+
+    #[test]
+    fn velocity() {
+        let player = player();
+        let forwarding = Forwarding::Velocity {
+            secret: b"secret".to_vec(),
+        };
+        let Some(c2s::LoginPacket::CustomQuery {
+            data: Some(data), ..
+        }) = forwarding
+            .respond(&player, 0, VELOCITY_CHANNEL, &[4])
+            .unwrap()
+        else {
+            panic!()
+        };
+        let forwarded_player = ForwardedPlayer::from_velocity(b"secret", &data.0).unwrap();
+        assert_eq!(forwarded_player.address, player.address);
+        assert_eq!(forwarded_player.user.name, player.user.name);
+        assert!(matches!(
+            ForwardedPlayer::from_velocity(b"other", &data.0),
+            Err(Error::InvalidSignature)
+        ));
+        assert!(forwarding
+            .respond(&player, 0, "other:channel", &[])
+            .unwrap()
+            .is_none());
+    }
+}
@@ -0,0 +1,576 @@
+use std::fmt::Write;
+
+use crate::{
+    packet::{c2s, packet_name, s2c, Direction, State},
+    types::VarI32,
+    Decode, Error, Result,
+};
+
+/// Decoded packet with its id, name and length, which can be rendered as JSON
+/// or as tree.
+#[derive(Clone, Debug)]
+pub struct Inspection {
+    pub direction: Direction,
+    pub state: State,
+    pub id: i32,
+    /// Name of the packet, none if the id is unknown.
+    pub name: Option<&'static str>,
+    /// Length of the packet data, including the id.
+    pub length: usize,
+    /// Fields of the packet, or the error decoding failed with.
+    pub fields: std::result::Result<Vec<(String, Value)>, String>,
+}
+
+/// Decodes the packet data using the decoder for its direction and state.
+pub fn inspect(direction: Direction, state: State, data: &[u8]) -> Inspection {
+    let id = VarI32::decode(&mut &data[..]).map_or(-1, |id| id.0);
+    let fields = debug(direction, state, data)
+        .map(|debug| match Value::parse(&debug) {
+            // unit variants have no fields
+            Value::Atom(_) => vec![],
+            Value::Struct(_, fields) => fields,
+            Value::Tuple(_, mut values) if values.len() == 1 => match values.remove(0) {
+                Value::Struct(_, fields) => fields,
+                value => vec![("0".to_string(), value)],
+            },
+            value => vec![("0".to_string(), value)],
+        })
+        .map_err(|error| error.to_string());
+    Inspection {
+        direction,
+        state,
+        id,
+        name: packet_name(direction, state, id),
+        length: data.len(),
+        fields,
+    }
+}
+
+fn debug(direction: Direction, state: State, mut data: &[u8]) -> Result<String> {
+    let data = &mut data;
+    Ok(match (direction, state) {
+        (Direction::C2s, State::Handshake) => {
+            format!("{:?}", c2s::HandshakePacket::decode(data)?)
+        }
+        (Direction::C2s, State::Status) => format!("{:?}", c2s::StatusPacket::decode(data)?),
+        (Direction::C2s, State::Login) => format!("{:?}", c2s::LoginPacket::decode(data)?),
+        (Direction::C2s, State::Game) => format!("{:?}", c2s::GamePacket::decode(data)?),
+        (Direction::S2c, State::Handshake) => return Err(Error::Unexpected),
+        (Direction::S2c, State::Status) => format!("{:?}", s2c::StatusPacket::decode(data)?),
+        (Direction::S2c, State::Login) => format!("{:?}", s2c::LoginPacket::decode(data)?),
+        (Direction::S2c, State::Game) => format!("{:?}", s2c::GamePacket::decode(data)?),
+    })
+}
+
+impl Inspection {
+    pub fn to_json(&self) -> serde_json::Value {
+        let mut json = serde_json::json!({
+            "direction": format!("{:?}", self.direction),
+            "state": format!("{:?}", self.state),
+            "id": self.id,
+            "name": self.name,
+            "length": self.length,
+        });
+        match &self.fields {
+            Ok(fields) => {
+                json["fields"] = fields
+                    .iter()
+                    .map(|(name, value)| (name.clone(), value.to_json()))
+                    .collect::<serde_json::Map<_, _>>()
+                    .into()
+            }
+            Err(error) => json["error"] = error.clone().into(),
+        }
+        json
+    }
+
+    /// Renders the packet as indented tree, lists and maps are truncated to
+    /// the given number of items.
+    pub fn to_tree(&self, max_items: usize) -> String {
+        let mut output = format!(
+            "{:?} {:?} 0x{:02x} {} ({} bytes)\n",
+            self.direction,
+            self.state,
+            self.id,
+            self.name.unwrap_or("Unknown"),
+            self.length
+        );
+        match &self.fields {
+            Ok(fields) => {
+                for (name, value) in fields {
+                    write_tree(&mut output, 1, name, value, max_items);
+                }
+            }
+            Err(error) => writeln!(output, "  error: {error}").unwrap(),
+        }
+        output
+    }
+}
+
+fn write_tree(output: &mut String, depth: usize, label: &str, value: &Value, max_items: usize) {
+    let indent = depth * 2;
+    writeln!(output, "{:indent$}{label}: {}", "", value.header(max_items)).unwrap();
+    let children = value.children();
+    for (label, value) in children.iter().take(max_items) {
+        write_tree(output, depth + 1, label, value, max_items);
+    }
+    if children.len() > max_items {
+        let indent = indent + 2;
+        writeln!(
+            output,
+            "{:indent$}... {} more",
+            "",
+            children.len() - max_items
+        )
+        .unwrap();
+    }
+}
+
+/// Value parsed from the output of `Debug`.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Value {
+    /// Numbers, booleans, unit variants and other unquoted values.
+    Atom(String),
+    String(String),
+    List(Vec<Value>),
+    Map(Vec<(Value, Value)>),
+    Struct(String, Vec<(String, Value)>),
+    Tuple(String, Vec<Value>),
+}
+
+impl Value {
+    /// Parses the output of `Debug`, the whole output is kept as atom if it
+    /// can't be parsed.
+    pub fn parse(debug: &str) -> Self {
+        let mut parser = Parser { input: debug };
+        match parser.value() {
+            Some(value) if parser.input.trim().is_empty() => value,
+            _ => Value::Atom(debug.to_string()),
+        }
+    }
+
+    /// Converts the value to JSON, names of structs are omitted and single
+    /// values of named tuples are keyed by their name, except for `Some` and
+    /// the `Json` and `Nbt` wrappers.
+    pub fn to_json(&self) -> serde_json::Value {
+        match self {
+            Value::Atom(atom) => match atom.as_str() {
+                "true" => true.into(),
+                "false" => false.into(),
+                "None" => serde_json::Value::Null,
+                _ => atom
+                    .parse::<i64>()
+                    .map(Into::into)
+                    .or_else(|_| atom.parse::<u64>().map(Into::into))
+                    .ok()
+                    .or_else(|| {
+                        atom.parse::<f64>()
+                            .ok()
+                            .and_then(serde_json::Number::from_f64)
+                            .map(Into::into)
+                    })
+                    .unwrap_or_else(|| atom.clone().into()),
+            },
+            Value::String(string) => string.clone().into(),
+            Value::List(values) => values.iter().map(Value::to_json).collect(),
+            Value::Map(entries) => {
+                if entries
+                    .iter()
+                    .all(|(key, _)| matches!(key, Value::Atom(_) | Value::String(_)))
+                {
+                    entries
+                        .iter()
+                        .map(|(key, value)| {
+                            let (Value::Atom(key) | Value::String(key)) = key else {
+                                unreachable!()
+                            };
+                            (key.clone(), value.to_json())
+                        })
+                        .collect::<serde_json::Map<_, _>>()
+                        .into()
+                } else {
+                    entries
+                        .iter()
+                        .map(|(key, value)| serde_json::json!([key.to_json(), value.to_json()]))
+                        .collect()
+                }
+            }
+            Value::Struct(_, fields) => fields
+                .iter()
+                .map(|(name, value)| (name.clone(), value.to_json()))
+                .collect::<serde_json::Map<_, _>>()
+                .into(),
+            Value::Tuple(name, values) => match values.as_slice() {
+                [value] if matches!(name.as_str(), "Some" | "Json" | "Nbt") => value.to_json(),
+                [value] if !name.is_empty() => serde_json::json!({ name: value.to_json() }),
+                _ => {
+                    let values = values.iter().map(Value::to_json).collect();
+                    if name.is_empty() {
+                        values
+                    } else {
+                        serde_json::json!({ name: values })
+                    }
+                }
+            },
+        }
+    }
+
+    fn is_scalar(&self) -> bool {
+        matches!(self, Value::Atom(_) | Value::String(_))
+    }
+
+    /// Line of the value in the tree, scalars and lists of scalars are
+    /// rendered inline.
+    fn header(&self, max_items: usize) -> String {
+        let inline = |values: &[Value]| {
+            let mut header = values
+                .iter()
+                .take(max_items)
+                .map(|value| value.header(max_items))
+                .collect::<Vec<_>>()
+                .join(", ");
+            if values.len() > max_items {
+                write!(header, ", ... {} more", values.len() - max_items).unwrap();
+            }
+            header
+        };
+        match self {
+            Value::Atom(atom) => atom.clone(),
+            Value::String(string) => format!("{string:?}"),
+            Value::List(values) if values.iter().all(Value::is_scalar) => {
+                format!("[{}]", inline(values))
+            }
+            Value::List(values) => format!("[{}]", values.len()),
+            Value::Map(entries) => format!("{{{}}}", entries.len()),
+            Value::Struct(name, _) => name.clone(),
+            Value::Tuple(name, values) => match values.as_slice() {
+                [value] => format!("{name}({})", value.header(max_items)),
+                _ if values.iter().all(Value::is_scalar) => {
+                    format!("{name}({})", inline(values))
+                }
+                _ => format!("{name}({})", values.len()),
+            },
+        }
+    }
+
+    /// Children of the value in the tree.
+    fn children(&self) -> Vec<(String, &Value)> {
+        fn indexed(values: &[Value]) -> Vec<(String, &Value)> {
+            values
+                .iter()
+                .enumerate()
+                .map(|(index, value)| (index.to_string(), value))
+                .collect()
+        }
+
+        match self {
+            Value::Atom(_) | Value::String(_) => vec![],
+            Value::List(values) if values.iter().all(Value::is_scalar) => vec![],
+            Value::List(values) => indexed(values),
+            Value::Map(entries) => entries
+                .iter()
+                .map(|(key, value)| (key.header(0), value))
+                .collect(),
+            Value::Struct(_, fields) => fields
+                .iter()
+                .map(|(name, value)| (name.clone(), value))
+                .collect(),
+            Value::Tuple(_, values) => match values.as_slice() {
+                [value] => value.children(),
+                _ if values.iter().all(Value::is_scalar) => vec![],
+                _ => indexed(values),
+            },
+        }
+    }
+}
+
+struct Parser<'a> {
+    input: &'a str,
+}
+
+impl Parser<'_> {
+    fn eat(&mut self, token: &str) -> bool {
+        self.input = self.input.trim_start();
+        if let Some(input) = self.input.strip_prefix(token) {
+            self.input = input;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn value(&mut self) -> Option<Value> {
+        self.input = self.input.trim_start();
+        Some(match self.input.chars().next()? {
+            '"' | '\'' => Value::String(self.string()?),
+            '[' => {
+                self.eat("[");
+                Value::List(self.values("]")?)
+            }
+            '{' => {
+                self.eat("{");
+                self.map()?
+            }
+            '(' => {
+                self.eat("(");
+                Value::Tuple(String::new(), self.values(")")?)
+            }
+            // summaries like <16 bytes>
+            '<' => {
+                let end = self.input.find('>')? + 1;
+                let atom = self.input[..end].to_string();
+                self.input = &self.input[end..];
+                Value::Atom(atom)
+            }
+            _ => {
+                let atom = self.atom()?;
+                if self.input.starts_with(" {") {
+                    self.eat("{");
+                    Value::Struct(atom, self.fields()?)
+                } else if self.eat("(") {
+                    Value::Tuple(atom, self.values(")")?)
+                } else {
+                    Value::Atom(atom)
+                }
+            }
+        })
+    }
+
+    /// Reads until a delimiter, colons are only part of paths.
+    fn atom(&mut self) -> Option<String> {
+        let mut end = self.input.len();
+        let mut chars = self.input.char_indices().peekable();
+        while let Some((index, char)) = chars.next() {
+            match char {
+                ':' if chars.peek().map(|(_, char)| *char) == Some(':') => {
+                    chars.next();
+                }
+                ',' | ':' | ' ' | '{' | '}' | '[' | ']' | '(' | ')' | '"' => {
+                    end = index;
+                    break;
+                }
+                _ => {}
+            }
+        }
+        if end == 0 {
+            return None;
+        }
+        let atom = self.input[..end].to_string();
+        self.input = &self.input[end..];
+        Some(atom)
+    }
+
+    fn string(&mut self) -> Option<String> {
+        let mut chars = self.input.chars();
+        let quote = chars.next()?;
+        let mut string = String::new();
+        loop {
+            match chars.next()? {
+                '\\' => string.push(match chars.next()? {
+                    'n' => '\n',
+                    'r' => '\r',
+                    't' => '\t',
+                    '0' => '\0',
+                    'u' => {
+                        chars.next().filter(|&char| char == '{')?;
+                        let code = chars
+                            .by_ref()
+                            .take_while(|&char| char != '}')
+                            .collect::<String>();
+                        char::from_u32(u32::from_str_radix(&code, 16).ok()?)?
+                    }
+                    char => char,
+                }),
+                char if char == quote => break,
+                char => string.push(char),
+            }
+        }
+        self.input = chars.as_str();
+        Some(string)
+    }
+
+    fn values(&mut self, end: &str) -> Option<Vec<Value>> {
+        let mut values = vec![];
+        while !self.eat(end) {
+            values.push(self.value()?);
+            if !self.eat(",") {
+                self.eat(end).then_some(())?;
+                break;
+            }
+        }
+        Some(values)
+    }
+
+    fn fields(&mut self) -> Option<Vec<(String, Value)>> {
+        let mut fields = vec![];
+        while !self.eat("}") {
+            // non-exhaustive structs
+            if self.eat("..") {
+                continue;
+            }
+            self.input = self.input.trim_start();
+            let name = self.atom()?;
+            self.eat(":").then_some(())?;
+            fields.push((name, self.value()?));
+            if !self.eat(",") {
+                self.eat("}").then_some(())?;
+                break;
+            }
+        }
+        Some(fields)
+    }
+
+    /// Reads a map or a set, which is returned as list.
+    fn map(&mut self) -> Option<Value> {
+        let mut entries = vec![];
+        let mut values = vec![];
+        while !self.eat("}") {
+            let key = self.value()?;
+            if self.eat(":") {
+                entries.push((key, self.value()?));
+            } else {
+                values.push(key);
+            }
+            if !self.eat(",") {
+                self.eat("}").then_some(())?;
+                break;
+            }
+        }
+        match (entries.is_empty(), values.is_empty()) {
+            (_, true) => Some(Value::Map(entries)),
+            (true, false) => Some(Value::List(values)),
+            (false, false) => None,
+        }
+    }
+}
+
+/// Filters packets by name, patterns are case-insensitive and may contain `*`
+/// as wildcard.
+#[derive(Clone, Debug, Default)]
+pub struct Filter {
+    include: Vec<String>,
+    exclude: Vec<String>,
+}
+
+impl Filter {
+    /// Packets match if they match any included pattern, or if there are
+    /// none, and no excluded pattern.
+    pub fn new(include: Vec<String>, exclude: Vec<String>) -> Self {
+        Self {
+            include: include
+                .iter()
+                .map(|pattern| pattern.to_lowercase())
+                .collect(),
+            exclude: exclude
+                .iter()
+                .map(|pattern| pattern.to_lowercase())
+                .collect(),
+        }
+    }
+
+    pub fn matches(&self, name: &str) -> bool {
+        let name = name.to_lowercase();
+        (self.include.is_empty() || self.include.iter().any(|pattern| glob(pattern, &name)))
+            && !self.exclude.iter().any(|pattern| glob(pattern, &name))
+    }
+
+    /// Matches the packet by its id, without decoding it.
+    pub fn matches_data(&self, direction: Direction, state: State, data: &[u8]) -> bool {
+        let id = VarI32::decode(&mut &data[..]).map_or(-1, |id| id.0);
+        self.matches(packet_name(direction, state, id).unwrap_or_default())
+    }
+}
+
+fn glob(pattern: &str, name: &str) -> bool {
+    match pattern.split_once('*') {
+        None => pattern == name,
+        Some((prefix, pattern)) => {
+            name.starts_with(prefix)
+                && (prefix.len()..=name.len())
+                    .filter(|&index| name.is_char_boundary(index))
+                    .any(|index| glob(pattern, &name[index..]))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        inspect::{inspect, Filter, Value},
+        packet::{c2s, s2c, Direction, State},
+        types::{Component, Json},
+        Encode,
+    };
+
+    #[test]
+    fn parse() {
+        assert_eq!(
+            Value::parse(r#"Foo { a: Some(1), b: [2.5, -3], c: {"d": Bar}, e: <4 bytes> }"#),
+            Value::Struct(
+                "Foo".to_string(),
+                vec![
+                    (
+                        "a".to_string(),
+                        Value::Tuple("Some".to_string(), vec![Value::Atom("1".to_string())])
+                    ),
+                    (
+                        "b".to_string(),
+                        Value::List(vec![
+                            Value::Atom("2.5".to_string()),
+                            Value::Atom("-3".to_string())
+                        ])
+                    ),
+                    (
+                        "c".to_string(),
+                        Value::Map(vec![(
+                            Value::String("d".to_string()),
+                            Value::Atom("Bar".to_string())
+                        )])
+                    ),
+                    ("e".to_string(), Value::Atom("<4 bytes>".to_string())),
+                ]
+            )
+        );
+        assert_eq!(
+            Value::parse("not { valid"),
+            Value::Atom("not { valid".to_string())
+        );
+    }
+
+    #[test]
+    fn inspect_and_filter() {
+        let mut data = vec![];
+        s2c::GamePacket::SystemChat {
+            content: Json(Component::Literal("Hello \"world\"".to_string())),
+            overlay: false,
+        }
+        .encode(&mut data)
+        .unwrap();
+        let inspection = inspect(Direction::S2c, State::Game, &data);
+        assert_eq!(inspection.name, Some("SystemChat"));
+        assert_eq!(inspection.length, data.len());
+        assert_eq!(
+            inspection.to_json()["fields"],
+            serde_json::json!({ "content": { "Literal": "Hello \"world\"" }, "overlay": false })
+        );
+        assert!(inspection
+            .to_tree(8)
+            .contains("content: Json(Literal(\"Hello \\\"world\\\"\"))"));
+
+        let filter = Filter::new(vec!["system*".to_string()], vec!["*Player*".to_string()]);
+        assert!(filter.matches_data(Direction::S2c, State::Game, &data));
+        assert!(!filter.matches("PlayerChat"));
+        assert!(!filter.matches("KeepAlive"));
+
+        let mut data = vec![];
+        c2s::GamePacket::KeepAlive { id: 1 }
+            .encode(&mut data)
+            .unwrap();
+        let inspection = inspect(Direction::C2s, State::Game, &data);
+        assert_eq!(inspection.name, Some("KeepAlive"));
+        assert_eq!(inspection.to_json()["fields"]["id"], 1);
+        assert!(inspect(Direction::C2s, State::Game, &[0x7f])
+            .fields
+            .is_err());
+    }
+}
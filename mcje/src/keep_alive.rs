@@ -0,0 +1,211 @@
+use std::{
+    io::ErrorKind,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use tokio::time::Instant;
+
+use crate::{
+    packet::{c2s, s2c},
+    Result,
+};
+
+/// Server side of the keep alive, which sends keep alives on a schedule, times
+/// out clients not answering them and measures the round-trip time.
+pub struct KeepAliveSender {
+    interval: Duration,
+    timeout: Duration,
+
+    next: Instant,
+    pending: Option<(i64, Instant)>,
+    latency: Option<Duration>,
+}
+
+impl KeepAliveSender {
+    pub fn new(interval: Duration, timeout: Duration) -> Self {
+        Self {
+            interval,
+            timeout,
+            next: Instant::now(),
+            pending: None,
+            latency: None,
+        }
+    }
+
+    /// Waits until the next keep alive has to be sent and returns its id, fails
+    /// if the last keep alive hasn't been answered in time.
+    ///
+    /// This is cancel safe, and therefore can be used in `tokio::select!`.
+    pub async fn tick(&mut self) -> Result<i64> {
+        if let Some((_, sent)) = self.pending {
+            tokio::time::sleep_until(sent + self.timeout).await;
+            return Err(std::io::Error::from(ErrorKind::TimedOut).into());
+        }
+
+        tokio::time::sleep_until(self.next).await;
+        let now = Instant::now();
+        let id = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_millis() as i64;
+        self.next = now + self.interval;
+        self.pending = Some((id, now));
+        Ok(id)
+    }
+
+    /// Handles the response to a keep alive, returns false if the id doesn't
+    /// belong to the pending keep alive.
+    pub fn receive(&mut self, id: i64) -> bool {
+        match self.pending {
+            Some((pending_id, sent)) if pending_id == id => {
+                let round_trip_time = sent.elapsed();
+                // smoothed the same way as the vanilla server does
+                self.latency = Some(match self.latency {
+                    Some(latency) => (latency * 3 + round_trip_time) / 4,
+                    None => round_trip_time,
+                });
+                self.pending = None;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Convenience function for handling received packets, returns false if
+    /// the packet is an unexpected keep alive.
+    pub fn handle(&mut self, packet: &c2s::GamePacket) -> bool {
+        match packet {
+            c2s::GamePacket::KeepAlive { id } => self.receive(*id),
+            _ => true,
+        }
+    }
+
+    /// Smoothed round-trip time, none until the first keep alive has been
+    /// answered.
+    pub fn latency(&self) -> Option<Duration> {
+        self.latency
+    }
+
+    /// Latency in milliseconds as used in the player info.
+    pub fn latency_millis(&self) -> i32 {
+        self.latency.map_or(0, |latency| {
+            latency.as_millis().min(i32::MAX as u128) as i32
+        })
+    }
+}
+
+/// Client side of the keep alive, which answers keep alives and pings, and
+/// detects if the server stopped sending keep alives.
+pub struct KeepAliveResponder {
+    timeout: Duration,
+
+    last_received: Instant,
+}
+
+impl KeepAliveResponder {
+    pub fn new(timeout: Duration) -> Self {
+        Self {
+            timeout,
+            last_received: Instant::now(),
+        }
+    }
+
+    /// Returns the response to the received packet, if it requires one.
+    pub fn handle(&mut self, packet: &s2c::GamePacket) -> Option<c2s::GamePacket<'static>> {
+        match packet {
+            s2c::GamePacket::KeepAlive { id } => {
+                self.last_received = Instant::now();
+                Some(c2s::GamePacket::KeepAlive { id: *id })
+            }
+            s2c::GamePacket::Ping { id } => Some(c2s::GamePacket::Pong { id: *id }),
+            _ => None,
+        }
+    }
+
+    /// Completes when no keep alive has been received within the timeout.
+    pub async fn timed_out(&self) {
+        tokio::time::sleep_until(self.last_received + self.timeout).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{io::ErrorKind, time::Duration};
+
+    use tokio::time::Instant;
+
+    use crate::{
+        keep_alive::{KeepAliveResponder, KeepAliveSender},
+        packet::{c2s, s2c},
+        Error,
+    };
+
+    #[tokio::test(start_paused = true)]
+    async fn timeout() {
+        let mut sender = KeepAliveSender::new(Duration::from_secs(15), Duration::from_secs(30));
+        sender.tick().await.unwrap();
+        let sent = Instant::now();
+        assert!(matches!(
+            sender.tick().await,
+            Err(Error::Io(error)) if error.kind() == ErrorKind::TimedOut
+        ));
+        assert_eq!(sent.elapsed(), Duration::from_secs(30));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn reject_unexpected_ids() {
+        let mut sender = KeepAliveSender::new(Duration::from_secs(15), Duration::from_secs(30));
+        let id = sender.tick().await.unwrap();
+        assert!(!sender.receive(id + 1));
+        assert!(!sender.handle(&c2s::GamePacket::KeepAlive { id: id - 1 }));
+        assert!(sender.handle(&c2s::GamePacket::KeepAlive { id }));
+        // already answered
+        assert!(!sender.receive(id));
+        assert!(sender.handle(&c2s::GamePacket::Pong { id: 0 }));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn latency() {
+        let mut sender = KeepAliveSender::new(Duration::from_secs(15), Duration::from_secs(30));
+        assert_eq!(sender.latency(), None);
+        assert_eq!(sender.latency_millis(), 0);
+
+        let id = sender.tick().await.unwrap();
+        tokio::time::advance(Duration::from_millis(100)).await;
+        assert!(sender.receive(id));
+        assert_eq!(sender.latency(), Some(Duration::from_millis(100)));
+
+        // the previous latency is weighted three times as much
+        let id = sender.tick().await.unwrap();
+        tokio::time::advance(Duration::from_millis(200)).await;
+        assert!(sender.receive(id));
+        assert_eq!(sender.latency(), Some(Duration::from_millis(125)));
+        assert_eq!(sender.latency_millis(), 125);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn respond() {
+        let mut responder = KeepAliveResponder::new(Duration::from_secs(30));
+        assert!(matches!(
+            responder.handle(&s2c::GamePacket::KeepAlive { id: 5 }),
+            Some(c2s::GamePacket::KeepAlive { id: 5 })
+        ));
+        assert!(matches!(
+            responder.handle(&s2c::GamePacket::Ping { id: 6 }),
+            Some(c2s::GamePacket::Pong { id: 6 })
+        ));
+        assert!(responder
+            .handle(&s2c::GamePacket::SetTime {
+                game_time: 0,
+                day_time: 0
+            })
+            .is_none());
+
+        // each keep alive restarts the timeout
+        tokio::time::advance(Duration::from_secs(20)).await;
+        responder.handle(&s2c::GamePacket::KeepAlive { id: 7 });
+        let received = Instant::now();
+        responder.timed_out().await;
+        assert_eq!(received.elapsed(), Duration::from_secs(30));
+    }
+}
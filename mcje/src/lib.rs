@@ -7,11 +7,27 @@ use thiserror::Error;
 
 pub use iokum_mcje_derive::{Decode, Encode};
 
+#[cfg(feature = "arbitrary")]
+pub mod arbitrary;
+pub mod capture;
+pub mod channel;
 pub mod codec;
+pub mod connection;
+pub mod forwarding;
+pub mod inspect;
+pub mod keep_alive;
 pub mod packet;
+pub mod ping;
 pub mod resource_pack;
+pub mod server;
 pub mod types;
 
+/// Protocol version implemented by this crate.
+pub const PROTOCOL_VERSION: i32 = 762;
+
+/// Name of the game version implemented by this crate.
+pub const VERSION: &str = "1.19.4";
+
 #[derive(Error, Debug)]
 pub enum Error {
     // delegated marshalling errors
@@ -23,26 +39,89 @@ pub enum Error {
     Json(#[from] serde_json::Error),
     #[error("NBT error")]
     Nbt(#[from] iokum_nbt::error::Error),
+    #[error("Base64 error")]
+    Base64(#[from] base64::DecodeError),
+    #[error("Zip error")]
+    Zip(#[from] zip::result::ZipError),
 
     // marshalling errors
     #[error("Invalid length")]
     InvalidLength,
     #[error("Unknown variant: {0}")]
     UnknownVariant(i32),
+    #[error("Invalid image")]
+    InvalidImage,
 
     // processing errors
     #[error("Unexpected end")]
     UnexpectedEnd,
+    #[error("Invalid signature")]
+    InvalidSignature,
+    #[error("Unknown transaction: {0}")]
+    UnknownTransaction(i32),
     #[error("Unexpected")]
     Unexpected,
+
+    // resource pack errors
+    #[error("Missing resource: {0}")]
+    MissingResource(String),
+    #[error("Circular parent: {0}")]
+    CircularParent(String),
 }
 
 pub type Result<T> = std::result::Result<T, Error>;
 
 pub trait Encode {
     fn encode(&self, output: &mut impl std::io::Write) -> Result<()>;
+
+    /// Returns the number of bytes written by [`Encode::encode`], which is
+    /// counted by encoding the value unless overridden.
+    fn encoded_len(&self) -> Result<usize> {
+        let mut output = ByteCount(0);
+        self.encode(&mut output)?;
+        Ok(output.0)
+    }
 }
 
 pub trait Decode<'a>: Sized {
     fn decode(input: &mut &'a [u8]) -> Result<Self>;
 }
+
+/// Encodes the value as `T`, which is used for fields with `#[using(T)]`.
+#[diagnostic::on_unimplemented(
+    message = "`{Self}` can't be encoded as `{T}`",
+    label = "required by `#[using({T})]`"
+)]
+pub trait EncodeAs<T> {
+    fn encode_as(&self, output: &mut impl std::io::Write) -> Result<()>;
+
+    /// Returns the number of bytes written by [`EncodeAs::encode_as`].
+    fn encoded_len_as(&self) -> Result<usize> {
+        let mut output = ByteCount(0);
+        self.encode_as(&mut output)?;
+        Ok(output.0)
+    }
+}
+
+/// Decodes the value as `T`, which is used for fields with `#[using(T)]`.
+#[diagnostic::on_unimplemented(
+    message = "`{Self}` can't be decoded as `{T}`",
+    label = "required by `#[using({T})]`"
+)]
+pub trait DecodeAs<'a, T>: Sized {
+    fn decode_as(input: &mut &'a [u8]) -> Result<Self>;
+}
+
+/// Writer which only counts the written bytes.
+struct ByteCount(usize);
+
+impl std::io::Write for ByteCount {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0 += buf.len();
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
@@ -1,7 +1,10 @@
+use std::borrow::Cow;
+
 use glam::IVec3;
 use uuid::Uuid;
 
 use crate::{
+    packet::PacketName,
     types::{
         ChatSession, ChatVisibility, ClickType, Difficulty, Direction, Hand, ItemStack,
         LastSeenMessages, MainHand, RecipeBookType, TrailingBytes, VarI32, VarI64,
@@ -9,8 +12,14 @@ use crate::{
     Decode, Encode,
 };
 
-#[derive(Encode, Decode, Clone, Debug)]
-pub enum GamePacket {
+#[derive(Encode, Decode, PacketName, Clone, Debug)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(tag = "type", content = "data")
+)]
+#[cfg_attr(feature = "arbitrary", derive(crate::arbitrary::Arbitrary))]
+pub enum GamePacket<'a> {
     AcceptTeleportation {
         #[using(VarI32)]
         id: i32,
@@ -28,16 +37,24 @@ pub enum GamePacket {
         offset: i32,
     },
     ChatCommand {
-        command: String,
+        command: Cow<'a, str>,
         timestamp: i64,
         salt: i64,
+        #[cfg_attr(
+            feature = "serde",
+            serde(with = "serde_with::As::<Vec<(serde_with::Same, serde_with::Bytes)>>")
+        )]
         argument_signatures: Vec<(String, [u8; 256])>,
         last_seen_messages: LastSeenMessages,
     },
     Chat {
-        message: String,
+        message: Cow<'a, str>,
         timestamp: i64,
         salt: i64,
+        #[cfg_attr(
+            feature = "serde",
+            serde(with = "serde_with::As::<Option<serde_with::Bytes>>")
+        )]
         signature: Option<[u8; 256]>,
         last_seen_messages: LastSeenMessages,
     },
@@ -81,7 +98,7 @@ pub enum GamePacket {
     },
     CustomPayload {
         identifier: String,
-        data: TrailingBytes<{ (1 << 15) - 1 }>,
+        data: TrailingBytes<'a, { (1 << 15) - 1 }>,
     },
     EditBook {
         #[using(VarI32)]
@@ -280,12 +297,16 @@ pub enum GamePacket {
 }
 
 #[derive(Encode, Decode, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "arbitrary", derive(crate::arbitrary::Arbitrary))]
 pub enum ClientCommandPacketAction {
     PerformRespawn,
     RequestStats,
 }
 
 #[derive(Encode, Decode, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "arbitrary", derive(crate::arbitrary::Arbitrary))]
 pub enum InteractPacketAction {
     Interact { hand: Hand },
     Attack,
@@ -293,6 +314,8 @@ pub enum InteractPacketAction {
 }
 
 #[derive(Encode, Decode, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "arbitrary", derive(crate::arbitrary::Arbitrary))]
 pub enum PlayerActionPacketAction {
     StartDestroyBlock,
     AbortDestroyBlock,
@@ -304,6 +327,8 @@ pub enum PlayerActionPacketAction {
 }
 
 #[derive(Encode, Decode, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "arbitrary", derive(crate::arbitrary::Arbitrary))]
 pub enum PlayerCommandPacketAction {
     PressShiftKey,
     ReleaseShiftKey,
@@ -317,6 +342,8 @@ pub enum PlayerCommandPacketAction {
 }
 
 #[derive(Encode, Decode, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "arbitrary", derive(crate::arbitrary::Arbitrary))]
 pub enum ResourcePackPacket {
     SuccessfullyLoaded,
     Declined,
@@ -325,6 +352,8 @@ pub enum ResourcePackPacket {
 }
 
 #[derive(Encode, Decode, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "arbitrary", derive(crate::arbitrary::Arbitrary))]
 pub enum SeenAdvancementsPacket {
     OpenedTab { tab: String },
     ClosedScreen,
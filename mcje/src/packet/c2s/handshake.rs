@@ -1,9 +1,16 @@
 use crate::{
+    packet::PacketName,
     types::{Intention, VarI32},
     Decode, Encode,
 };
 
-#[derive(Encode, Decode, Clone, Debug)]
+#[derive(Encode, Decode, PacketName, Clone, Debug)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(tag = "type", content = "data")
+)]
+#[cfg_attr(feature = "arbitrary", derive(crate::arbitrary::Arbitrary))]
 pub enum HandshakePacket {
     Intention {
         #[using(VarI32)]
@@ -1,12 +1,19 @@
 use uuid::Uuid;
 
 use crate::{
+    packet::PacketName,
     types::{TrailingBytes, VarI32},
     Decode, Encode,
 };
 
-#[derive(Encode, Decode, Clone, Debug)]
-pub enum LoginPacket {
+#[derive(Encode, Decode, PacketName, Clone, Debug)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(tag = "type", content = "data")
+)]
+#[cfg_attr(feature = "arbitrary", derive(crate::arbitrary::Arbitrary))]
+pub enum LoginPacket<'a> {
     Hello {
         name: String,
         uuid: Option<Uuid>,
@@ -18,6 +25,6 @@ pub enum LoginPacket {
     CustomQuery {
         #[using(VarI32)]
         transaction_id: i32,
-        data: TrailingBytes<{ 1 << 20 }>,
+        data: Option<TrailingBytes<'a, { 1 << 20 }>>,
     },
 }
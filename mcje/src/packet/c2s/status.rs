@@ -1,6 +1,12 @@
-use crate::{Decode, Encode};
+use crate::{packet::PacketName, Decode, Encode};
 
-#[derive(Encode, Decode, Clone, Debug)]
+#[derive(Encode, Decode, PacketName, Clone, Debug)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(tag = "type", content = "data")
+)]
+#[cfg_attr(feature = "arbitrary", derive(crate::arbitrary::Arbitrary))]
 pub enum StatusPacket {
     StatusRequest,
     PingRequest { time: i64 },
@@ -1,2 +1,270 @@
+pub use iokum_mcje_derive::PacketName;
+
+use crate::{Decode, Encode};
+
 pub mod c2s;
 pub mod s2c;
+
+/// Direction a packet is sent in, which together with the state determines
+/// the packet type.
+#[derive(Encode, Decode, Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "arbitrary", derive(crate::arbitrary::Arbitrary))]
+pub enum Direction {
+    C2s,
+    S2c,
+}
+
+impl Direction {
+    pub fn opposite(self) -> Self {
+        match self {
+            Direction::C2s => Direction::S2c,
+            Direction::S2c => Direction::C2s,
+        }
+    }
+}
+
+/// State of a connection, the handshake determines if status or login
+/// follows, and game follows after the login.
+#[derive(Encode, Decode, Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "arbitrary", derive(crate::arbitrary::Arbitrary))]
+pub enum State {
+    Handshake,
+    Status,
+    Login,
+    Game,
+}
+
+/// Maps packets to their id and name, the id is the index of the variant unless
+/// set using `#[id = ..]`. The names are indexed by id, and empty for
+/// unassigned ids.
+pub trait PacketName {
+    const NAMES: &'static [&'static str];
+
+    fn id(&self) -> i32;
+
+    fn name(&self) -> &'static str {
+        Self::NAMES[self.id() as usize]
+    }
+}
+
+/// Returns the name of the packet with the given id, or none if the id is
+/// unknown in this direction and state.
+pub fn packet_name(direction: Direction, state: State, id: i32) -> Option<&'static str> {
+    let names = match (direction, state) {
+        (Direction::C2s, State::Handshake) => c2s::HandshakePacket::NAMES,
+        (Direction::C2s, State::Status) => c2s::StatusPacket::NAMES,
+        (Direction::C2s, State::Login) => c2s::LoginPacket::NAMES,
+        (Direction::C2s, State::Game) => c2s::GamePacket::NAMES,
+        (Direction::S2c, State::Handshake) => &[],
+        (Direction::S2c, State::Status) => s2c::StatusPacket::NAMES,
+        (Direction::S2c, State::Login) => s2c::LoginPacket::NAMES,
+        (Direction::S2c, State::Game) => s2c::GamePacket::NAMES,
+    };
+    usize::try_from(id)
+        .ok()
+        .and_then(|id| names.get(id).copied())
+        .filter(|name| !name.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn keep_received_order() {
+        use indexmap::IndexMap;
+
+        use crate::{
+            packet::s2c,
+            types::{EntityDataValue, EquipmentSlot},
+            Decode, Encode,
+        };
+
+        // re-encoding a decoded packet has to result in the same bytes, e.g.
+        // when passing it through a proxy
+        fn round_trip(packet: s2c::GamePacket) -> Vec<u8> {
+            let mut data = vec![];
+            packet.encode(&mut data).unwrap();
+            let mut reencoded = vec![];
+            s2c::GamePacket::decode(&mut data.as_slice())
+                .unwrap()
+                .encode(&mut reencoded)
+                .unwrap();
+            assert_eq!(reencoded, data);
+            data
+        }
+
+        let data = round_trip(s2c::GamePacket::SetEntityData {
+            id: 1,
+            packed_items: IndexMap::from([
+                (8, EntityDataValue::Byte(0)),
+                (0, EntityDataValue::Int(1)),
+                (17, EntityDataValue::Float(2.0)),
+                (3, EntityDataValue::Byte(3)),
+            ]),
+        });
+        let Ok(s2c::GamePacket::SetEntityData { packed_items, .. }) =
+            s2c::GamePacket::decode(&mut data.as_slice())
+        else {
+            panic!("packet changed");
+        };
+        assert_eq!(
+            packed_items.keys().copied().collect::<Vec<_>>(),
+            [8, 0, 17, 3]
+        );
+
+        let data = round_trip(s2c::GamePacket::SetEquipment {
+            entity: 1,
+            slots: IndexMap::from([
+                (EquipmentSlot::Head, None),
+                (EquipmentSlot::MainHand, None),
+                (EquipmentSlot::Feet, None),
+                (EquipmentSlot::OffHand, None),
+            ]),
+        });
+        let Ok(s2c::GamePacket::SetEquipment { slots, .. }) =
+            s2c::GamePacket::decode(&mut data.as_slice())
+        else {
+            panic!("packet changed");
+        };
+        assert_eq!(
+            slots.keys().copied().collect::<Vec<_>>(),
+            [
+                EquipmentSlot::Head,
+                EquipmentSlot::MainHand,
+                EquipmentSlot::Feet,
+                EquipmentSlot::OffHand
+            ]
+        );
+    }
+
+    #[test]
+    fn reject_inconsistent_packets() {
+        use uuid::Uuid;
+
+        use crate::{
+            packet::s2c::game::{
+                PlayerInfoUpdatePacket, PlayerInfoUpdatePacketEntry, SetEquipmentPacketSlots,
+            },
+            types::VarI32,
+            Decode, Encode, Error,
+        };
+
+        // slot 6 doesn't exist
+        assert!(matches!(
+            SetEquipmentPacketSlots::decode(&mut [0x06, 0x00].as_slice()),
+            Err(Error::UnknownVariant(6))
+        ));
+
+        // the second entry lacks the latency of the first one
+        let entry = |latency| PlayerInfoUpdatePacketEntry {
+            profile_id: Uuid::nil(),
+            profile: None,
+            chat_session: None,
+            game_mode: None,
+            listed: None,
+            latency,
+            display_name: None,
+        };
+        assert!(matches!(
+            PlayerInfoUpdatePacket {
+                entries: vec![entry(Some(VarI32(1))), entry(None)],
+            }
+            .encode(&mut vec![]),
+            Err(Error::Unexpected)
+        ));
+    }
+
+    #[test]
+    fn long_disconnect_reason() {
+        use crate::{
+            packet::s2c,
+            types::{Component, Json, MAX_STRING_LENGTH},
+            Decode, Encode,
+        };
+
+        // chat components may be longer than other strings
+        let reason = "a".repeat(MAX_STRING_LENGTH * 2);
+        let mut data = vec![];
+        s2c::GamePacket::Disconnect {
+            reason: Json(Component::Literal(reason.clone())),
+        }
+        .encode(&mut data)
+        .unwrap();
+        let Ok(s2c::GamePacket::Disconnect {
+            reason: Json(Component::Literal(decoded)),
+        }) = s2c::GamePacket::decode(&mut data.as_slice())
+        else {
+            panic!("packet changed");
+        };
+        assert_eq!(decoded, reason);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_round_trip() {
+        use indexmap::IndexMap;
+
+        use crate::{
+            packet::{c2s, s2c},
+            types::{Component, EntityDataValue, Json, LastSeenMessages},
+            Decode, Encode,
+        };
+
+        // packets decoded from JSON have to encode to the same JSON again
+        macro_rules! round_trip {
+            ($type:ty, $packet:expr) => {{
+                let json = serde_json::to_value(&$packet).unwrap();
+                let mut data = vec![];
+                serde_json::from_value::<$type>(json.clone())
+                    .unwrap()
+                    .encode(&mut data)
+                    .unwrap();
+                let decoded = <$type>::decode(&mut data.as_slice()).unwrap();
+                assert_eq!(serde_json::to_value(decoded).unwrap(), json);
+                json
+            }};
+        }
+
+        assert_eq!(
+            round_trip!(c2s::GamePacket, c2s::GamePacket::KeepAlive { id: 1 }),
+            serde_json::json!({ "type": "KeepAlive", "data": { "id": 1 } })
+        );
+        round_trip!(
+            c2s::GamePacket,
+            c2s::GamePacket::ChatCommand {
+                command: "msg".into(),
+                timestamp: 2,
+                salt: 3,
+                argument_signatures: vec![("message".to_string(), [4; 256])],
+                last_seen_messages: LastSeenMessages {
+                    offset: 0,
+                    acknowledged: [0; 3],
+                },
+            }
+        );
+        round_trip!(
+            s2c::GamePacket,
+            s2c::GamePacket::SystemChat {
+                content: Json(Component::Literal("Hello".to_string())),
+                overlay: false,
+            }
+        );
+        round_trip!(
+            s2c::GamePacket,
+            s2c::GamePacket::SetEntityData {
+                id: 5,
+                packed_items: IndexMap::from([
+                    (0, EntityDataValue::Byte(6)),
+                    (1, EntityDataValue::OptionalComponent(None)),
+                ]),
+            }
+        );
+        round_trip!(
+            s2c::LoginPacket,
+            s2c::LoginPacket::LoginCompression {
+                compression_threshold: 256,
+            }
+        );
+    }
+}
@@ -1,20 +1,29 @@
-use std::{collections::HashMap, io::Write};
+use std::{borrow::Cow, io::Write};
 
 use derivative::Derivative;
 use glam::{DVec3, IVec3};
+use indexmap::IndexMap;
 use uuid::Uuid;
 
 use crate::{
+    packet::PacketName,
     types::{
-        Advancement, Anchor, Angle, BossEventColor, BossEventOverlay, ChatSession, ChatTypeBound,
-        Component, Difficulty, EntityData, EntityDataValue, EquipmentSlot, GameType, Hand,
-        ItemStack, Json, MapDecoration, MapPatch, MerchantOffer, Nbt, Recipe, Registries, Sound,
-        SoundSource, TrailingBytes, User, VarI32, VarI64,
+        capacity, debug_byte_lists, debug_bytes, decode_length, Advancement, Anchor, Angle,
+        BossEventColor, BossEventOverlay, ChatSession, ChatTypeBound, Component, Difficulty,
+        EntityData, EntityDataValue, EquipmentSlot, GameType, Hand, ItemStack, Json, MapDecoration,
+        MapPatch, MerchantOffer, Nbt, Recipe, Registries, Sound, SoundSource, TrailingBytes, User,
+        VarI32, VarI64,
     },
-    Decode, Encode, Error, Result,
+    Decode, DecodeAs, Encode, EncodeAs, Error, Result,
 };
 
-#[derive(Encode, Decode, Clone, Debug)]
+#[derive(Encode, Decode, PacketName, Clone, Debug)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(tag = "type", content = "data")
+)]
+#[cfg_attr(feature = "arbitrary", derive(crate::arbitrary::Arbitrary))]
 pub enum GamePacket<'a> {
     BundleDelimiter,
     AddEntity {
@@ -101,7 +110,7 @@ pub enum GamePacket<'a> {
         x: i32,
         #[using(VarI32)]
         z: i32,
-        buffer: Vec<u8>,
+        buffer: Cow<'a, [u8]>,
     },
     ClearTitles {
         reset_times: bool,
@@ -154,7 +163,7 @@ pub enum GamePacket<'a> {
     },
     CustomPayload {
         identifier: String,
-        data: TrailingBytes<{ 1 << 20 }>,
+        data: TrailingBytes<'a, { 1 << 20 }>,
     },
     DamageEvent {
         #[using(VarI32)]
@@ -171,7 +180,7 @@ pub enum GamePacket<'a> {
         message_signature: Vec<u8>,
     },
     Disconnect {
-        reason: String,
+        reason: Json<Component>,
     },
     DisguisedChatPacket {
         message: Json<Component>,
@@ -228,8 +237,8 @@ pub enum GamePacket<'a> {
     LevelChunkWithLight {
         x: i32,
         z: i32,
-        chunk_data: LevelChunkPacketData,
-        light_data: LightUpdatePacketData,
+        chunk_data: LevelChunkPacketData<'a>,
+        light_data: LightUpdatePacketData<'a>,
     },
     LevelEvent {
         type_: i32,
@@ -254,7 +263,7 @@ pub enum GamePacket<'a> {
         x: i32,
         #[using(VarI32)]
         z: i32,
-        light_data: LightUpdatePacketData,
+        light_data: LightUpdatePacketData<'a>,
     },
     Login {
         player_id: i32,
@@ -356,8 +365,12 @@ pub enum GamePacket<'a> {
         sender: Uuid,
         #[using(VarI32)]
         index: i32,
+        #[cfg_attr(
+            feature = "serde",
+            serde(with = "serde_with::As::<Option<serde_with::Bytes>>")
+        )]
         signature: Option<[u8; 256]>,
-        message: String,
+        message: Cow<'a, str>,
         timestamp: i64,
         salt: i64,
         unsigned_content: Option<Json<Component>>,
@@ -487,7 +500,13 @@ pub enum GamePacket<'a> {
         #[using(VarI32)]
         id: i32,
         #[using(EntityData)]
-        packed_items: HashMap<u8, EntityDataValue>,
+        #[cfg_attr(
+            feature = "serde",
+            serde(
+                with = "serde_with::As::<IndexMap<serde_with::DisplayFromStr, serde_with::Same>>"
+            )
+        )]
+        packed_items: IndexMap<u8, EntityDataValue>,
     },
     SetEntityLink {
         source_id: i32,
@@ -504,7 +523,7 @@ pub enum GamePacket<'a> {
         #[using(VarI32)]
         entity: i32,
         #[using(SetEquipmentPacketSlots)]
-        slots: HashMap<EquipmentSlot, Option<ItemStack>>,
+        slots: IndexMap<EquipmentSlot, Option<ItemStack>>,
     },
     SetExperience {
         experience_progress: f32,
@@ -643,6 +662,8 @@ pub enum GamePacket<'a> {
 
 #[derive(Encode, Decode, Clone, Debug)]
 #[using(u8)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "arbitrary", derive(crate::arbitrary::Arbitrary))]
 pub enum AnimatePacketAction {
     SwingMainHand,
     WakeUp,
@@ -652,6 +673,8 @@ pub enum AnimatePacketAction {
 }
 
 #[derive(Encode, Decode, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "arbitrary", derive(crate::arbitrary::Arbitrary))]
 pub enum BossEventPacketOperation {
     Add {
         name: Json<Component>,
@@ -677,6 +700,8 @@ pub enum BossEventPacketOperation {
 }
 
 #[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "arbitrary", derive(crate::arbitrary::Arbitrary))]
 pub struct CommandsPacketEntry {
     children: Vec<VarI32>,
     redirect: Option<VarI32>,
@@ -684,6 +709,8 @@ pub struct CommandsPacketEntry {
 }
 
 #[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "arbitrary", derive(crate::arbitrary::Arbitrary))]
 pub enum CommandsPacketNodeStub {
     Root,
     Literal {
@@ -775,6 +802,8 @@ impl Decode<'_> for CommandsPacketEntry {
 }
 
 #[derive(Encode, Decode, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "arbitrary", derive(crate::arbitrary::Arbitrary))]
 pub enum CommandsPacketArgumentType {
     Bool,
     Float(CommandsPacketArgumentTypeNumber<f32>),
@@ -827,6 +856,8 @@ pub enum CommandsPacketArgumentType {
 }
 
 #[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "arbitrary", derive(crate::arbitrary::Arbitrary))]
 pub struct CommandsPacketArgumentTypeNumber<T> {
     min: Option<T>,
     max: Option<T>,
@@ -865,6 +896,8 @@ impl<'a, T: Decode<'a>> Decode<'a> for CommandsPacketArgumentTypeNumber<T> {
 }
 
 #[derive(Encode, Decode, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "arbitrary", derive(crate::arbitrary::Arbitrary))]
 pub enum CommandsPacketArgumentTypeString {
     SingleWord,
     QuotablePhrase,
@@ -872,6 +905,8 @@ pub enum CommandsPacketArgumentTypeString {
 }
 
 #[derive(Encode, Decode, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "arbitrary", derive(crate::arbitrary::Arbitrary))]
 pub enum CustomChatCompletionsPacketAction {
     Add,
     Remove,
@@ -880,6 +915,8 @@ pub enum CustomChatCompletionsPacketAction {
 
 #[derive(Encode, Decode, Clone, Debug)]
 #[using(u8)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "arbitrary", derive(crate::arbitrary::Arbitrary))]
 pub enum GameEventPacketEvent {
     NoRespawnBlockAvailable,
     StartRaining,
@@ -897,15 +934,18 @@ pub enum GameEventPacketEvent {
 
 #[derive(Encode, Decode, Clone, Derivative)]
 #[derivative(Debug)]
-pub struct LevelChunkPacketData {
-    #[derivative(Debug = "ignore")]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "arbitrary", derive(crate::arbitrary::Arbitrary))]
+pub struct LevelChunkPacketData<'a> {
     pub heightmaps: Nbt<serde_value::Value>,
-    #[derivative(Debug = "ignore")]
-    pub buffer: Vec<u8>,
+    #[derivative(Debug(format_with = "debug_bytes"))]
+    pub buffer: Cow<'a, [u8]>,
     pub block_entities_data: Vec<LevelChunkPacketDataBlockEntity>,
 }
 
 #[derive(Encode, Decode, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "arbitrary", derive(crate::arbitrary::Arbitrary))]
 pub struct LevelChunkPacketDataBlockEntity {
     pub xz: i8,
     pub y: i16,
@@ -916,23 +956,23 @@ pub struct LevelChunkPacketDataBlockEntity {
 
 #[derive(Encode, Decode, Clone, Derivative)]
 #[derivative(Debug)]
-pub struct LightUpdatePacketData {
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "arbitrary", derive(crate::arbitrary::Arbitrary))]
+pub struct LightUpdatePacketData<'a> {
     pub trust_edges: bool,
-    #[derivative(Debug = "ignore")]
     pub sky_y_mask: Vec<i64>,
-    #[derivative(Debug = "ignore")]
     pub block_y_mask: Vec<i64>,
-    #[derivative(Debug = "ignore")]
     pub empty_sky_y_mask: Vec<i64>,
-    #[derivative(Debug = "ignore")]
     pub empty_block_y_mask: Vec<i64>,
-    #[derivative(Debug = "ignore")]
-    pub sky_updates: Vec<Vec<u8>>,
-    #[derivative(Debug = "ignore")]
-    pub block_updates: Vec<Vec<u8>>,
+    #[derivative(Debug(format_with = "debug_byte_lists"))]
+    pub sky_updates: Vec<Cow<'a, [u8]>>,
+    #[derivative(Debug(format_with = "debug_byte_lists"))]
+    pub block_updates: Vec<Cow<'a, [u8]>>,
 }
 
 #[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "arbitrary", derive(crate::arbitrary::Arbitrary))]
 pub struct PlayerAbilitiesPacket {
     pub invulnerable: bool,
     pub is_flying: bool,
@@ -980,11 +1020,13 @@ impl Decode<'_> for PlayerAbilitiesPacket {
 }
 
 #[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct PlayerInfoUpdatePacket {
     pub entries: Vec<PlayerInfoUpdatePacketEntry>,
 }
 
 #[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct PlayerInfoUpdatePacketEntry {
     pub profile_id: Uuid,
     pub profile: Option<User>,
@@ -997,6 +1039,8 @@ pub struct PlayerInfoUpdatePacketEntry {
 
 impl Encode for PlayerInfoUpdatePacket {
     fn encode(&self, output: &mut impl Write) -> Result<()> {
+        // the first entry determines the updated fields, which all other
+        // entries have to contain as well
         if let Some(first_entry) = self.entries.first() {
             let add_player = first_entry.profile.is_some();
             let initialize_chat = first_entry.chat_session.is_some();
@@ -1027,24 +1071,40 @@ impl Encode for PlayerInfoUpdatePacket {
             VarI32(self.entries.len() as i32).encode(output)?;
             for entry in &self.entries {
                 if add_player {
-                    entry.profile.as_ref().unwrap().encode(output)?;
+                    entry
+                        .profile
+                        .as_ref()
+                        .ok_or(Error::Unexpected)?
+                        .encode(output)?;
                 } else {
                     entry.profile_id.encode(output)?;
                 }
                 if initialize_chat {
-                    entry.chat_session.as_ref().unwrap().encode(output)?;
+                    entry
+                        .chat_session
+                        .as_ref()
+                        .ok_or(Error::Unexpected)?
+                        .encode(output)?;
                 }
                 if update_game_mode {
-                    entry.game_mode.as_ref().unwrap().encode(output)?;
+                    entry
+                        .game_mode
+                        .as_ref()
+                        .ok_or(Error::Unexpected)?
+                        .encode(output)?;
                 }
                 if update_listed {
-                    entry.listed.unwrap().encode(output)?;
+                    entry.listed.ok_or(Error::Unexpected)?.encode(output)?;
                 }
                 if update_latency {
-                    entry.latency.unwrap().encode(output)?;
+                    entry.latency.ok_or(Error::Unexpected)?.encode(output)?;
                 }
                 if update_display_name {
-                    entry.display_name.as_ref().unwrap().encode(output)?;
+                    entry
+                        .display_name
+                        .as_ref()
+                        .ok_or(Error::Unexpected)?
+                        .encode(output)?;
                 }
             }
         } else {
@@ -1064,8 +1124,9 @@ impl Decode<'_> for PlayerInfoUpdatePacket {
         let update_listed = actions & (1 << 3) != 0;
         let update_latency = actions & (1 << 4) != 0;
         let update_display_name = actions & (1 << 5) != 0;
-        let entry_count = VarI32::decode(input)?.0;
-        let mut entries = Vec::with_capacity(entry_count as usize);
+        let entry_count = decode_length(input, usize::MAX)?;
+        let mut entries =
+            Vec::with_capacity(capacity::<PlayerInfoUpdatePacketEntry>(entry_count, input));
         for _ in 0..entry_count {
             let (profile_id, profile) = if add_player {
                 let profile = User::decode(input)?;
@@ -1112,7 +1173,47 @@ impl Decode<'_> for PlayerInfoUpdatePacket {
     }
 }
 
+/// All entries update the same fields.
+#[cfg(feature = "arbitrary")]
+impl crate::arbitrary::Arbitrary for PlayerInfoUpdatePacket {
+    fn arbitrary(input: &mut crate::arbitrary::Unstructured) -> crate::arbitrary::Result<Self> {
+        use crate::arbitrary::Arbitrary;
+
+        fn field<T: Arbitrary>(
+            updated: bool,
+            input: &mut crate::arbitrary::Unstructured,
+        ) -> crate::arbitrary::Result<Option<T>> {
+            Ok(if updated {
+                Some(T::arbitrary(input)?)
+            } else {
+                None
+            })
+        }
+
+        let actions: [bool; 6] = Arbitrary::arbitrary(input)?;
+        let mut entries = vec![];
+        while input.arbitrary()? {
+            let profile: Option<User> = field(actions[0], input)?;
+            entries.push(PlayerInfoUpdatePacketEntry {
+                profile_id: match &profile {
+                    Some(profile) => profile.id,
+                    None => Arbitrary::arbitrary(input)?,
+                },
+                profile,
+                chat_session: field(actions[1], input)?,
+                game_mode: field(actions[2], input)?,
+                listed: field(actions[3], input)?,
+                latency: field(actions[4], input)?,
+                display_name: field(actions[5], input)?,
+            });
+        }
+        Ok(Self { entries })
+    }
+}
+
 #[derive(Encode, Decode, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "arbitrary", derive(crate::arbitrary::Arbitrary))]
 pub struct PlayerLookAtPacketAtEntity {
     #[using(VarI32)]
     pub entity: i32,
@@ -1120,6 +1221,7 @@ pub struct PlayerLookAtPacketAtEntity {
 }
 
 #[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct SectionBlocksUpdatePacket {
     pub section_pos: IVec3,
     pub suppress_light_updates: bool,
@@ -1160,7 +1262,26 @@ impl Decode<'_> for SectionBlocksUpdatePacket {
     }
 }
 
+/// The section position fits into 22 bits horizontally and 20 bits vertically.
+#[cfg(feature = "arbitrary")]
+impl crate::arbitrary::Arbitrary for SectionBlocksUpdatePacket {
+    fn arbitrary(input: &mut crate::arbitrary::Unstructured) -> crate::arbitrary::Result<Self> {
+        use crate::arbitrary::Arbitrary;
+
+        Ok(Self {
+            section_pos: IVec3::new(
+                input.int_in_range(-0x200000..=0x1FFFFF)?,
+                input.int_in_range(-0x80000..=0x7FFFF)?,
+                input.int_in_range(-0x200000..=0x1FFFFF)?,
+            ),
+            suppress_light_updates: Arbitrary::arbitrary(input)?,
+            position_and_states: Arbitrary::arbitrary(input)?,
+        })
+    }
+}
+
 #[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct SectionBlocksUpdatePacketPositionAndState {
     pub x: u8,
     pub y: u8,
@@ -1192,7 +1313,22 @@ impl Decode<'_> for SectionBlocksUpdatePacketPositionAndState {
     }
 }
 
+/// Positions are within the section.
+#[cfg(feature = "arbitrary")]
+impl crate::arbitrary::Arbitrary for SectionBlocksUpdatePacketPositionAndState {
+    fn arbitrary(input: &mut crate::arbitrary::Unstructured) -> crate::arbitrary::Result<Self> {
+        Ok(Self {
+            x: input.int_in_range(0x0..=0xF)?,
+            y: input.int_in_range(0x0..=0xF)?,
+            z: input.int_in_range(0x0..=0xF)?,
+            block_state: input.int_in_range(0x0..=0x1FFFFFFFFFFFF)?,
+        })
+    }
+}
+
 #[derive(Encode, Decode, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "arbitrary", derive(crate::arbitrary::Arbitrary))]
 pub enum RecipePacket {
     Init {
         crafting_recipe_book_open: bool,
@@ -1230,12 +1366,26 @@ pub enum RecipePacket {
     },
 }
 
-pub struct SetEquipmentPacketSlots(HashMap<EquipmentSlot, Option<ItemStack>>);
+/// Slots in the order they have been received.
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SetEquipmentPacketSlots(IndexMap<EquipmentSlot, Option<ItemStack>>);
 
 impl Encode for SetEquipmentPacketSlots {
     fn encode(&self, output: &mut impl Write) -> Result<()> {
-        if !self.0.is_empty() {
-            let mut slots = self.0.iter().peekable();
+        EncodeAs::<SetEquipmentPacketSlots>::encode_as(&self.0, output)
+    }
+}
+
+impl Decode<'_> for SetEquipmentPacketSlots {
+    fn decode(input: &mut &'_ [u8]) -> Result<Self> {
+        Ok(Self(DecodeAs::<SetEquipmentPacketSlots>::decode_as(input)?))
+    }
+}
+
+impl EncodeAs<SetEquipmentPacketSlots> for IndexMap<EquipmentSlot, Option<ItemStack>> {
+    fn encode_as(&self, output: &mut impl Write) -> Result<()> {
+        if !self.is_empty() {
+            let mut slots = self.iter().peekable();
             while let Some((&slot, item)) = slots.next() {
                 (u8::from(slot) | if slots.peek().is_some() { 0x80 } else { 0x00 })
                     .encode(output)?;
@@ -1246,24 +1396,44 @@ impl Encode for SetEquipmentPacketSlots {
     }
 }
 
-impl Decode<'_> for SetEquipmentPacketSlots {
-    fn decode(input: &mut &'_ [u8]) -> Result<Self> {
-        let mut slots = HashMap::new();
+impl DecodeAs<'_, SetEquipmentPacketSlots> for IndexMap<EquipmentSlot, Option<ItemStack>> {
+    fn decode_as(input: &mut &'_ [u8]) -> Result<Self> {
+        let mut slots = IndexMap::new();
         loop {
             let slot_and_next_bit = u8::decode(input)?;
             slots.insert(
-                EquipmentSlot::try_from(slot_and_next_bit & 0x7F).unwrap(),
+                EquipmentSlot::try_from(slot_and_next_bit & 0x7F)
+                    .map_err(|error| Error::UnknownVariant(error.number as i32))?,
                 Decode::decode(input)?,
             );
             if slot_and_next_bit & 0x80 == 0 {
                 break;
             }
         }
+        Ok(slots)
+    }
+}
+
+/// At least one slot is always sent.
+#[cfg(feature = "arbitrary")]
+impl crate::arbitrary::Arbitrary for SetEquipmentPacketSlots {
+    fn arbitrary(input: &mut crate::arbitrary::Unstructured) -> crate::arbitrary::Result<Self> {
+        use crate::arbitrary::Arbitrary;
+
+        let mut slots = IndexMap::arbitrary(input)?;
+        if slots.is_empty() {
+            slots.insert(
+                EquipmentSlot::arbitrary(input)?,
+                Arbitrary::arbitrary(input)?,
+            );
+        }
         Ok(Self(slots))
     }
 }
 
 #[derive(Encode, Decode, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "arbitrary", derive(crate::arbitrary::Arbitrary))]
 pub enum SetObjectivePacketMethod {
     Add {
         display_name: Json<Component>,
@@ -1279,6 +1449,8 @@ pub enum SetObjectivePacketMethod {
 }
 
 #[derive(Encode, Decode, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "arbitrary", derive(crate::arbitrary::Arbitrary))]
 pub enum SetPlayerTeamPacketMethod {
     Add {
         display_name: Json<Component>,
@@ -1311,6 +1483,8 @@ pub enum SetPlayerTeamPacketMethod {
 }
 
 #[derive(Encode, Decode, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "arbitrary", derive(crate::arbitrary::Arbitrary))]
 pub enum SetScorePacketMethod {
     Change {
         objective_name: String,
@@ -1323,6 +1497,8 @@ pub enum SetScorePacketMethod {
 }
 
 #[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "arbitrary", derive(crate::arbitrary::Arbitrary))]
 pub struct StopSoundPacket {
     pub source: Option<SoundSource>,
     pub name: Option<String>,
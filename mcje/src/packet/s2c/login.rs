@@ -1,10 +1,17 @@
 use crate::{
+    packet::PacketName,
     types::{Component, Json, TrailingBytes, User, VarI32},
     Decode, Encode,
 };
 
-#[derive(Encode, Decode, Clone, Debug)]
-pub enum LoginPacket {
+#[derive(Encode, Decode, PacketName, Clone, Debug)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(tag = "type", content = "data")
+)]
+#[cfg_attr(feature = "arbitrary", derive(crate::arbitrary::Arbitrary))]
+pub enum LoginPacket<'a> {
     LoginDisconnect {
         reason: Json<Component>,
     },
@@ -24,6 +31,6 @@ pub enum LoginPacket {
         #[using(VarI32)]
         transaction_id: i32,
         identifier: String,
-        data: TrailingBytes<{ 1 << 20 }>,
+        data: TrailingBytes<'a, { 1 << 20 }>,
     },
 }
@@ -1,9 +1,16 @@
 use crate::{
+    packet::PacketName,
     types::{Json, Status},
     Decode, Encode,
 };
 
-#[derive(Encode, Decode, Clone, Debug)]
+#[derive(Encode, Decode, PacketName, Clone, Debug)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(tag = "type", content = "data")
+)]
+#[cfg_attr(feature = "arbitrary", derive(crate::arbitrary::Arbitrary))]
 pub enum StatusPacket {
     StatusResponse { status: Json<Status> },
     PongResponse { time: i64 },
@@ -0,0 +1,300 @@
+use std::{
+    io::ErrorKind,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+use futures::{stream, StreamExt};
+use tokio::{
+    io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt},
+    net::TcpStream,
+};
+
+use crate::{
+    connection::Connection,
+    packet::{c2s, s2c},
+    types::{Intention, Status},
+    Error, Result, PROTOCOL_VERSION,
+};
+
+pub const DEFAULT_PORT: u16 = 25565;
+
+#[derive(Clone, Debug)]
+pub struct Ping {
+    pub status: Status,
+    /// Round-trip time of the ping request.
+    pub latency: Duration,
+}
+
+/// Status as reported by servers prior to 1.7.
+#[derive(Clone, Debug, PartialEq)]
+pub struct LegacyPing {
+    /// Only reported by servers since 1.4.
+    pub protocol: Option<i32>,
+    /// Only reported by servers since 1.4.
+    pub version: Option<String>,
+    pub motd: String,
+    pub online: i32,
+    pub max: i32,
+    pub latency: Duration,
+}
+
+/// Requests the status of the server at the given address, which consists of
+/// the host name and an optional port.
+pub async fn ping(addr: &str) -> Result<Ping> {
+    let (host_name, port) = split_addr(addr);
+    let socket = TcpStream::connect((host_name, port)).await?;
+    socket.set_nodelay(true)?;
+    ping_stream(socket, host_name, port).await
+}
+
+/// Requests the status over an already established stream.
+pub async fn ping_stream<S: AsyncRead + AsyncWrite + Unpin>(
+    stream: S,
+    host_name: &str,
+    port: u16,
+) -> Result<Ping> {
+    let mut connection = Connection::new(stream);
+    connection
+        .send(&c2s::HandshakePacket::Intention {
+            protocol_version: PROTOCOL_VERSION,
+            host_name: host_name.to_string(),
+            port,
+            intention: Intention::Status,
+        })
+        .await?;
+    connection.send(&c2s::StatusPacket::StatusRequest).await?;
+    let s2c::StatusPacket::StatusResponse { status } = connection.receive().await?.decode()? else {
+        return Err(Error::Unexpected);
+    };
+
+    let time = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_millis() as i64;
+    let sent = Instant::now();
+    connection
+        .send(&c2s::StatusPacket::PingRequest { time })
+        .await?;
+    match connection.receive().await?.decode()? {
+        s2c::StatusPacket::PongResponse { time: pong_time } if pong_time == time => Ok(Ping {
+            status: status.0,
+            latency: sent.elapsed(),
+        }),
+        _ => Err(Error::Unexpected),
+    }
+}
+
+/// Requests the status using the legacy server list ping (0xFE), which is
+/// understood by servers prior to 1.7 and most newer servers.
+pub async fn ping_legacy(addr: &str) -> Result<LegacyPing> {
+    let (host_name, port) = split_addr(addr);
+    let mut socket = TcpStream::connect((host_name, port)).await?;
+    socket.set_nodelay(true)?;
+
+    // 1.6 format, older servers only read the first or first two bytes
+    let mut request = vec![0xFE, 0x01, 0xFA];
+    write_utf16(&mut request, "MC|PingHost");
+    let host_name_utf16 = host_name.encode_utf16().collect::<Vec<_>>();
+    request.extend_from_slice(&(7 + host_name_utf16.len() as u16 * 2).to_be_bytes());
+    request.push(74);
+    write_utf16(&mut request, host_name);
+    request.extend_from_slice(&(port as i32).to_be_bytes());
+    let sent = Instant::now();
+    socket.write_all(&request).await?;
+
+    if socket.read_u8().await? != 0xFF {
+        return Err(Error::Unexpected);
+    }
+    let length = socket.read_u16().await? as usize;
+    let mut response = vec![0; length * 2];
+    socket.read_exact(&mut response).await?;
+    let latency = sent.elapsed();
+    let response = String::from_utf16_lossy(
+        &response
+            .chunks_exact(2)
+            .map(|char| u16::from_be_bytes([char[0], char[1]]))
+            .collect::<Vec<_>>(),
+    );
+    parse_legacy(&response, latency)
+}
+
+/// Pings all given addresses, with at most `concurrency` pings in flight and
+/// each ping being aborted after `timeout`. The results are returned in order
+/// of completion.
+pub async fn ping_all<I>(
+    addrs: I,
+    concurrency: usize,
+    timeout: Duration,
+) -> Vec<(String, Result<Ping>)>
+where
+    I: IntoIterator,
+    I::Item: Into<String>,
+{
+    stream::iter(addrs.into_iter().map(Into::into))
+        .map(|addr: String| async move {
+            let result = match tokio::time::timeout(timeout, ping(&addr)).await {
+                Ok(result) => result,
+                Err(_) => Err(std::io::Error::from(ErrorKind::TimedOut).into()),
+            };
+            (addr, result)
+        })
+        .buffer_unordered(concurrency)
+        .collect()
+        .await
+}
+
+fn split_addr(addr: &str) -> (&str, u16) {
+    let (host_name, port) = match addr.rsplit_once(':') {
+        // don't split IPv6 addresses without port
+        Some((host_name, port)) if !host_name.contains(':') || host_name.ends_with(']') => {
+            match port.parse() {
+                Ok(port) => (host_name, port),
+                Err(_) => (addr, DEFAULT_PORT),
+            }
+        }
+        _ => (addr, DEFAULT_PORT),
+    };
+    (
+        host_name.trim_start_matches('[').trim_end_matches(']'),
+        port,
+    )
+}
+
+fn write_utf16(output: &mut Vec<u8>, value: &str) {
+    let value = value.encode_utf16().collect::<Vec<_>>();
+    output.extend_from_slice(&(value.len() as u16).to_be_bytes());
+    for char in value {
+        output.extend_from_slice(&char.to_be_bytes());
+    }
+}
+
+fn parse_legacy(response: &str, latency: Duration) -> Result<LegacyPing> {
+    fn parse_int(value: Option<&str>) -> Result<i32> {
+        value
+            .and_then(|value| value.parse().ok())
+            .ok_or(Error::Unexpected)
+    }
+
+    Ok(if let Some(response) = response.strip_prefix("§1\0") {
+        // 1.4 and later: protocol, version, motd, online and max separated by NUL
+        let mut fields = response.split('\0');
+        LegacyPing {
+            protocol: Some(parse_int(fields.next())?),
+            version: Some(fields.next().ok_or(Error::Unexpected)?.to_string()),
+            motd: fields.next().ok_or(Error::Unexpected)?.to_string(),
+            online: parse_int(fields.next())?,
+            max: parse_int(fields.next())?,
+            latency,
+        }
+    } else {
+        // Beta 1.8 to 1.3: motd, online and max separated by section signs
+        let mut fields = response.rsplitn(3, '§');
+        let max = parse_int(fields.next())?;
+        let online = parse_int(fields.next())?;
+        LegacyPing {
+            protocol: None,
+            version: None,
+            motd: fields.next().ok_or(Error::Unexpected)?.to_string(),
+            online,
+            max,
+            latency,
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use serde_json::json;
+    use tokio::net::TcpListener;
+
+    use crate::{
+        ping::{parse_legacy, ping_all, ping_stream, split_addr, LegacyPing, DEFAULT_PORT},
+        server::tests::server,
+        PROTOCOL_VERSION, VERSION,
+    };
+
+    #[test]
+    fn split() {
+        assert_eq!(split_addr("localhost"), ("localhost", 25565));
+        assert_eq!(split_addr("localhost:25566"), ("localhost", 25566));
+        assert_eq!(split_addr("::1"), ("::1", 25565));
+        assert_eq!(split_addr("[::1]:25566"), ("::1", 25566));
+    }
+
+    #[test]
+    fn legacy() {
+        assert_eq!(
+            parse_legacy(
+                "§1\u{0}78\u{0}1.6.4\u{0}A Minecraft Server\u{0}1\u{0}20",
+                Duration::ZERO
+            )
+            .unwrap(),
+            LegacyPing {
+                protocol: Some(78),
+                version: Some("1.6.4".to_string()),
+                motd: "A Minecraft Server".to_string(),
+                online: 1,
+                max: 20,
+                latency: Duration::ZERO,
+            }
+        );
+        assert_eq!(
+            parse_legacy("A §aMinecraft Server§1§20", Duration::ZERO).unwrap(),
+            LegacyPing {
+                protocol: None,
+                version: None,
+                motd: "A §aMinecraft Server".to_string(),
+                online: 1,
+                max: 20,
+                latency: Duration::ZERO,
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn status() {
+        let server = server();
+        let (client, server_stream) = tokio::io::duplex(1 << 16);
+        tokio::spawn(async move { server.handle(server_stream).await });
+        let ping = ping_stream(client, "localhost", DEFAULT_PORT)
+            .await
+            .unwrap();
+        assert_eq!(
+            serde_json::to_value(&ping.status).unwrap(),
+            json!({
+                "players": { "max": 20, "online": 0 },
+                "version": { "name": VERSION, "protocol": PROTOCOL_VERSION },
+            })
+        );
+        assert!(ping.latency < Duration::from_secs(1));
+    }
+
+    #[tokio::test]
+    async fn unreachable() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let reachable = listener.local_addr().unwrap().to_string();
+        tokio::spawn(server().run(listener));
+        // nothing listens on the port anymore
+        let unreachable = TcpListener::bind("127.0.0.1:0")
+            .await
+            .unwrap()
+            .local_addr()
+            .unwrap()
+            .to_string();
+
+        let mut results = ping_all(
+            [reachable.clone(), unreachable.clone()],
+            2,
+            Duration::from_secs(5),
+        )
+        .await;
+        results.sort_by_key(|(addr, _)| *addr != reachable);
+        let [(first, Ok(ping)), (second, Err(_))] = results.as_slice() else {
+            panic!("one ping has to succeed");
+        };
+        assert_eq!((first, second), (&reachable, &unreachable));
+        assert_eq!(ping.status.players.as_ref().unwrap().max, 20);
+    }
+}
@@ -0,0 +1,392 @@
+//! Stitches textures into power-of-two sheets. Animated textures are split
+//! into their frames, which are placed next to each other, and interpolated
+//! animations are baked into one frame per tick.
+
+use std::collections::BTreeMap;
+
+use image::{imageops, ImageFormat, RgbaImage};
+use serde::Serialize;
+
+use crate::{
+    resource_pack::{mcmeta::McMeta, ResourcePack},
+    Error, Result,
+};
+
+/// Stitched sheets, and where the sprites of the textures are located.
+pub struct Atlas {
+    pub sheets: Vec<RgbaImage>,
+    /// Sprites by texture location, e.g. `minecraft:block/stone`.
+    pub sprites: BTreeMap<String, Sprite>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct Sprite {
+    /// Index of the sheet all frames are on.
+    pub sheet: usize,
+    /// Frames in order of playback, which loop. Textures which are not
+    /// animated have one frame.
+    pub frames: Vec<SpriteFrame>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct SpriteFrame {
+    /// Region on the sheet in pixels, as `[x, y, width, height]`.
+    pub region: [u32; 4],
+    /// Region on the sheet as `[u0, v0, u1, v1]`.
+    pub uv: [f32; 4],
+    /// Time the frame is shown in ticks.
+    pub time: u32,
+}
+
+impl Atlas {
+    /// Returns the UV lookup table, the sprites by texture location.
+    pub fn to_json(&self) -> serde_json::Value {
+        serde_json::to_value(&self.sprites).unwrap()
+    }
+}
+
+/// Collects textures, which are stitched by [`AtlasBuilder::build`].
+pub struct AtlasBuilder {
+    max_size: u32,
+    textures: BTreeMap<String, Texture>,
+}
+
+struct Texture {
+    /// Distinct frames, in order of their first use.
+    images: Vec<RgbaImage>,
+    /// Index into the images and time of the frames in order of playback.
+    frames: Vec<(usize, u32)>,
+}
+
+impl AtlasBuilder {
+    /// Sheets are at most `max_size` pixels wide and high, which is rounded
+    /// down to a power of two.
+    pub fn new(max_size: u32) -> Self {
+        Self {
+            max_size: max_size.checked_ilog2().map_or(0, |log| 1 << log),
+            textures: Default::default(),
+        }
+    }
+
+    /// Adds all textures of the pack in the given directories, e.g. `block`
+    /// and `item`, including their animation.
+    pub fn add_pack(&mut self, resource_pack: &mut ResourcePack, kinds: &[&str]) -> Result<()> {
+        for path in resource_pack.paths()? {
+            let Some((namespace, texture)) = path
+                .strip_prefix("assets/")
+                .and_then(|path| path.strip_suffix(".png"))
+                .and_then(|path| path.split_once("/textures/"))
+            else {
+                continue;
+            };
+            if !kinds.iter().any(|kind| {
+                texture
+                    .strip_prefix(kind)
+                    .is_some_and(|path| path.starts_with('/'))
+            }) {
+                continue;
+            }
+            let image =
+                image::load_from_memory_with_format(&resource_pack.read(&path)?, ImageFormat::Png)
+                    .map_err(|_| Error::InvalidImage)?
+                    .into_rgba8();
+            let mcmeta = match resource_pack.read(&format!("{path}.mcmeta")) {
+                Ok(data) => Some(serde_json::from_slice::<McMeta>(&data)?),
+                Err(Error::MissingResource(_)) => None,
+                Err(error) => return Err(error),
+            };
+            self.add(format!("{namespace}:{texture}"), image, mcmeta.as_ref())?;
+        }
+        Ok(())
+    }
+
+    /// Adds the texture, which is split into frames if it has an animation.
+    pub fn add(
+        &mut self,
+        location: impl Into<String>,
+        image: RgbaImage,
+        mcmeta: Option<&McMeta>,
+    ) -> Result<()> {
+        let Some(animation) = mcmeta.and_then(|mcmeta| mcmeta.animation.as_ref()) else {
+            self.textures.insert(
+                location.into(),
+                Texture {
+                    images: vec![image],
+                    frames: vec![(0, 1)],
+                },
+            );
+            return Ok(());
+        };
+
+        // frames are placed left to right, and top to bottom
+        let (frame_width, frame_height) = animation.frame_size(image.width(), image.height());
+        if frame_width == 0
+            || frame_height == 0
+            || !image.width().is_multiple_of(frame_width)
+            || !image.height().is_multiple_of(frame_height)
+        {
+            return Err(Error::InvalidImage);
+        }
+        let columns = image.width() / frame_width;
+        let frame_count = columns * (image.height() / frame_height);
+        let frame = |index: u32| {
+            imageops::crop_imm(
+                &image,
+                index % columns * frame_width,
+                index / columns * frame_height,
+                frame_width,
+                frame_height,
+            )
+            .to_image()
+        };
+
+        let frames = animation.frames(frame_count);
+        if frames.iter().any(|(index, _)| *index >= frame_count) {
+            return Err(Error::InvalidImage);
+        }
+        let mut texture = Texture {
+            images: vec![],
+            frames: vec![],
+        };
+        let mut image_indices = BTreeMap::new();
+        for (i, &(index, time)) in frames.iter().enumerate() {
+            let image_index = *image_indices.entry(index).or_insert_with(|| {
+                texture.images.push(frame(index));
+                texture.images.len() - 1
+            });
+            if !animation.interpolate || time <= 1 {
+                texture.frames.push((image_index, time));
+                continue;
+            }
+            // blends towards the next frame in each tick
+            let next = frame(frames[(i + 1) % frames.len()].0);
+            texture.frames.push((image_index, 1));
+            for tick in 1..time {
+                let mut blended = texture.images[image_index].clone();
+                let delta = tick as f32 / time as f32;
+                for (pixel, next_pixel) in blended.pixels_mut().zip(next.pixels()) {
+                    // like in the vanilla client only the color is blended, and the
+                    // alpha of the current frame is kept
+                    for (channel, next_channel) in pixel.0.iter_mut().zip(next_pixel.0).take(3) {
+                        *channel = (*channel as f32 * (1.0 - delta) + next_channel as f32 * delta)
+                            .round() as u8;
+                    }
+                }
+                texture.images.push(blended);
+                texture.frames.push((texture.images.len() - 1, 1));
+            }
+        }
+        self.textures.insert(location.into(), texture);
+        Ok(())
+    }
+
+    /// Packs the textures into as few sheets as possible, fails with
+    /// [`Error::InvalidImage`] if a texture doesn't fit into a sheet.
+    pub fn build(self) -> Result<Atlas> {
+        // the frames of a texture are placed in a grid, which is packed as one
+        // rectangle
+        let mut rectangles = self
+            .textures
+            .iter()
+            .map(|(location, texture)| {
+                let columns = (texture.images.len() as f32).sqrt().ceil() as u32;
+                let rows = (texture.images.len() as u32).div_ceil(columns);
+                let (width, height) = texture.images[0].dimensions();
+                (location, texture, columns, width * columns, height * rows)
+            })
+            .collect::<Vec<_>>();
+        // higher rectangles first, which keeps the shelves tight
+        rectangles.sort_by(|a, b| b.4.cmp(&a.4).then(b.3.cmp(&a.3)));
+
+        let mut sheets = Vec::<Sheet>::new();
+        let mut placements = vec![];
+        for (location, texture, columns, width, height) in rectangles {
+            if width > self.max_size || height > self.max_size {
+                return Err(Error::InvalidImage);
+            }
+            let placement = sheets
+                .iter_mut()
+                .enumerate()
+                .find_map(|(i, sheet)| Some((i, sheet.place(width, height, self.max_size)?)))
+                .unwrap_or_else(|| {
+                    let mut sheet = Sheet::default();
+                    let position = sheet.place(width, height, self.max_size).unwrap();
+                    sheets.push(sheet);
+                    (sheets.len() - 1, position)
+                });
+            placements.push((location, texture, columns, placement));
+        }
+
+        let mut images = sheets
+            .iter()
+            .map(|sheet| {
+                RgbaImage::new(
+                    sheet.width.next_power_of_two(),
+                    sheet.height.next_power_of_two(),
+                )
+            })
+            .collect::<Vec<_>>();
+        let mut sprites = BTreeMap::new();
+        for (location, texture, columns, (sheet, (x, y))) in placements {
+            let image = &mut images[sheet];
+            let (sheet_width, sheet_height) = (image.width() as f32, image.height() as f32);
+            let regions = texture
+                .images
+                .iter()
+                .enumerate()
+                .map(|(i, frame)| {
+                    let (width, height) = frame.dimensions();
+                    let frame_x = x + i as u32 % columns * width;
+                    let frame_y = y + i as u32 / columns * height;
+                    imageops::replace(image, frame, frame_x as i64, frame_y as i64);
+                    [frame_x, frame_y, width, height]
+                })
+                .collect::<Vec<_>>();
+            let frames = texture
+                .frames
+                .iter()
+                .map(|&(image_index, time)| {
+                    let region = regions[image_index];
+                    SpriteFrame {
+                        region,
+                        uv: [
+                            region[0] as f32 / sheet_width,
+                            region[1] as f32 / sheet_height,
+                            (region[0] + region[2]) as f32 / sheet_width,
+                            (region[1] + region[3]) as f32 / sheet_height,
+                        ],
+                        time,
+                    }
+                })
+                .collect();
+            sprites.insert(location.clone(), Sprite { sheet, frames });
+        }
+
+        Ok(Atlas {
+            sheets: images,
+            sprites,
+        })
+    }
+}
+
+/// Sheet which is filled by shelves, rows of rectangles which are at most as
+/// high as the first one.
+#[derive(Default)]
+struct Sheet {
+    // y, height and used width
+    shelves: Vec<(u32, u32, u32)>,
+    width: u32,
+    height: u32,
+}
+
+impl Sheet {
+    fn place(&mut self, width: u32, height: u32, max_size: u32) -> Option<(u32, u32)> {
+        let shelf = match self
+            .shelves
+            .iter_mut()
+            .find(|(_, shelf_height, used_width)| {
+                height <= *shelf_height && used_width + width <= max_size
+            }) {
+            Some(shelf) => shelf,
+            None => {
+                if self.height + height > max_size {
+                    return None;
+                }
+                self.shelves.push((self.height, height, 0));
+                self.height += height;
+                self.shelves.last_mut().unwrap()
+            }
+        };
+        let position = (shelf.2, shelf.0);
+        shelf.2 += width;
+        self.width = self.width.max(shelf.2);
+        Some(position)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use image::{Rgba, RgbaImage};
+
+    use crate::resource_pack::{
+        atlas::AtlasBuilder,
+        mcmeta::{Animation, Frame, McMeta},
+    };
+
+    #[test]
+    fn stitch() {
+        let mut builder = AtlasBuilder::new(16);
+        builder
+            .add(
+                "minecraft:block/stone",
+                RgbaImage::from_pixel(8, 8, Rgba([0x80; 4])),
+                None,
+            )
+            .unwrap();
+        // two frames with different alpha, of which the second is shown first
+        let mut animated = RgbaImage::from_pixel(4, 8, Rgba([0x00, 0x00, 0x00, 0xFF]));
+        for (_, y, pixel) in animated.enumerate_pixels_mut() {
+            if y >= 4 {
+                *pixel = Rgba([0xFF, 0xFF, 0xFF, 0x40]);
+            }
+        }
+        let mcmeta = McMeta {
+            animation: Some(Animation {
+                interpolate: true,
+                width: None,
+                height: None,
+                frametime: 1,
+                frames: vec![Frame::IndexTime { index: 1, time: 2 }, Frame::Index(0)],
+                extra: Default::default(),
+            }),
+            ..Default::default()
+        };
+        builder
+            .add("minecraft:block/lava", animated, Some(&mcmeta))
+            .unwrap();
+        let atlas = builder.build().unwrap();
+        assert_eq!(atlas.sheets.len(), 1);
+        assert_eq!(atlas.sheets[0].dimensions(), (16, 8));
+
+        let stone = &atlas.sprites["minecraft:block/stone"];
+        assert_eq!(stone.frames.len(), 1);
+        assert_eq!(stone.frames[0].region, [8, 0, 8, 8]);
+        assert_eq!(stone.frames[0].uv, [0.5, 0.0, 1.0, 1.0]);
+
+        // the first frame is interpolated over two ticks, keeping its alpha
+        let lava = &atlas.sprites["minecraft:block/lava"];
+        assert_eq!(
+            lava.frames
+                .iter()
+                .map(|frame| frame.time)
+                .collect::<Vec<_>>(),
+            [1, 1, 1]
+        );
+        let pixel = |frame: usize| {
+            let region = lava.frames[frame].region;
+            atlas.sheets[lava.sheet].get_pixel(region[0], region[1]).0
+        };
+        assert_eq!(pixel(0), [0xFF, 0xFF, 0xFF, 0x40]);
+        assert_eq!(pixel(1), [0x80, 0x80, 0x80, 0x40]);
+        assert_eq!(pixel(2), [0x00, 0x00, 0x00, 0xFF]);
+    }
+
+    #[test]
+    fn max_size() {
+        // two textures would fit next to each other, but the sheet would have
+        // to be 32 pixels wide
+        let mut builder = AtlasBuilder::new(24);
+        for location in ["minecraft:block/dirt", "minecraft:block/stone"] {
+            builder.add(location, RgbaImage::new(12, 12), None).unwrap();
+        }
+        let atlas = builder.build().unwrap();
+        assert_eq!(
+            atlas
+                .sheets
+                .iter()
+                .map(|sheet| sheet.dimensions())
+                .collect::<Vec<_>>(),
+            [(16, 16), (16, 16)]
+        );
+    }
+}
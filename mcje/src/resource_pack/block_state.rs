@@ -4,7 +4,7 @@ use serde::{Deserialize, Serialize};
 use serde_with::{serde_as, OneOrMany};
 
 #[serde_as]
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 #[serde(rename_all = "lowercase")]
 pub enum BlockState {
     /// Holds the names of all the variants of the block.
@@ -16,11 +16,11 @@ pub enum BlockState {
 }
 
 #[serde_as]
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 #[serde(rename_all = "lowercase")]
 pub struct Variant(#[serde_as(as = "OneOrMany<_>")] pub Vec<Model>);
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 #[serde(rename_all = "lowercase")]
 pub struct Model {
     /// Specifies the path to the model file of the block, in form of a resource
@@ -38,7 +38,7 @@ pub struct Model {
     /// Can be true or false (default). Locks the rotation of the texture of a
     /// block, if set to true. This way the texture does not rotate with the
     /// block when using the x and y-tags above.
-    #[serde(default, skip_serializing_if = "if_false")]
+    #[serde(default, rename = "uvlock", skip_serializing_if = "if_false")]
     pub uv_lock: bool,
 
     /// Sets the probability of the model for being used in the game, defaults
@@ -51,10 +51,14 @@ pub struct Model {
     /// and 50%, respectively.)
     #[serde(default = "default_1", skip_serializing_if = "if_1")]
     pub weight: u32,
+
+    /// Keys which aren't known, which are kept to write them back.
+    #[serde(flatten, skip_serializing_if = "HashMap::is_empty")]
+    pub extra: HashMap<String, serde_json::Value>,
 }
 
 #[serde_as]
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 #[serde(rename_all = "lowercase")]
 pub struct Case {
     /// A list of cases that have to be met for the model to be applied. If
@@ -70,13 +74,67 @@ pub struct Case {
     pub apply: Vec<Model>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 #[serde(untagged)]
 pub enum When {
     One(HashMap<String, String>),
     Many(HashMap<String, Vec<HashMap<String, String>>>),
 }
 
+impl When {
+    /// Returns whether the condition holds for the properties of a block state.
+    /// Values can list alternatives separated by `|`, and are negated by a
+    /// leading `!`.
+    pub fn matches(&self, properties: &HashMap<String, String>) -> bool {
+        fn matches_all(
+            conditions: &HashMap<String, String>,
+            properties: &HashMap<String, String>,
+        ) -> bool {
+            conditions.iter().all(|(key, values)| {
+                let value = properties.get(key).map_or("", String::as_str);
+                match values.strip_prefix('!') {
+                    Some(values) => !values.split('|').any(|expected| expected == value),
+                    None => values.split('|').any(|expected| expected == value),
+                }
+            })
+        }
+
+        match self {
+            When::One(conditions) => matches_all(conditions, properties),
+            When::Many(operations) => {
+                operations
+                    .iter()
+                    .all(|(operator, conditions)| match operator.as_str() {
+                        "OR" => conditions
+                            .iter()
+                            .any(|conditions| matches_all(conditions, properties)),
+                        "AND" => conditions
+                            .iter()
+                            .all(|conditions| matches_all(conditions, properties)),
+                        _ => false,
+                    })
+            }
+        }
+    }
+}
+
+/// Chooses one of the models by their weight, the seed is usually derived
+/// from the position of the block.
+pub fn choose(models: &[Model], seed: u64) -> Option<&Model> {
+    let total_weight = models.iter().map(|model| model.weight as u64).sum::<u64>();
+    if total_weight == 0 {
+        return models.first();
+    }
+    let mut remaining = seed % total_weight;
+    models.iter().find(|model| {
+        if remaining < model.weight as u64 {
+            return true;
+        }
+        remaining -= model.weight as u64;
+        false
+    })
+}
+
 fn default_1() -> u32 {
     1
 }
@@ -0,0 +1,124 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+use serde_with::{formats::PreferOne, serde_as, OneOrMany};
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Font {
+    /// A list of providers, which are queried in order for each character.
+    pub providers: Vec<Provider>,
+
+    /// Keys which aren't known, which are kept to write them back.
+    #[serde(flatten, skip_serializing_if = "HashMap::is_empty")]
+    pub extra: HashMap<String, serde_json::Value>,
+}
+
+#[serde_as]
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Provider {
+    /// Characters from a texture, which is split into a grid by the rows of
+    /// characters. Keys which aren't known, e.g. `filter`, are kept in `extra`
+    /// for all providers.
+    Bitmap {
+        /// The resource location of the used file, starting in
+        /// assets/<namespace>/textures.
+        file: String,
+
+        /// The height of the character, measured in pixels. Can be negative.
+        /// Defaults to 8.
+        #[serde(default = "default_8", skip_serializing_if = "if_8")]
+        height: i32,
+
+        /// The ascent of the character, measured in pixels. This value adds a
+        /// vertical shift to the displayed result.
+        ascent: i32,
+
+        /// A list of strings containing the characters replaced by this
+        /// provider, as well as their order within the texture. All strings
+        /// need to have the same number of characters.
+        chars: Vec<String>,
+
+        #[serde(flatten, skip_serializing_if = "HashMap::is_empty")]
+        extra: HashMap<String, serde_json::Value>,
+    },
+
+    /// Characters from a TrueType or OpenType font.
+    Ttf {
+        /// The resource location of the font file, starting in
+        /// assets/<namespace>/font.
+        file: String,
+
+        /// The distance by which the characters of this provider are shifted,
+        /// as [left, down].
+        #[serde(default, skip_serializing_if = "if_zero")]
+        shift: [f32; 2],
+
+        /// Font size to render at. Defaults to 11.0.
+        #[serde(default = "default_11", skip_serializing_if = "if_11")]
+        size: f32,
+
+        /// Resolution to render at, higher values result in sharper
+        /// characters. Defaults to 1.0.
+        #[serde(default = "default_1", skip_serializing_if = "if_1")]
+        oversample: f32,
+
+        /// String or list of strings of characters to exclude.
+        #[serde_as(as = "OneOrMany<_, PreferOne>")]
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        skip: Vec<String>,
+
+        #[serde(flatten, skip_serializing_if = "HashMap::is_empty")]
+        extra: HashMap<String, serde_json::Value>,
+    },
+
+    /// Characters which are not rendered, but only advance.
+    Space {
+        /// Advances by character, in pixels.
+        advances: HashMap<String, f32>,
+
+        #[serde(flatten, skip_serializing_if = "HashMap::is_empty")]
+        extra: HashMap<String, serde_json::Value>,
+    },
+
+    /// Characters from the unicode pages of the legacy unicode font.
+    LegacyUnicode {
+        /// The resource location of the glyph sizes file.
+        sizes: String,
+
+        /// The resource location of the pages, where `%s` is replaced by the
+        /// page number in hexadecimal.
+        template: String,
+
+        #[serde(flatten, skip_serializing_if = "HashMap::is_empty")]
+        extra: HashMap<String, serde_json::Value>,
+    },
+}
+
+fn default_1() -> f32 {
+    1.0
+}
+
+fn default_8() -> i32 {
+    8
+}
+
+fn default_11() -> f32 {
+    11.0
+}
+
+fn if_1(value: &f32) -> bool {
+    *value == 1.0
+}
+
+fn if_8(value: &i32) -> bool {
+    *value == 8
+}
+
+fn if_11(value: &f32) -> bool {
+    *value == 11.0
+}
+
+fn if_zero(value: &[f32; 2]) -> bool {
+    *value == [0.0; 2]
+}
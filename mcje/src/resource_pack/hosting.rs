@@ -0,0 +1,310 @@
+//! Hosts a resource pack for clients, which download it after receiving the
+//! resource pack packet and report back whether it has been loaded.
+
+use std::{
+    collections::HashMap,
+    io::{Cursor, Write},
+    sync::Arc,
+    time::Duration,
+};
+
+use sha1::{Digest, Sha1};
+use tokio::{
+    io::{AsyncBufReadExt, AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, BufReader},
+    net::TcpListener,
+};
+use uuid::Uuid;
+use zip::{write::FileOptions, CompressionMethod, DateTime, ZipWriter};
+
+use crate::{
+    connection,
+    packet::{
+        c2s::{self, game::ResourcePackPacket},
+        s2c,
+    },
+    resource_pack::ResourcePack,
+    types::{Component, Json},
+    Error, Result,
+};
+
+/// Maximum length of the request head, longer requests are rejected.
+const MAX_REQUEST_LENGTH: usize = 8192;
+
+/// Time a connection has to send its request and receive the pack, before it's
+/// closed.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Zipped resource pack, which is served over HTTP.
+pub struct HostedPack {
+    data: Vec<u8>,
+    hash: String,
+}
+
+impl HostedPack {
+    /// Zips the pack, the same files always result in the same zip file and
+    /// therefore the same hash.
+    pub fn new(resource_pack: &mut ResourcePack) -> Result<Self> {
+        let mut writer = ZipWriter::new(Cursor::new(vec![]));
+        // no timestamps or permissions which differ between checkouts
+        let options = FileOptions::default()
+            .compression_method(CompressionMethod::Deflated)
+            .last_modified_time(DateTime::default())
+            .unix_permissions(0o644);
+        for path in resource_pack.paths()? {
+            let data = resource_pack.read(&path)?;
+            writer.start_file(path, options)?;
+            writer.write_all(&data)?;
+        }
+        Ok(Self::from_data(writer.finish()?.into_inner()))
+    }
+
+    /// Uses an already zipped pack.
+    pub fn from_data(data: Vec<u8>) -> Self {
+        let hash = format!("{:x}", Sha1::digest(&data));
+        Self { data, hash }
+    }
+
+    pub fn data(&self) -> &[u8] {
+        &self.data
+    }
+
+    /// SHA-1 of the zip file as lowercase hex, which is checked by the client.
+    pub fn hash(&self) -> &str {
+        &self.hash
+    }
+
+    /// Returns the packet, which tells the client to download the pack from
+    /// the url.
+    pub fn packet(
+        &self,
+        url: impl Into<String>,
+        required: bool,
+        prompt: Option<Component>,
+    ) -> s2c::GamePacket<'static> {
+        s2c::GamePacket::ResourcePack {
+            url: url.into(),
+            hash: self.hash.clone(),
+            required,
+            prompt: prompt.map(Json),
+        }
+    }
+
+    /// Serves the pack on the listener without ever returning. Each download
+    /// runs in its own task and is dropped after [`REQUEST_TIMEOUT`], so slow
+    /// or idle clients don't hold on to their connection.
+    pub async fn run(self: Arc<Self>, listener: TcpListener) {
+        loop {
+            let (socket, _) = connection::accept(&listener).await;
+            let hosted_pack = self.clone();
+            tokio::spawn(async move {
+                let _ = tokio::time::timeout(REQUEST_TIMEOUT, hosted_pack.handle(socket)).await;
+            });
+        }
+    }
+
+    /// Answers a single HTTP request, the pack is served for every path.
+    pub async fn handle<S: AsyncRead + AsyncWrite + Unpin>(&self, stream: S) -> Result<()> {
+        let mut stream = BufReader::new(stream);
+        let mut request = vec![];
+        while !request.ends_with(b"\r\n\r\n") {
+            let remaining = (MAX_REQUEST_LENGTH - request.len()) as u64;
+            let mut head = (&mut stream).take(remaining);
+            if head.read_until(b'\n', &mut request).await? == 0 {
+                return Err(if remaining == 0 {
+                    Error::InvalidLength
+                } else {
+                    Error::UnexpectedEnd
+                });
+            }
+        }
+
+        let request = std::str::from_utf8(&request)?;
+        let method = request.split(' ').next().unwrap_or_default();
+        let (status, content_length, body) = match method {
+            "GET" => ("200 OK", self.data.len(), self.data.as_slice()),
+            "HEAD" => ("200 OK", self.data.len(), &[][..]),
+            _ => ("405 Method Not Allowed", 0, &[][..]),
+        };
+        stream
+            .write_all(
+                format!(
+                    "HTTP/1.1 {status}\r\nContent-Type: application/zip\r\nContent-Length: \
+                     {content_length}\r\nConnection: close\r\n\r\n"
+                )
+                .as_bytes(),
+            )
+            .await?;
+        stream.write_all(body).await?;
+        stream.shutdown().await?;
+        Ok(())
+    }
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum PackStatus {
+    /// Sent, but not answered yet.
+    Pending,
+    Accepted,
+    Declined,
+    FailedDownload,
+    Loaded,
+}
+
+/// Tracks the status of the pack for each player.
+#[derive(Default)]
+pub struct PackTracker {
+    players: HashMap<Uuid, PackStatus>,
+}
+
+impl PackTracker {
+    /// Marks the pack as sent to the player.
+    pub fn sent(&mut self, player: Uuid) {
+        self.players.insert(player, PackStatus::Pending);
+    }
+
+    /// Handles the response of the player, returns the new status if the
+    /// packet is one.
+    pub fn handle(&mut self, player: Uuid, packet: &c2s::GamePacket) -> Option<PackStatus> {
+        let c2s::GamePacket::ResourcePack(response) = packet else {
+            return None;
+        };
+        let status = match response {
+            ResourcePackPacket::Accepted => PackStatus::Accepted,
+            ResourcePackPacket::Declined => PackStatus::Declined,
+            ResourcePackPacket::FailedDownload => PackStatus::FailedDownload,
+            ResourcePackPacket::SuccessfullyLoaded => PackStatus::Loaded,
+        };
+        self.players.insert(player, status);
+        Some(status)
+    }
+
+    /// Status of the player, none if the pack hasn't been sent.
+    pub fn status(&self, player: Uuid) -> Option<PackStatus> {
+        self.players.get(&player).copied()
+    }
+
+    /// Forgets the player, e.g. after disconnecting.
+    pub fn remove(&mut self, player: Uuid) -> Option<PackStatus> {
+        self.players.remove(&player)
+    }
+
+    /// Players with the given status.
+    pub fn players(&self, status: PackStatus) -> impl Iterator<Item = Uuid> + '_ {
+        self.players
+            .iter()
+            .filter(move |(_, player_status)| **player_status == status)
+            .map(|(player, _)| *player)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use tokio::{
+        io::{AsyncReadExt, AsyncWriteExt},
+        net::{TcpListener, TcpStream},
+    };
+    use uuid::Uuid;
+
+    use crate::{
+        packet::c2s::{self, game::ResourcePackPacket},
+        resource_pack::{
+            hosting::{HostedPack, PackStatus, PackTracker, MAX_REQUEST_LENGTH},
+            tests::TempDir,
+            ResourcePack,
+        },
+        Error,
+    };
+
+    #[tokio::test]
+    async fn host() {
+        let root = TempDir::new();
+        root.write(
+            "pack.mcmeta",
+            r#"{"pack": {"pack_format": 13, "description": "Pack"}}"#,
+        );
+        root.write("assets/minecraft/lang/en_us.json", "{}");
+        let hosted_pack = HostedPack::new(&mut ResourcePack::open(root.path()).unwrap()).unwrap();
+        assert_eq!(hosted_pack.hash().len(), 40);
+
+        // same hash after the files have been touched
+        std::thread::sleep(std::time::Duration::from_millis(10));
+        root.write("assets/minecraft/lang/en_us.json", "{}");
+        let rezipped = HostedPack::new(&mut ResourcePack::open(root.path()).unwrap()).unwrap();
+        assert_eq!(rezipped.data(), hosted_pack.data());
+        assert_eq!(rezipped.hash(), hosted_pack.hash());
+
+        // the served zip can be opened again
+        let (mut client, server) = tokio::io::duplex(1 << 16);
+        client
+            .write_all(b"GET /pack.zip HTTP/1.1\r\nHost: localhost\r\n\r\n")
+            .await
+            .unwrap();
+        hosted_pack.handle(server).await.unwrap();
+        let mut response = vec![];
+        client.read_to_end(&mut response).await.unwrap();
+        let head_length = response
+            .windows(4)
+            .position(|window| window == b"\r\n\r\n")
+            .unwrap()
+            + 4;
+        assert!(response.starts_with(b"HTTP/1.1 200 OK\r\n"));
+        assert_eq!(&response[head_length..], hosted_pack.data());
+        let zip = TempDir::new();
+        zip.write("pack.zip", &response[head_length..]);
+        let served = ResourcePack::open(zip.path().join("pack.zip")).unwrap();
+        assert_eq!(
+            served.paths().unwrap(),
+            ["assets/minecraft/lang/en_us.json", "pack.mcmeta"]
+        );
+
+        let mut tracker = PackTracker::default();
+        let player = Uuid::new_v4();
+        tracker.sent(player);
+        assert_eq!(tracker.status(player), Some(PackStatus::Pending));
+        assert_eq!(
+            tracker.handle(
+                player,
+                &c2s::GamePacket::ResourcePack(ResourcePackPacket::Accepted)
+            ),
+            Some(PackStatus::Accepted)
+        );
+        tracker.handle(
+            player,
+            &c2s::GamePacket::ResourcePack(ResourcePackPacket::SuccessfullyLoaded),
+        );
+        assert_eq!(
+            tracker.players(PackStatus::Loaded).collect::<Vec<_>>(),
+            [player]
+        );
+    }
+
+    #[tokio::test]
+    async fn serve() {
+        let hosted_pack = Arc::new(HostedPack::from_data(b"pack".to_vec()));
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        tokio::spawn(hosted_pack.clone().run(listener));
+
+        // keeps serving after a connection failed
+        for request in [&b"GET"[..], b"GET / HTTP/1.1\r\n\r\n"] {
+            let mut client = TcpStream::connect(address).await.unwrap();
+            client.write_all(request).await.unwrap();
+            client.shutdown().await.unwrap();
+            let mut response = vec![];
+            client.read_to_end(&mut response).await.unwrap();
+            assert_eq!(response.ends_with(b"\r\n\r\npack"), request.len() > 3);
+        }
+
+        let (mut client, server) = tokio::io::duplex(1 << 16);
+        client
+            .write_all(&vec![b'a'; MAX_REQUEST_LENGTH + 1])
+            .await
+            .unwrap();
+        assert!(matches!(
+            hosted_pack.handle(server).await,
+            Err(Error::InvalidLength)
+        ));
+    }
+}
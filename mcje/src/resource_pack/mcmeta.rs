@@ -1,10 +1,169 @@
+use std::collections::HashMap;
+
 use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Serialize, Deserialize)]
+use crate::types::Component;
+
+/// Metadata of a texture, or of the pack itself if named pack.mcmeta.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
 pub struct McMeta {
     /// Contains data for the animation
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub animation: Option<Animation>,
+
+    /// Contains data for the rendering of the texture.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub texture: Option<Texture>,
+
+    /// Contains data for villager textures.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub villager: Option<Villager>,
+
+    /// Holds the pack information.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pack: Option<Pack>,
+
+    /// Filters files out of packs applied below this one.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub filter: Option<Filter>,
+
+    /// Directories which are applied on top of the pack, depending on the
+    /// pack format of the game.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub overlays: Option<Overlays>,
+
+    /// Additional languages by language code, e.g. `en_us`.
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub language: HashMap<String, Language>,
+
+    /// Sections which aren't known, e.g. `credit` or ones of mods, which are
+    /// kept to write them back.
+    #[serde(flatten, skip_serializing_if = "HashMap::is_empty")]
+    pub extra: HashMap<String, serde_json::Value>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Pack {
+    /// Pack version. If this number does not match the current required
+    /// number, the resource pack displays an error and requires additional
+    /// confirmation to load the pack.
+    pub pack_format: u32,
+
+    /// Text shown below the pack name in the resource pack menu.
+    pub description: Component,
+
+    /// Range of pack formats this pack supports.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub supported_formats: Option<Formats>,
+
+    #[serde(flatten, skip_serializing_if = "HashMap::is_empty")]
+    pub extra: HashMap<String, serde_json::Value>,
+}
+
+/// Pack formats, either a single one, or an inclusive range.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum Formats {
+    Format(u32),
+    Range([u32; 2]),
+    Object {
+        min_inclusive: u32,
+        max_inclusive: u32,
+    },
+}
+
+impl Formats {
+    /// Returns whether the pack format is in the range.
+    pub fn contains(&self, format: u32) -> bool {
+        match *self {
+            Formats::Format(expected) => format == expected,
+            Formats::Range([min_inclusive, max_inclusive])
+            | Formats::Object {
+                min_inclusive,
+                max_inclusive,
+            } => (min_inclusive..=max_inclusive).contains(&format),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Filter {
+    /// List of patterns, files matching any of them are treated as if they
+    /// were not present in the packs below this one.
+    pub block: Vec<FilterPattern>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct FilterPattern {
+    /// Regular expression for the namespace of files to filter out. If
+    /// unspecified, it applies to every namespace.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub namespace: Option<String>,
+
+    /// Regular expression for the paths of files to filter out. If
+    /// unspecified, it applies to every file.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub path: Option<String>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Overlays {
+    /// List of overlays, which are applied in order.
+    pub entries: Vec<Overlay>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Overlay {
+    /// Range of pack formats for which the overlay is applied.
+    pub formats: Formats,
+
+    /// Directory to overlay for the respective versions, relative to the root
+    /// of the pack.
+    pub directory: String,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Language {
+    /// The full name of the language.
+    pub name: String,
+
+    /// The country or region name.
+    pub region: String,
+
+    /// If true, the language reads right to left.
+    #[serde(default, skip_serializing_if = "if_false")]
+    pub bidirectional: bool,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Texture {
+    /// Causes the texture to blur when viewed from close up. Defaults to false.
+    #[serde(default, skip_serializing_if = "if_false")]
+    pub blur: bool,
+
+    /// Causes the texture to stretch instead of tiling in cases where it
+    /// otherwise would. Defaults to false.
+    #[serde(default, skip_serializing_if = "if_false")]
+    pub clamp: bool,
+
+    #[serde(flatten, skip_serializing_if = "HashMap::is_empty")]
+    pub extra: HashMap<String, serde_json::Value>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Villager {
+    /// Determines whether the hat layer of the profession texture covers the
+    /// hat of the biome texture.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hat: Option<Hat>,
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Hat {
+    None,
+    Partial,
+    Full,
 }
 
 /// Block, item, particle, painting, item frame, and status effect icon
@@ -13,7 +172,7 @@ pub struct McMeta {
 /// a .mcmeta file in JSON format with the same name and .png at the end of the
 /// filename, in the same directory. For example, the .mcmeta file for stone.png
 /// would be stone.png.mcmeta.
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct Animation {
     /// If true, Minecraft generates additional frames between frames with a
     /// frame time greater than 1 between them. Defaults to false.
@@ -41,9 +200,41 @@ pub struct Animation {
     /// top to bottom.
     #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub frames: Vec<Frame>,
+
+    #[serde(flatten, skip_serializing_if = "HashMap::is_empty")]
+    pub extra: HashMap<String, serde_json::Value>,
+}
+
+impl Animation {
+    /// Returns the size of the frames in pixels for a texture of the given
+    /// size, frames are square unless set otherwise.
+    pub fn frame_size(&self, width: u32, height: u32) -> (u32, u32) {
+        match (self.width, self.height) {
+            (None, None) => (width.min(height), width.min(height)),
+            (frame_width, frame_height) => {
+                (frame_width.unwrap_or(width), frame_height.unwrap_or(height))
+            }
+        }
+    }
+
+    /// Returns the index and time of the frames in order of playback.
+    pub fn frames(&self, frame_count: u32) -> Vec<(u32, u32)> {
+        if self.frames.is_empty() {
+            return (0..frame_count)
+                .map(|index| (index, self.frametime))
+                .collect();
+        }
+        self.frames
+            .iter()
+            .map(|frame| match *frame {
+                Frame::Index(index) => (index, self.frametime),
+                Frame::IndexTime { index, time } => (index, time),
+            })
+            .collect()
+    }
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 #[serde(untagged)]
 pub enum Frame {
     /// A number corresponding to position of a frame from the top, with the top
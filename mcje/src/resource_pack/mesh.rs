@@ -0,0 +1,498 @@
+//! Bakes resolved block models into meshes, which can be exported as glTF or
+//! OBJ. Positions are in blocks, with the block spanning from 0 to 1, and UVs
+//! are relative to the texture of the face, with the origin at the top left.
+
+use std::{collections::BTreeMap, io::Write};
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use glam::{Quat, Vec2, Vec3};
+use serde_json::{json, Value};
+
+use crate::resource_pack::{
+    model::{Axis, Element, Face, FaceEnum},
+    path, BlockModel,
+};
+
+/// Baked faces of one or more block models.
+#[derive(Clone, Debug, Default)]
+pub struct Mesh {
+    pub quads: Vec<Quad>,
+}
+
+/// Face of an element, the vertices are counter-clockwise when seen from
+/// the front.
+#[derive(Clone, Debug)]
+pub struct Quad {
+    pub positions: [Vec3; 4],
+    pub uvs: [Vec2; 4],
+    pub normal: Vec3,
+    /// Texture location, or an unresolved texture variable.
+    pub texture: String,
+    /// Side of the block at which a neighboring block hides the face, after
+    /// rotating the model.
+    pub cull_face: Option<FaceEnum>,
+    pub tint_index: i32,
+    pub shade: bool,
+}
+
+impl Mesh {
+    /// Bakes the models of a block state, as returned by
+    /// [`ResourcePack::resolve`](crate::resource_pack::ResourcePack::resolve).
+    pub fn bake(models: &[BlockModel]) -> Self {
+        let mut mesh = Self::default();
+        for model in models {
+            mesh.add(model);
+        }
+        mesh
+    }
+
+    /// Adds the elements of the model, rotated by the variant. With uv lock
+    /// the UVs are projected from the rotated positions, so that the texture
+    /// keeps its orientation.
+    pub fn add(&mut self, model: &BlockModel) {
+        // same order as the game, which rotates clockwise when seen from the
+        // positive axis
+        let rotation = Quat::from_rotation_y(-(model.y as f32).to_radians())
+            * Quat::from_rotation_x(-(model.x as f32).to_radians());
+        let center = Vec3::splat(0.5);
+        for element in &model.model.elements {
+            for (&direction, face) in &element.faces {
+                let mut quad = bake_face(element, direction, face);
+                for position in &mut quad.positions {
+                    *position = snap(rotation * (*position - center) + center);
+                }
+                if model.uv_lock {
+                    let direction = nearest_direction(rotation * normal(direction));
+                    for (uv, position) in quad.uvs.iter_mut().zip(quad.positions) {
+                        *uv = project(direction, position);
+                    }
+                }
+                quad.normal = face_normal(&quad.positions);
+                quad.cull_face = quad
+                    .cull_face
+                    .map(|cull_face| nearest_direction(rotation * normal(cull_face)));
+                self.quads.push(quad);
+            }
+        }
+    }
+
+    /// Returns the quads grouped by texture, ordered by texture.
+    fn by_texture(&self) -> BTreeMap<&str, Vec<&Quad>> {
+        let mut quads = BTreeMap::<_, Vec<_>>::new();
+        for quad in &self.quads {
+            quads.entry(quad.texture.as_str()).or_default().push(quad);
+        }
+        quads
+    }
+
+    /// Returns the mesh as glTF, with the buffer embedded. There is one
+    /// primitive and material per texture, and the images refer to the
+    /// textures by their path in the pack.
+    pub fn to_gltf(&self) -> Value {
+        let mut buffer = Vec::<u8>::new();
+        let mut buffer_views = vec![];
+        let mut accessors = vec![];
+        let mut primitives = vec![];
+        let mut materials = vec![];
+        let mut images = vec![];
+        let mut textures = vec![];
+
+        // appends the data as buffer view and accessor, and returns the index of
+        // the accessor
+        let mut add_accessor = |data: Vec<f32>, indices: Option<Vec<u32>>, kind: &str| {
+            let offset = buffer.len();
+            let (count, component_type, target) = match indices {
+                Some(indices) => {
+                    for index in &indices {
+                        buffer.extend_from_slice(&index.to_le_bytes());
+                    }
+                    (indices.len(), 5125, 34963)
+                }
+                None => {
+                    for value in &data {
+                        buffer.extend_from_slice(&value.to_le_bytes());
+                    }
+                    let components = if kind == "VEC3" { 3 } else { 2 };
+                    (data.len() / components, 5126, 34962)
+                }
+            };
+            buffer_views.push(json!({
+                "buffer": 0,
+                "byteOffset": offset,
+                "byteLength": buffer.len() - offset,
+                "target": target,
+            }));
+            let mut accessor = json!({
+                "bufferView": buffer_views.len() - 1,
+                "componentType": component_type,
+                "count": count,
+                "type": kind,
+            });
+            // bounds are required for positions
+            if kind == "VEC3" && !data.is_empty() {
+                let (min, max) = data.chunks(3).map(Vec3::from_slice).fold(
+                    (Vec3::splat(f32::MAX), Vec3::splat(f32::MIN)),
+                    |(min, max), position| (min.min(position), max.max(position)),
+                );
+                accessor["min"] = json!(min.to_array());
+                accessor["max"] = json!(max.to_array());
+            }
+            accessors.push(accessor);
+            accessors.len() - 1
+        };
+
+        for (texture, quads) in self.by_texture() {
+            let mut positions = vec![];
+            let mut normals = vec![];
+            let mut uvs = vec![];
+            let mut indices = vec![];
+            for quad in quads {
+                let offset = (positions.len() / 3) as u32;
+                for (position, uv) in quad.positions.iter().zip(&quad.uvs) {
+                    positions.extend_from_slice(&position.to_array());
+                    normals.extend_from_slice(&quad.normal.to_array());
+                    uvs.extend_from_slice(&uv.to_array());
+                }
+                indices.extend([0, 1, 2, 0, 2, 3].map(|index| offset + index));
+            }
+            let position = add_accessor(positions, None, "VEC3");
+            let normal = add_accessor(normals, None, "VEC3");
+            let uv = add_accessor(uvs, None, "VEC2");
+            let indices = add_accessor(vec![], Some(indices), "SCALAR");
+            primitives.push(json!({
+                "attributes": {
+                    "POSITION": position,
+                    "NORMAL": normal,
+                    "TEXCOORD_0": uv,
+                },
+                "indices": indices,
+                "material": materials.len(),
+            }));
+            materials.push(json!({
+                "name": texture,
+                "pbrMetallicRoughness": {
+                    "baseColorTexture": { "index": textures.len() },
+                    "metallicFactor": 0.0,
+                },
+                "alphaMode": "MASK",
+            }));
+            textures.push(json!({ "sampler": 0, "source": images.len() }));
+            images.push(json!({ "uri": path("textures", texture, "png") }));
+        }
+
+        json!({
+            "asset": { "version": "2.0" },
+            "scene": 0,
+            "scenes": [{ "nodes": [0] }],
+            "nodes": [{ "mesh": 0 }],
+            "meshes": [{ "primitives": primitives }],
+            "materials": materials,
+            "textures": textures,
+            "images": images,
+            // pixel art is sampled without filtering
+            "samplers": [{ "magFilter": 9728, "minFilter": 9728 }],
+            "accessors": accessors,
+            "bufferViews": buffer_views,
+            "buffers": [{
+                "byteLength": buffer.len(),
+                "uri": format!("data:application/octet-stream;base64,{}", STANDARD.encode(&buffer)),
+            }],
+        })
+    }
+
+    /// Writes the mesh as OBJ, with one material per texture, which are
+    /// written by [`Mesh::write_mtl`].
+    pub fn write_obj(&self, output: &mut impl Write, mtl: &str) -> std::io::Result<()> {
+        writeln!(output, "mtllib {mtl}")?;
+        let mut vertex = 1;
+        for (texture, quads) in self.by_texture() {
+            writeln!(output, "usemtl {texture}")?;
+            for quad in quads {
+                for (position, uv) in quad.positions.iter().zip(&quad.uvs) {
+                    writeln!(output, "v {} {} {}", position.x, position.y, position.z)?;
+                    // the origin of OBJ texture coordinates is at the bottom left
+                    writeln!(output, "vt {} {}", uv.x, 1.0 - uv.y)?;
+                    writeln!(
+                        output,
+                        "vn {} {} {}",
+                        quad.normal.x, quad.normal.y, quad.normal.z
+                    )?;
+                }
+                for [a, b, c] in [[0, 1, 2], [0, 2, 3]] {
+                    let (a, b, c) = (vertex + a, vertex + b, vertex + c);
+                    writeln!(output, "f {a}/{a}/{a} {b}/{b}/{b} {c}/{c}/{c}")?;
+                }
+                vertex += 4;
+            }
+        }
+        Ok(())
+    }
+
+    /// Writes the materials of [`Mesh::write_obj`], which refer to the textures
+    /// by their path in the pack.
+    pub fn write_mtl(&self, output: &mut impl Write) -> std::io::Result<()> {
+        for texture in self.by_texture().keys() {
+            writeln!(output, "newmtl {texture}")?;
+            writeln!(output, "map_Kd {}", path("textures", texture, "png"))?;
+        }
+        Ok(())
+    }
+}
+
+/// Bakes the face of the element without the rotation of the variant.
+fn bake_face(element: &Element, direction: FaceEnum, face: &Face) -> Quad {
+    let from = element.from / 16.0;
+    let to = element.to / 16.0;
+    // same vertex order as the game, which maps to the UV corners (u0, v0),
+    // (u0, v1), (u1, v1) and (u1, v0)
+    let mut positions = match direction {
+        FaceEnum::Down => [
+            Vec3::new(from.x, from.y, to.z),
+            Vec3::new(from.x, from.y, from.z),
+            Vec3::new(to.x, from.y, from.z),
+            Vec3::new(to.x, from.y, to.z),
+        ],
+        FaceEnum::Up => [
+            Vec3::new(from.x, to.y, from.z),
+            Vec3::new(from.x, to.y, to.z),
+            Vec3::new(to.x, to.y, to.z),
+            Vec3::new(to.x, to.y, from.z),
+        ],
+        FaceEnum::North => [
+            Vec3::new(to.x, to.y, from.z),
+            Vec3::new(to.x, from.y, from.z),
+            Vec3::new(from.x, from.y, from.z),
+            Vec3::new(from.x, to.y, from.z),
+        ],
+        FaceEnum::South => [
+            Vec3::new(from.x, to.y, to.z),
+            Vec3::new(from.x, from.y, to.z),
+            Vec3::new(to.x, from.y, to.z),
+            Vec3::new(to.x, to.y, to.z),
+        ],
+        FaceEnum::West => [
+            Vec3::new(from.x, to.y, from.z),
+            Vec3::new(from.x, from.y, from.z),
+            Vec3::new(from.x, from.y, to.z),
+            Vec3::new(from.x, to.y, to.z),
+        ],
+        FaceEnum::East => [
+            Vec3::new(to.x, to.y, to.z),
+            Vec3::new(to.x, from.y, to.z),
+            Vec3::new(to.x, from.y, from.z),
+            Vec3::new(to.x, to.y, from.z),
+        ],
+    };
+
+    // UVs default to the position of the face
+    let [u0, v0, u1, v1] = face.uv.map_or_else(
+        || {
+            let from = project(direction, from);
+            let to = project(direction, to);
+            [
+                from.x.min(to.x),
+                from.y.min(to.y),
+                from.x.max(to.x),
+                from.y.max(to.y),
+            ]
+        },
+        |uv| uv.map(|value| value / 16.0),
+    );
+    let corners = [
+        Vec2::new(u0, v0),
+        Vec2::new(u0, v1),
+        Vec2::new(u1, v1),
+        Vec2::new(u1, v0),
+    ];
+    let shift = (face.rotation / 90) as usize;
+    let uvs = std::array::from_fn(|i| corners[(i + shift) % 4]);
+
+    if let Some(rotation) = &element.rotation {
+        let (axis, scale) = match rotation.axis {
+            Axis::X => (Vec3::X, Vec3::new(0.0, 1.0, 1.0)),
+            Axis::Y => (Vec3::Y, Vec3::new(1.0, 0.0, 1.0)),
+            Axis::Z => (Vec3::Z, Vec3::new(1.0, 1.0, 0.0)),
+        };
+        let angle = rotation.angle.to_radians();
+        let origin = rotation.origin / 16.0;
+        // rescaling stretches the rotated element back to the full block
+        let scale = if rotation.rescale {
+            Vec3::ONE + scale * (1.0 / angle.cos() - 1.0)
+        } else {
+            Vec3::ONE
+        };
+        let quat = Quat::from_axis_angle(axis, angle);
+        for position in &mut positions {
+            *position = quat * (*position - origin) * scale + origin;
+        }
+    }
+
+    Quad {
+        normal: face_normal(&positions),
+        positions,
+        uvs,
+        texture: face.texture.clone(),
+        cull_face: face.cull_face,
+        tint_index: face.tint_index,
+        shade: element.shade,
+    }
+}
+
+/// Returns the UV of the position on a face in the direction, as used for
+/// faces without explicit UVs.
+fn project(direction: FaceEnum, position: Vec3) -> Vec2 {
+    match direction {
+        FaceEnum::Down => Vec2::new(position.x, 1.0 - position.z),
+        FaceEnum::Up => Vec2::new(position.x, position.z),
+        FaceEnum::North => Vec2::new(1.0 - position.x, 1.0 - position.y),
+        FaceEnum::South => Vec2::new(position.x, 1.0 - position.y),
+        FaceEnum::West => Vec2::new(position.z, 1.0 - position.y),
+        FaceEnum::East => Vec2::new(1.0 - position.z, 1.0 - position.y),
+    }
+}
+
+fn normal(direction: FaceEnum) -> Vec3 {
+    match direction {
+        FaceEnum::Down => Vec3::NEG_Y,
+        FaceEnum::Up => Vec3::Y,
+        FaceEnum::North => Vec3::NEG_Z,
+        FaceEnum::South => Vec3::Z,
+        FaceEnum::West => Vec3::NEG_X,
+        FaceEnum::East => Vec3::X,
+    }
+}
+
+fn nearest_direction(normal: Vec3) -> FaceEnum {
+    let abs = normal.abs();
+    if abs.x >= abs.y && abs.x >= abs.z {
+        if normal.x < 0.0 {
+            FaceEnum::West
+        } else {
+            FaceEnum::East
+        }
+    } else if abs.y >= abs.z {
+        if normal.y < 0.0 {
+            FaceEnum::Down
+        } else {
+            FaceEnum::Up
+        }
+    } else if normal.z < 0.0 {
+        FaceEnum::North
+    } else {
+        FaceEnum::South
+    }
+}
+
+fn face_normal(positions: &[Vec3; 4]) -> Vec3 {
+    (positions[1] - positions[0])
+        .cross(positions[2] - positions[0])
+        .normalize_or_zero()
+}
+
+/// Removes the error introduced by rotating in steps of 90 degrees.
+fn snap(position: Vec3) -> Vec3 {
+    (position * 65536.0).round() / 65536.0
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::HashMap, sync::Arc};
+
+    use glam::{Vec2, Vec3};
+
+    use crate::resource_pack::{
+        mesh::Mesh,
+        model::{Element, Face, FaceEnum, Model},
+        BlockModel,
+    };
+
+    fn slab() -> Arc<Model> {
+        let face = |texture: &str, cull_face| Face {
+            uv: None,
+            texture: texture.to_string(),
+            cull_face,
+            rotation: 0,
+            tint_index: -1,
+            extra: HashMap::new(),
+        };
+        Arc::new(Model {
+            parent: None,
+            ambient_occlusion: true,
+            display: HashMap::new(),
+            textures: HashMap::new(),
+            elements: vec![Element {
+                from: Vec3::new(0.0, 0.0, 0.0),
+                to: Vec3::new(16.0, 8.0, 16.0),
+                rotation: None,
+                shade: true,
+                faces: HashMap::from([
+                    (FaceEnum::Up, face("minecraft:block/top", None)),
+                    (
+                        FaceEnum::North,
+                        face("minecraft:block/side", Some(FaceEnum::North)),
+                    ),
+                ]),
+                extra: HashMap::new(),
+            }],
+            groups: vec![],
+            overrides: vec![],
+            gui_light: None,
+            extra: HashMap::new(),
+        })
+    }
+
+    #[test]
+    fn bake() {
+        let mesh = Mesh::bake(&[BlockModel {
+            model: slab(),
+            x: 0,
+            y: 0,
+            uv_lock: false,
+        }]);
+        let up = mesh
+            .quads
+            .iter()
+            .find(|quad| quad.texture == "minecraft:block/top")
+            .unwrap();
+        assert_eq!(up.normal, Vec3::Y);
+        assert!(up.positions.iter().all(|position| position.y == 0.5));
+        let north = mesh
+            .quads
+            .iter()
+            .find(|quad| quad.texture == "minecraft:block/side")
+            .unwrap();
+        assert_eq!(north.normal, Vec3::NEG_Z);
+        // the lower half of the texture
+        assert_eq!(north.uvs[0], Vec2::new(0.0, 0.5));
+        assert_eq!(north.uvs[2], Vec2::new(1.0, 1.0));
+
+        // upside down, and rotated to face east
+        let mesh = Mesh::bake(&[BlockModel {
+            model: slab(),
+            x: 180,
+            y: 90,
+            uv_lock: true,
+        }]);
+        let north = mesh
+            .quads
+            .iter()
+            .find(|quad| quad.texture == "minecraft:block/side")
+            .unwrap();
+        assert_eq!(north.normal, Vec3::NEG_X);
+        assert_eq!(north.cull_face, Some(FaceEnum::West));
+        assert!(north.positions.iter().all(|position| position.y >= 0.5));
+        // the texture is kept upright
+        assert_eq!(
+            north.uvs.map(|uv| uv.y).iter().cloned().fold(1.0, f32::min),
+            0.0
+        );
+
+        let gltf = mesh.to_gltf();
+        assert_eq!(gltf["meshes"][0]["primitives"].as_array().unwrap().len(), 2);
+        assert_eq!(gltf["accessors"][0]["count"], 4);
+        let mut obj = vec![];
+        mesh.write_obj(&mut obj, "slab.mtl").unwrap();
+        let obj = String::from_utf8(obj).unwrap();
+        assert_eq!(obj.lines().filter(|line| line.starts_with("f ")).count(), 4);
+    }
+}
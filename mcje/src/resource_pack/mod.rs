@@ -1,3 +1,663 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    fs::File,
+    io::Read,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use zip::{result::ZipError, ZipArchive};
+
+use crate::{Error, Result};
+
+pub mod atlas;
 pub mod block_state;
+pub mod font;
+pub mod hosting;
 pub mod mcmeta;
+pub mod mesh;
 pub mod model;
+pub mod sounds;
+pub mod validate;
+
+/// Maximum number of parents of a model, which guards against cycles.
+const MAX_PARENTS: usize = 64;
+
+/// Resource pack, which is read from a directory or zip file. Block states and
+/// models are cached once loaded.
+pub struct ResourcePack {
+    source: Source,
+    /// Packs below this one, in order, which provide the files this pack
+    /// doesn't have.
+    bases: Vec<Source>,
+    block_states: HashMap<String, Arc<block_state::BlockState>>,
+    models: HashMap<String, Arc<model::Model>>,
+    resolved_models: HashMap<String, Arc<model::Model>>,
+}
+
+enum Source {
+    Directory(PathBuf),
+    Zip(ZipArchive<File>),
+}
+
+impl Source {
+    /// Reads the file, none if it doesn't exist.
+    fn read(&mut self, path: &str) -> Result<Option<Vec<u8>>> {
+        let mut data = vec![];
+        match self {
+            Source::Directory(root) => match File::open(root.join(path)) {
+                Ok(mut file) => file.read_to_end(&mut data)?,
+                Err(error) if error.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+                Err(error) => return Err(error.into()),
+            },
+            Source::Zip(archive) => match archive.by_name(path) {
+                Ok(mut file) => file.read_to_end(&mut data)?,
+                Err(ZipError::FileNotFound) => return Ok(None),
+                Err(error) => return Err(error.into()),
+            },
+        };
+        Ok(Some(data))
+    }
+
+    fn contains(&mut self, path: &str) -> bool {
+        match self {
+            Source::Directory(root) => root.join(path).is_file(),
+            Source::Zip(archive) => archive.by_name(path).is_ok(),
+        }
+    }
+}
+
+/// Model of a block state, with the rotation of the variant.
+#[derive(Clone, Debug)]
+pub struct BlockModel {
+    /// Model with all parents merged, and concrete textures on all faces.
+    pub model: Arc<model::Model>,
+    /// Rotation on the x-axis in increments of 90 degrees.
+    pub x: u32,
+    /// Rotation on the y-axis in increments of 90 degrees.
+    pub y: u32,
+    pub uv_lock: bool,
+}
+
+/// Contents of a language file, translations by their key.
+pub type Language = HashMap<String, String>;
+
+/// File of a pack, parsed if it's of a known format.
+#[derive(Clone, Debug)]
+// there is one per file, which isn't worth boxing the metadata for
+#[allow(clippy::large_enum_variant)]
+pub enum Resource {
+    McMeta(mcmeta::McMeta),
+    BlockState(block_state::BlockState),
+    Model(model::Model),
+    Sounds(sounds::Sounds),
+    Language(Language),
+    Font(font::Font),
+    Raw(Vec<u8>),
+}
+
+impl Resource {
+    /// Parses the file by its path relative to the root of the pack, which can
+    /// also be in an overlay directory. Files which fail to parse are kept as
+    /// raw data, so they are written back unchanged.
+    pub fn parse(path: &str, data: Vec<u8>) -> Self {
+        fn parse_json<T: serde::de::DeserializeOwned>(
+            data: Vec<u8>,
+            resource: fn(T) -> Resource,
+        ) -> Resource {
+            match serde_json::from_slice(&data) {
+                Ok(value) => resource(value),
+                Err(_) => Resource::Raw(data),
+            }
+        }
+
+        if path.ends_with(".mcmeta") {
+            return parse_json(data, Resource::McMeta);
+        }
+        let path = match path.split_once('/') {
+            Some((_, overlay_path)) if !path.starts_with("assets/") => overlay_path,
+            _ => path,
+        };
+        let Some((kind, path)) = path
+            .strip_prefix("assets/")
+            .and_then(|path| path.split_once('/'))
+            .and_then(|(_, path)| path.strip_suffix(".json"))
+            .map(|path| path.split_once('/').unwrap_or(("", path)))
+        else {
+            return Resource::Raw(data);
+        };
+        match (kind, path) {
+            ("blockstates", _) => parse_json(data, Resource::BlockState),
+            ("models", _) => parse_json(data, Resource::Model),
+            ("", "sounds") => parse_json(data, Resource::Sounds),
+            ("lang", _) => parse_json(data, Resource::Language),
+            ("font", _) => parse_json(data, Resource::Font),
+            _ => Resource::Raw(data),
+        }
+    }
+
+    /// Serializes the file, parsed files are written as pretty JSON. Values
+    /// are kept, but not the formatting, key order, or whether decimals were
+    /// written as integers.
+    pub fn to_vec(&self) -> Result<Vec<u8>> {
+        Ok(match self {
+            Resource::McMeta(mcmeta) => serde_json::to_vec_pretty(mcmeta)?,
+            Resource::BlockState(block_state) => serde_json::to_vec_pretty(block_state)?,
+            Resource::Model(model) => serde_json::to_vec_pretty(model)?,
+            Resource::Sounds(sounds) => serde_json::to_vec_pretty(sounds)?,
+            Resource::Language(language) => serde_json::to_vec_pretty(language)?,
+            Resource::Font(font) => serde_json::to_vec_pretty(font)?,
+            Resource::Raw(data) => data.clone(),
+        })
+    }
+}
+
+impl ResourcePack {
+    /// Opens the resource pack, which is either a directory or zip file.
+    pub fn open(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let source = if path.is_dir() {
+            Source::Directory(path.to_path_buf())
+        } else {
+            Source::Zip(ZipArchive::new(File::open(path)?)?)
+        };
+        Ok(Self {
+            source,
+            bases: vec![],
+            block_states: Default::default(),
+            models: Default::default(),
+            resolved_models: Default::default(),
+        })
+    }
+
+    /// Adds a pack below this one and its previously added bases, e.g. the
+    /// client jar. Files which this pack doesn't have are read from the bases,
+    /// like the client does when layering packs.
+    pub fn with_base(mut self, base: ResourcePack) -> Self {
+        self.bases.push(base.source);
+        self.bases.extend(base.bases);
+        self
+    }
+
+    /// Returns the paths of all files, relative to the root of the pack and
+    /// separated by `/`. Files of the bases are not included.
+    pub fn paths(&self) -> Result<Vec<String>> {
+        fn visit(root: &Path, directory: &Path, paths: &mut Vec<String>) -> Result<()> {
+            for entry in std::fs::read_dir(directory)? {
+                let path = entry?.path();
+                if path.is_dir() {
+                    visit(root, &path, paths)?;
+                } else if let Ok(path) = path.strip_prefix(root) {
+                    let path = path
+                        .components()
+                        .map(|component| component.as_os_str().to_string_lossy())
+                        .collect::<Vec<_>>()
+                        .join("/");
+                    paths.push(path);
+                }
+            }
+            Ok(())
+        }
+
+        let mut paths = vec![];
+        match &self.source {
+            Source::Directory(root) => visit(root, root, &mut paths)?,
+            Source::Zip(archive) => paths.extend(
+                archive
+                    .file_names()
+                    .filter(|path| !path.ends_with('/'))
+                    .map(str::to_string),
+            ),
+        }
+        paths.sort();
+        Ok(paths)
+    }
+
+    /// Reads the file at the path relative to the root of the pack, or from
+    /// the first base which has it. Fails with [`Error::MissingResource`] if
+    /// none has it.
+    pub fn read(&mut self, path: &str) -> Result<Vec<u8>> {
+        for source in std::iter::once(&mut self.source).chain(&mut self.bases) {
+            if let Some(data) = source.read(path)? {
+                return Ok(data);
+            }
+        }
+        Err(Error::MissingResource(path.to_string()))
+    }
+
+    /// Returns whether the file at the path relative to the root of the pack
+    /// exists in the pack or one of its bases.
+    pub fn contains(&mut self, path: &str) -> bool {
+        std::iter::once(&mut self.source)
+            .chain(&mut self.bases)
+            .any(|source| source.contains(path))
+    }
+
+    /// Reads and parses all files of the pack, by their path. Files which
+    /// fail to parse are returned as [`Resource::Raw`].
+    pub fn resources(&mut self) -> Result<BTreeMap<String, Resource>> {
+        let mut resources = BTreeMap::new();
+        for path in self.paths()? {
+            let data = self.read(&path)?;
+            resources.insert(path.clone(), Resource::parse(&path, data));
+        }
+        Ok(resources)
+    }
+
+    /// Returns the block state file, e.g. `minecraft:stone`.
+    pub fn block_state(&mut self, location: &str) -> Result<Arc<block_state::BlockState>> {
+        let location = normalize(location);
+        if let Some(block_state) = self.block_states.get(&location) {
+            return Ok(block_state.clone());
+        }
+        let block_state: Arc<block_state::BlockState> = Arc::new(serde_json::from_slice(
+            &self.read(&path("blockstates", &location, "json"))?,
+        )?);
+        self.block_states.insert(location, block_state.clone());
+        Ok(block_state)
+    }
+
+    /// Returns the model file as is, e.g. `minecraft:block/stone`.
+    pub fn model(&mut self, location: &str) -> Result<Arc<model::Model>> {
+        let location = normalize(location);
+        if let Some(model) = self.models.get(&location) {
+            return Ok(model.clone());
+        }
+        let model: Arc<model::Model> = Arc::new(serde_json::from_slice(
+            &self.read(&path("models", &location, "json"))?,
+        )?);
+        self.models.insert(location, model.clone());
+        Ok(model)
+    }
+
+    /// Returns the model with all its parents merged, and the texture
+    /// variables of the faces replaced by the textures they refer to. Texture
+    /// variables which can't be resolved are kept.
+    pub fn resolved_model(&mut self, location: &str) -> Result<Arc<model::Model>> {
+        let location = normalize(location);
+        if let Some(model) = self.resolved_models.get(&location) {
+            return Ok(model.clone());
+        }
+
+        let mut models = vec![];
+        let mut parent = Some(location.clone());
+        while let Some(location) = parent {
+            // built-in models have no file
+            if location.starts_with("minecraft:builtin/") {
+                break;
+            }
+            if models.len() == MAX_PARENTS {
+                return Err(Error::CircularParent(location));
+            }
+            let model = self.model(&location)?;
+            parent = model.parent.as_deref().map(normalize);
+            models.push(model);
+        }
+
+        // children override their parents
+        let mut resolved = model::Model {
+            parent: None,
+            ambient_occlusion: true,
+            display: Default::default(),
+            textures: Default::default(),
+            elements: vec![],
+            groups: vec![],
+            overrides: models
+                .first()
+                .map_or(vec![], |model| model.overrides.clone()),
+            gui_light: None,
+            extra: Default::default(),
+        };
+        for model in models.iter().rev() {
+            resolved.ambient_occlusion &= model.ambient_occlusion;
+            resolved.gui_light = model.gui_light.or(resolved.gui_light);
+            resolved.display.extend(model.display.clone());
+            resolved.textures.extend(model.textures.clone());
+            if !model.elements.is_empty() {
+                resolved.elements = model.elements.clone();
+                resolved.groups = model.groups.clone();
+            }
+        }
+        let textures = resolved
+            .textures
+            .iter()
+            .map(|(variable, texture)| {
+                (
+                    variable.clone(),
+                    resolve_texture(&resolved.textures, texture),
+                )
+            })
+            .collect();
+        resolved.textures = textures;
+        for element in &mut resolved.elements {
+            for face in element.faces.values_mut() {
+                face.texture = resolve_texture(&resolved.textures, &face.texture);
+            }
+        }
+
+        let resolved = Arc::new(resolved);
+        self.resolved_models.insert(location, resolved.clone());
+        Ok(resolved)
+    }
+
+    /// Returns the models of the block state, e.g.
+    /// `minecraft:oak_stairs[facing=east,half=bottom]`, properties which are
+    /// not given only match conditions which allow any value. The seed chooses
+    /// between weighted models, and is usually derived from the position of
+    /// the block.
+    pub fn resolve(&mut self, block_state: &str, seed: u64) -> Result<Vec<BlockModel>> {
+        let (location, properties) = parse_block_state(block_state);
+        let models = match &*self.block_state(location)? {
+            block_state::BlockState::Variants(variants) => {
+                // the most specific variant matching all its properties
+                let variant = variants
+                    .iter()
+                    .filter_map(|(key, variant)| {
+                        let conditions = parse_properties(key);
+                        conditions
+                            .iter()
+                            .all(|(key, value)| properties.get(key) == Some(value))
+                            .then_some((conditions.len(), variant))
+                    })
+                    .max_by_key(|(specificity, _)| *specificity)
+                    .map(|(_, variant)| variant)
+                    .ok_or_else(|| Error::MissingResource(block_state.to_string()))?;
+                block_state::choose(&variant.0, seed)
+                    .into_iter()
+                    .cloned()
+                    .collect::<Vec<_>>()
+            }
+            block_state::BlockState::Multipart(cases) => cases
+                .iter()
+                .filter(|case| {
+                    case.when
+                        .as_ref()
+                        .is_none_or(|when| when.matches(&properties))
+                })
+                .filter_map(|case| block_state::choose(&case.apply, seed))
+                .cloned()
+                .collect(),
+        };
+        models
+            .into_iter()
+            .map(|model| {
+                Ok(BlockModel {
+                    model: self.resolved_model(&model.model)?,
+                    x: model.x,
+                    y: model.y,
+                    uv_lock: model.uv_lock,
+                })
+            })
+            .collect()
+    }
+}
+
+/// Writes the files to the directory, which can be opened as pack again.
+pub fn write(root: impl AsRef<Path>, resources: &BTreeMap<String, Resource>) -> Result<()> {
+    let root = root.as_ref();
+    for (path, resource) in resources {
+        let path = root.join(path);
+        if let Some(directory) = path.parent() {
+            std::fs::create_dir_all(directory)?;
+        }
+        std::fs::write(path, resource.to_vec()?)?;
+    }
+    Ok(())
+}
+
+/// Prefixes the resource location with the default namespace if it has none.
+pub fn normalize(location: &str) -> String {
+    if location.contains(':') {
+        location.to_string()
+    } else {
+        format!("minecraft:{location}")
+    }
+}
+
+/// Returns the path of the resource location, relative to the root of the
+/// pack, e.g. `assets/minecraft/models/block/stone.json`.
+pub fn path(kind: &str, location: &str, extension: &str) -> String {
+    let (namespace, path) = location.split_once(':').unwrap_or(("minecraft", location));
+    format!("assets/{namespace}/{kind}/{path}.{extension}")
+}
+
+/// Splits a block state into its location and properties, e.g.
+/// `minecraft:oak_stairs[facing=east]`.
+pub fn parse_block_state(block_state: &str) -> (&str, HashMap<String, String>) {
+    match block_state
+        .strip_suffix(']')
+        .and_then(|block_state| block_state.split_once('['))
+    {
+        Some((location, properties)) => (location, parse_properties(properties)),
+        None => (block_state, HashMap::new()),
+    }
+}
+
+fn parse_properties(properties: &str) -> HashMap<String, String> {
+    properties
+        .split(',')
+        .filter_map(|property| property.split_once('='))
+        .map(|(key, value)| (key.trim().to_string(), value.trim().to_string()))
+        .collect()
+}
+
+/// Follows texture variables until a texture is found, which is returned with
+/// its namespace, or the last variable if it can't be resolved.
+fn resolve_texture(textures: &HashMap<String, String>, texture: &str) -> String {
+    let mut texture = texture;
+    for _ in 0..textures.len() + 1 {
+        let Some(variable) = texture.strip_prefix('#') else {
+            return normalize(texture);
+        };
+        match textures.get(variable) {
+            Some(next_texture) => texture = next_texture,
+            None => break,
+        }
+    }
+    texture.to_string()
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use std::{
+        collections::HashMap,
+        path::{Path, PathBuf},
+    };
+
+    use crate::resource_pack::{model::FaceEnum, Resource, ResourcePack};
+
+    /// Directory in the system's temporary directory, which is removed when
+    /// dropped, also if the test fails.
+    pub(crate) struct TempDir(PathBuf);
+
+    impl TempDir {
+        pub(crate) fn new() -> Self {
+            let path = std::env::temp_dir().join(uuid::Uuid::new_v4().to_string());
+            std::fs::create_dir_all(&path).unwrap();
+            Self(path)
+        }
+
+        pub(crate) fn path(&self) -> &Path {
+            &self.0
+        }
+
+        /// Writes the file, creating its parent directories.
+        pub(crate) fn write(&self, path: &str, contents: impl AsRef<[u8]>) {
+            let path = self.0.join(path);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, contents).unwrap();
+        }
+    }
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_dir_all(&self.0);
+        }
+    }
+
+    #[test]
+    fn resolve() {
+        let root = TempDir::new();
+        root.write(
+            "assets/minecraft/blockstates/log.json",
+            r#"{"variants": {
+                "axis=y": {"model": "block/log"},
+                "axis=x": [{"model": "block/log", "x": 90, "y": 90, "weight": 0}, {"model": "block/log", "x": 90, "uvlock": true}]
+            }}"#,
+        );
+        root.write(
+            "assets/minecraft/blockstates/fence.json",
+            r#"{"multipart": [
+                {"apply": {"model": "block/log"}},
+                {"when": {"north": "true|low"}, "apply": {"model": "block/log", "y": 180}},
+                {"when": {"OR": [{"east": "true"}, {"west": "true"}]}, "apply": {"model": "block/log", "y": 90}}
+            ]}"#,
+        );
+        root.write(
+            "assets/minecraft/models/block/cube.json",
+            r##"{"elements": [{"from": [0, 0, 0], "to": [16, 16, 16], "faces": {
+                "up": {"texture": "#end", "cullface": "up"},
+                "north": {"texture": "#side"},
+                "down": {"texture": "#missing"}
+            }}]}"##,
+        );
+        root.write(
+            "assets/minecraft/models/block/column.json",
+            r##"{"parent": "block/cube", "textures": {"side": "#all"}}"##,
+        );
+        root.write(
+            "assets/minecraft/models/block/log.json",
+            r#"{"parent": "minecraft:block/column", "textures": {"all": "block/log", "end": "other:block/log_top"}}"#,
+        );
+
+        let mut resource_pack = ResourcePack::open(root.path()).unwrap();
+        let models = resource_pack.resolve("minecraft:log[axis=x]", 0).unwrap();
+        assert_eq!(models.len(), 1);
+        assert_eq!((models[0].x, models[0].y, models[0].uv_lock), (90, 0, true));
+        let faces = &models[0].model.elements[0].faces;
+        assert_eq!(faces[&FaceEnum::Up].texture, "other:block/log_top");
+        assert_eq!(faces[&FaceEnum::North].texture, "minecraft:block/log");
+        assert_eq!(faces[&FaceEnum::Down].texture, "#missing");
+
+        let models = resource_pack
+            .resolve("fence[north=low,east=false,west=true]", 0)
+            .unwrap();
+        assert_eq!(
+            models.iter().map(|model| model.y).collect::<Vec<_>>(),
+            [0, 180, 90]
+        );
+        let models = resource_pack.resolve("fence", 0).unwrap();
+        assert_eq!(models.len(), 1);
+    }
+
+    #[test]
+    fn lossless() {
+        let root = TempDir::new();
+        let files = [
+            (
+                "pack.mcmeta",
+                r#"{
+                    "pack": {"pack_format": 13, "description": {"text": "Pack", "color": "gold"}, "supported_formats": [13, 15]},
+                    "filter": {"block": [{"namespace": "minecraft", "path": "textures/block/.*"}, {"path": "sounds\\.json"}]},
+                    "overlays": {"entries": [{"formats": {"min_inclusive": 15, "max_inclusive": 18}, "directory": "overlay"}]},
+                    "language": {"tlh_aa": {"name": "tlhIngan Hol", "region": "Qo'noS", "bidirectional": true}},
+                    "credit": "Made by someone"
+                }"#,
+            ),
+            (
+                "assets/minecraft/textures/block/lava.png.mcmeta",
+                r#"{"animation": {"frametime": 2, "frames": [0, {"index": 1, "time": 4}]}, "texture": {"blur": true}}"#,
+            ),
+            (
+                "assets/minecraft/sounds.json",
+                r#"{
+                    "block.stone.break": {"replace": true, "subtitle": "subtitles.block.generic.break", "sounds": [
+                        "dig/stone1",
+                        {"name": "dig/stone2", "volume": 0.5, "pitch": 0.75, "weight": 2, "stream": true, "attenuation_distance": 8, "preload": true},
+                        {"name": "block.stone.hit", "type": "event"}
+                    ]}
+                }"#,
+            ),
+            (
+                "assets/minecraft/lang/en_us.json",
+                r#"{"block.minecraft.stone": "Stone", "menu.quit": "Quit"}"#,
+            ),
+            (
+                "overlay/assets/minecraft/lang/de_de.json",
+                r#"{"block.minecraft.stone": "Stein"}"#,
+            ),
+            (
+                "assets/minecraft/font/default.json",
+                r#"{"providers": [
+                    {"type": "bitmap", "file": "font/ascii.png", "height": 7, "ascent": 7, "chars": ["ab", "cd"], "filter": {"uniform": true}},
+                    {"type": "ttf", "file": "font/regular.ttf", "shift": [0.5, 1.5], "size": 12.5, "oversample": 2.0, "skip": ["ab", "cd"]},
+                    {"type": "ttf", "file": "font/bold.ttf", "skip": "e"},
+                    {"type": "space", "advances": {" ": 4.0}},
+                    {"type": "legacy_unicode", "sizes": "font/glyph_sizes.bin", "template": "font/unicode_page_%s.png"}
+                ]}"#,
+            ),
+            (
+                "assets/minecraft/models/item/bow.json",
+                r#"{"parent": "item/generated", "gui_light": "front", "textures": {"layer0": "item/bow"}, "overrides": [
+                    {"predicate": {"pulling": 1.0}, "model": "item/bow_pulling_0"},
+                    {"predicate": {"pulling": 1.0, "pull": 0.65}, "model": "item/bow_pulling_1"}
+                ]}"#,
+            ),
+            (
+                "assets/minecraft/models/block/lamp.json",
+                r##"{
+                    "credit": "Made with Blockbench", "texture_size": [32, 32],
+                    "display": {"gui": {"rotation": [30.0, 225.0, 0.0], "scale": [0.625, 0.625, 0.625]}},
+                    "elements": [{"name": "base", "from": [0.0, 0.0, 0.0], "to": [16.0, 8.0, 16.0], "faces": {
+                        "up": {"texture": "#top", "uv": [0.0, 0.0, 16.0, 16.0], "emissive": true}
+                    }}]
+                }"##,
+            ),
+            (
+                "assets/minecraft/models/block/broken.json",
+                "{\"elements\": ",
+            ),
+            ("pack.png", "\u{89}PNG"),
+        ];
+        for (path, contents) in files {
+            root.write(path, contents);
+        }
+
+        let resources = ResourcePack::open(root.path())
+            .unwrap()
+            .resources()
+            .unwrap();
+        assert!(matches!(resources["pack.mcmeta"], Resource::McMeta(_)));
+        assert!(matches!(
+            resources["overlay/assets/minecraft/lang/de_de.json"],
+            Resource::Language(_)
+        ));
+        assert!(matches!(resources["pack.png"], Resource::Raw(_)));
+        // files which fail to parse don't fail the pack
+        assert!(matches!(
+            resources["assets/minecraft/models/block/broken.json"],
+            Resource::Raw(_)
+        ));
+        let Resource::Model(bow) = &resources["assets/minecraft/models/item/bow.json"] else {
+            panic!();
+        };
+        let predicates = HashMap::from([("pulling".to_string(), 1.0), ("pull".to_string(), 0.9)]);
+        assert_eq!(bow.override_model(&predicates), Some("item/bow_pulling_1"));
+        assert_eq!(bow.override_model(&HashMap::new()), None);
+
+        let output = TempDir::new();
+        crate::resource_pack::write(output.path(), &resources).unwrap();
+        for (path, contents) in files {
+            let written = std::fs::read(output.path().join(path)).unwrap();
+            if path == "pack.png" || path.ends_with("broken.json") {
+                assert_eq!(written, contents.as_bytes());
+            } else {
+                assert_eq!(
+                    serde_json::from_slice::<serde_json::Value>(&written).unwrap(),
+                    serde_json::from_str::<serde_json::Value>(contents).unwrap(),
+                    "{path}"
+                );
+            }
+        }
+    }
+}
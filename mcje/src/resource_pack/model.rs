@@ -3,7 +3,7 @@ use std::collections::HashMap;
 use glam::Vec3;
 use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 #[serde(rename_all = "lowercase")]
 pub struct Model {
     /// Loads a different model from the given path, in form of a resource
@@ -14,7 +14,11 @@ pub struct Model {
 
     /// Whether to use ambient occlusion (true - default), or not (false).
     /// Note:only works on Parent file
-    #[serde(default = "default_true", skip_serializing_if = "if_true")]
+    #[serde(
+        default = "default_true",
+        rename = "ambientocclusion",
+        skip_serializing_if = "if_true"
+    )]
     pub ambient_occlusion: bool,
 
     /// Holds the different places where item models are displayed.
@@ -34,26 +38,86 @@ pub struct Model {
 
     #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub groups: Vec<Group>,
+
+    /// Determines cases in which a different model should be used based on
+    /// item predicates, the last matching case is used.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub overrides: Vec<Override>,
+
+    /// Lights the item model in the GUI from the front or the side, defaults
+    /// to the side.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub gui_light: Option<GuiLight>,
+
+    /// Keys which aren't known, e.g. `texture_size` or `credit` written by
+    /// model editors, which are kept to write them back.
+    #[serde(flatten, skip_serializing_if = "HashMap::is_empty")]
+    pub extra: HashMap<String, serde_json::Value>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+impl Model {
+    /// Returns the model of the last override matching the item predicates,
+    /// e.g. `pull` or `custom_model_data`.
+    pub fn override_model(&self, predicates: &HashMap<String, f32>) -> Option<&str> {
+        self.overrides
+            .iter()
+            .rev()
+            .find(|item_override| item_override.matches(predicates))
+            .map(|item_override| item_override.model.as_str())
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Override {
+    /// Holds the cases, predicates which are not set are treated as 0.
+    pub predicate: HashMap<String, f32>,
+
+    /// The path to the model to use if the case is met, in form of a resource
+    /// location.
+    pub model: String,
+
+    #[serde(flatten, skip_serializing_if = "HashMap::is_empty")]
+    pub extra: HashMap<String, serde_json::Value>,
+}
+
+impl Override {
+    /// Returns whether all predicates of the item are at least the value of
+    /// the case.
+    pub fn matches(&self, predicates: &HashMap<String, f32>) -> bool {
+        self.predicate
+            .iter()
+            .all(|(key, value)| predicates.get(key).copied().unwrap_or(0.0) >= *value)
+    }
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum GuiLight {
+    Front,
+    Side,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct Display {
     /// Specifies the rotation of the model according to the scheme [x, y, z].
-    #[serde(default)]
+    #[serde(default, skip_serializing_if = "if_zero")]
     pub rotation: Vec3,
 
     /// Specifies the position of the model according to the scheme [x, y, z].
     /// The values are clamped between -80 and 80.
-    #[serde(default)]
+    #[serde(default, skip_serializing_if = "if_zero")]
     pub translation: Vec3,
 
     /// Specifies the scale of the model according to the scheme [x, y, z]. If
     /// the value is greater than 4, it is displayed as 4.
-    #[serde(default)]
+    #[serde(default, skip_serializing_if = "if_zero")]
     pub scale: Vec3,
+
+    #[serde(flatten, skip_serializing_if = "HashMap::is_empty")]
+    pub extra: HashMap<String, serde_json::Value>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct Element {
     /// Start point of a cuboid according to the scheme [x, y, z]. Values must
     /// be between -16 and 32.
@@ -74,9 +138,13 @@ pub struct Element {
     /// Holds all the faces of the cuboid. If a face is left out, it does not
     /// render.
     pub faces: HashMap<FaceEnum, Face>,
+
+    /// Keys which aren't known, e.g. the `name` of the element.
+    #[serde(flatten, skip_serializing_if = "HashMap::is_empty")]
+    pub extra: HashMap<String, serde_json::Value>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct Rotation {
     /// Sets the center of the rotation according to the scheme [x, y, z].
     pub origin: Vec3,
@@ -92,6 +160,9 @@ pub struct Rotation {
     /// be true or false. Defaults to false.
     #[serde(default, skip_serializing_if = "if_false")]
     pub rescale: bool,
+
+    #[serde(flatten, skip_serializing_if = "HashMap::is_empty")]
+    pub extra: HashMap<String, serde_json::Value>,
 }
 
 #[derive(Copy, Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
@@ -113,7 +184,7 @@ pub enum FaceEnum {
     Down,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 #[serde(rename_all = "lowercase")]
 pub struct Face {
     /// Defines the area of the texture to use according to the scheme [x1, y1,
@@ -133,7 +204,7 @@ pub struct Face {
     /// up, north, south, west, or east. It also determines the side of the
     /// block to use the light level from for lighting the face, and if unset,
     /// defaults to the side.
-    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[serde(default, rename = "cullface", skip_serializing_if = "Option::is_none")]
     pub cull_face: Option<FaceEnum>,
 
     /// Rotates the texture by the specified number of degrees. Can be 0, 90,
@@ -151,11 +222,18 @@ pub struct Face {
     /// multiple tint values, and thus the tint index value is ignored (as long
     /// as it is set to something other than -1); it could be used for modded
     /// blocks that need multiple distinct tint values in the same block though.
-    #[serde(default = "default_n1", skip_serializing_if = "if_n1")]
+    #[serde(
+        default = "default_n1",
+        rename = "tintindex",
+        skip_serializing_if = "if_n1"
+    )]
     pub tint_index: i32,
+
+    #[serde(flatten, skip_serializing_if = "HashMap::is_empty")]
+    pub extra: HashMap<String, serde_json::Value>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 #[serde(untagged, rename_all = "lowercase")]
 pub enum Group {
     Group {
@@ -189,3 +267,7 @@ fn if_0(value: &u32) -> bool {
 fn if_n1(value: &i32) -> bool {
     *value == -1
 }
+
+fn if_zero(value: &Vec3) -> bool {
+    *value == Vec3::ZERO
+}
@@ -0,0 +1,121 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+/// Contents of sounds.json, sound events by their name, e.g.
+/// `block.stone.break`.
+pub type Sounds = HashMap<String, SoundEvent>;
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SoundEvent {
+    /// Used only in resource packs. True if the sounds listed in sounds should
+    /// replace the sounds listed in the default sounds.json for this sound
+    /// event. False if the sounds listed should be added to the list of default
+    /// sounds. Defaults to false.
+    #[serde(default, skip_serializing_if = "if_false")]
+    pub replace: bool,
+
+    /// The sound files this sound event uses. One of the listed sounds is
+    /// randomly selected to play when this sound event is triggered.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub sounds: Vec<Sound>,
+
+    /// Translated as the subtitle of the sound if Show Subtitles is enabled
+    /// ingame.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub subtitle: Option<String>,
+
+    /// Keys which aren't known, which are kept to write them back.
+    #[serde(flatten, skip_serializing_if = "HashMap::is_empty")]
+    pub extra: HashMap<String, serde_json::Value>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum Sound {
+    /// The path to the sound file from the namespace's sounds folder, with all
+    /// other values at their default.
+    Name(String),
+    Object {
+        /// The path to the sound file from the namespace's sounds folder, or
+        /// the name of another sound event if the type is event.
+        name: String,
+
+        /// The volume for playing this sound. Value is a decimal between 0.0
+        /// and 1.0. Defaults to 1.0.
+        #[serde(default = "default_1f", skip_serializing_if = "if_1f")]
+        volume: f32,
+
+        /// Plays the pitch at the specified value. Defaults to 1.0.
+        #[serde(default = "default_1f", skip_serializing_if = "if_1f")]
+        pitch: f32,
+
+        /// The chance that this sound is selected to play when this sound event
+        /// is triggered. Defaults to 1.
+        #[serde(default = "default_1", skip_serializing_if = "if_1")]
+        weight: u32,
+
+        /// True if this sound should be streamed from its file. It is
+        /// recommended that this is set to true for sounds that have a
+        /// duration longer than a few seconds to avoid lag. Defaults to false.
+        #[serde(default, skip_serializing_if = "if_false")]
+        stream: bool,
+
+        /// Modify sound reduction rate based on distance. Defaults to 16.
+        #[serde(default = "default_16", skip_serializing_if = "if_16")]
+        attenuation_distance: u32,
+
+        /// True if this sound should be loaded when loading the pack instead of
+        /// when the sound is played. Defaults to false.
+        #[serde(default, skip_serializing_if = "if_false")]
+        preload: bool,
+
+        /// Whether the name refers to a sound file or another sound event.
+        /// Defaults to file.
+        #[serde(default, rename = "type", skip_serializing_if = "if_file")]
+        kind: SoundKind,
+
+        #[serde(flatten, skip_serializing_if = "HashMap::is_empty")]
+        extra: HashMap<String, serde_json::Value>,
+    },
+}
+
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SoundKind {
+    #[default]
+    File,
+    Event,
+}
+
+fn default_1() -> u32 {
+    1
+}
+
+fn default_1f() -> f32 {
+    1.0
+}
+
+fn default_16() -> u32 {
+    16
+}
+
+fn if_false(value: &bool) -> bool {
+    !*value
+}
+
+fn if_1(value: &u32) -> bool {
+    *value == 1
+}
+
+fn if_1f(value: &f32) -> bool {
+    *value == 1.0
+}
+
+fn if_16(value: &u32) -> bool {
+    *value == 16
+}
+
+fn if_file(value: &SoundKind) -> bool {
+    *value == SoundKind::File
+}
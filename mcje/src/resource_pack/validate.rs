@@ -0,0 +1,416 @@
+//! Finds problems in resource packs, which otherwise only show up in-game as
+//! missing models and textures. Models, parents and textures are looked up in
+//! the pack and its bases, see [`ResourcePack::with_base`], so packs which
+//! build on the vanilla one can be validated against the client jar.
+
+use std::{
+    collections::{HashMap, HashSet},
+    io::Cursor,
+};
+
+use image::{io::Reader, ImageFormat};
+
+use crate::{
+    resource_pack::{
+        block_state::{BlockState, When},
+        mcmeta::McMeta,
+        normalize, path, ResourcePack,
+    },
+    Error, Result,
+};
+
+/// Problem in a file of the pack, the path is relative to the root of the
+/// pack.
+#[derive(thiserror::Error, Debug, Clone, PartialEq, Eq)]
+pub enum Problem {
+    #[error("{path}: {message}")]
+    Invalid { path: String, message: String },
+    #[error("{path}: missing parent model {parent}")]
+    MissingParent { path: String, parent: String },
+    #[error("{path}: circular parent")]
+    CircularParent { path: String },
+    #[error("{path}: unresolved texture variable {variable}")]
+    UnresolvedTexture { path: String, variable: String },
+    #[error("{path}: missing model {model}")]
+    MissingModel { path: String, model: String },
+    #[error("{path}: {reason}")]
+    InvalidAnimation { path: String, reason: String },
+    #[error("{path}: invalid condition, {reason}")]
+    InvalidWhen { path: String, reason: String },
+}
+
+/// Validates all block states, models and texture animations of the pack,
+/// files of its bases are only used to resolve references. Texture variables
+/// are only checked for models which are used by block states or are item
+/// models, as other models are usually templates.
+pub fn validate(resource_pack: &mut ResourcePack) -> Result<Vec<Problem>> {
+    let mut problems = vec![];
+    let mut checked_models = HashSet::new();
+    for file_path in resource_pack.paths()? {
+        let Some((namespace, relative_path)) = file_path
+            .strip_prefix("assets/")
+            .and_then(|path| path.split_once('/'))
+        else {
+            continue;
+        };
+        if let Some(location) = relative_path
+            .strip_prefix("blockstates/")
+            .and_then(|path| path.strip_suffix(".json"))
+        {
+            let block_state = match resource_pack.block_state(&format!("{namespace}:{location}")) {
+                Ok(block_state) => block_state,
+                Err(Error::Json(error)) => {
+                    problems.push(Problem::Invalid {
+                        path: file_path,
+                        message: error.to_string(),
+                    });
+                    continue;
+                }
+                Err(error) => return Err(error),
+            };
+            let models = match &*block_state {
+                BlockState::Variants(variants) => variants
+                    .values()
+                    .flat_map(|variant| &variant.0)
+                    .collect::<Vec<_>>(),
+                BlockState::Multipart(cases) => {
+                    for when in cases.iter().filter_map(|case| case.when.as_ref()) {
+                        if let Err(reason) = validate_when(when) {
+                            problems.push(Problem::InvalidWhen {
+                                path: file_path.clone(),
+                                reason,
+                            });
+                        }
+                    }
+                    cases.iter().flat_map(|case| &case.apply).collect()
+                }
+            };
+            for model in models {
+                let model = normalize(&model.model);
+                if resource_pack.contains(&path("models", &model, "json")) {
+                    validate_textures(resource_pack, &model, &mut checked_models, &mut problems)?;
+                } else if !model.starts_with("minecraft:builtin/") {
+                    problems.push(Problem::MissingModel {
+                        path: file_path.clone(),
+                        model,
+                    });
+                }
+            }
+        } else if let Some(location) = relative_path
+            .strip_prefix("models/")
+            .and_then(|path| path.strip_suffix(".json"))
+        {
+            let location = format!("{namespace}:{location}");
+            let model = match resource_pack.model(&location) {
+                Ok(model) => model,
+                Err(Error::Json(error)) => {
+                    problems.push(Problem::Invalid {
+                        path: file_path,
+                        message: error.to_string(),
+                    });
+                    continue;
+                }
+                Err(error) => return Err(error),
+            };
+            if let Some(parent) = model.parent.as_deref().map(normalize) {
+                if !parent.starts_with("minecraft:builtin/")
+                    && !resource_pack.contains(&path("models", &parent, "json"))
+                {
+                    problems.push(Problem::MissingParent {
+                        path: file_path,
+                        parent,
+                    });
+                    continue;
+                }
+            }
+            if location.contains(":item/") {
+                validate_textures(resource_pack, &location, &mut checked_models, &mut problems)?;
+            }
+        } else if let Some(texture_path) = relative_path
+            .strip_prefix("textures/")
+            .and_then(|path| path.strip_suffix(".png.mcmeta"))
+        {
+            let texture_path = format!("assets/{namespace}/textures/{texture_path}.png");
+            let mcmeta = match serde_json::from_slice::<McMeta>(&resource_pack.read(&file_path)?) {
+                Ok(mcmeta) => mcmeta,
+                Err(error) => {
+                    problems.push(Problem::Invalid {
+                        path: file_path,
+                        message: error.to_string(),
+                    });
+                    continue;
+                }
+            };
+            let Some(animation) = mcmeta.animation else {
+                continue;
+            };
+            let image = match resource_pack.read(&texture_path) {
+                Ok(image) => image,
+                Err(Error::MissingResource(_)) => continue,
+                Err(error) => return Err(error),
+            };
+            let Ok((width, height)) =
+                Reader::with_format(Cursor::new(image), ImageFormat::Png).into_dimensions()
+            else {
+                problems.push(Problem::Invalid {
+                    path: texture_path,
+                    message: "invalid image".to_string(),
+                });
+                continue;
+            };
+
+            let (frame_width, frame_height) = animation.frame_size(width, height);
+            if frame_width == 0
+                || frame_height == 0
+                || !width.is_multiple_of(frame_width)
+                || !height.is_multiple_of(frame_height)
+            {
+                problems.push(Problem::InvalidAnimation {
+                    path: texture_path,
+                    reason: format!(
+                        "{width}x{height} texture doesn't split into {frame_width}x{frame_height} frames"
+                    ),
+                });
+                continue;
+            }
+            let frame_count = (width / frame_width) * (height / frame_height);
+            for (index, time) in animation.frames(frame_count) {
+                let reason = if index >= frame_count {
+                    format!("frame {index} is out of {frame_count} frames")
+                } else if time == 0 {
+                    format!("frame {index} has no time")
+                } else {
+                    continue;
+                };
+                problems.push(Problem::InvalidAnimation {
+                    path: texture_path.clone(),
+                    reason,
+                });
+            }
+        }
+    }
+    Ok(problems)
+}
+
+/// Checks that all texture variables of the model resolve, once per model.
+fn validate_textures(
+    resource_pack: &mut ResourcePack,
+    location: &str,
+    checked_models: &mut HashSet<String>,
+    problems: &mut Vec<Problem>,
+) -> Result<()> {
+    if !checked_models.insert(location.to_string()) {
+        return Ok(());
+    }
+    let model = match resource_pack.resolved_model(location) {
+        Ok(model) => model,
+        Err(Error::CircularParent(_)) => {
+            problems.push(Problem::CircularParent {
+                path: path("models", location, "json"),
+            });
+            return Ok(());
+        }
+        // reported with the model which is invalid or has a missing parent
+        Err(Error::Json(_) | Error::MissingResource(_)) => return Ok(()),
+        Err(error) => return Err(error),
+    };
+    let mut variables = model
+        .textures
+        .values()
+        .chain(
+            model
+                .elements
+                .iter()
+                .flat_map(|element| element.faces.values())
+                .map(|face| &face.texture),
+        )
+        .filter(|texture| texture.starts_with('#'))
+        .cloned()
+        .collect::<Vec<_>>();
+    variables.sort();
+    variables.dedup();
+    problems.extend(
+        variables
+            .into_iter()
+            .map(|variable| Problem::UnresolvedTexture {
+                path: path("models", location, "json"),
+                variable,
+            }),
+    );
+    Ok(())
+}
+
+/// Checks that all values are non-empty, and only `OR` and `AND` are used to
+/// combine conditions.
+fn validate_when(when: &When) -> std::result::Result<(), String> {
+    fn validate_conditions(
+        conditions: &HashMap<String, String>,
+    ) -> std::result::Result<(), String> {
+        for (key, values) in conditions {
+            if key.is_empty() {
+                return Err("empty property".to_string());
+            }
+            let values = values.strip_prefix('!').unwrap_or(values);
+            if values.split('|').any(str::is_empty) {
+                return Err(format!("empty value for {key}"));
+            }
+        }
+        Ok(())
+    }
+
+    match when {
+        When::One(conditions) => validate_conditions(conditions),
+        When::Many(operations) => {
+            for (operator, conditions) in operations {
+                if operator != "OR" && operator != "AND" {
+                    return Err(format!("unknown operator {operator}"));
+                }
+                if conditions.is_empty() {
+                    return Err(format!("empty {operator}"));
+                }
+                for conditions in conditions {
+                    validate_conditions(conditions)?;
+                }
+            }
+            Ok(())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::resource_pack::{
+        tests::TempDir,
+        validate::{validate, Problem},
+        ResourcePack,
+    };
+
+    #[test]
+    fn problems() {
+        let root = TempDir::new();
+        root.write(
+            "assets/minecraft/blockstates/fence.json",
+            br#"{"multipart": [
+                {"apply": {"model": "block/fence"}},
+                {"when": {"XOR": [{"north": "true"}]}, "apply": {"model": "block/fence_side"}},
+                {"when": {"east": "true||low"}, "apply": {"model": "block/fence"}}
+            ]}"#,
+        );
+        root.write(
+            "assets/minecraft/models/block/fence.json",
+            br##"{"parent": "block/template", "textures": {"texture": "block/oak_planks"}}"##,
+        );
+        root.write(
+            "assets/minecraft/models/block/template.json",
+            br##"{"elements": [{"from": [0, 0, 0], "to": [16, 16, 16], "faces": {
+                "up": {"texture": "#texture"},
+                "down": {"texture": "#bottom"}
+            }}]}"##,
+        );
+        root.write(
+            "assets/minecraft/models/item/fence.json",
+            br#"{"parent": "block/fence_inventory"}"#,
+        );
+        root.write("assets/minecraft/models/item/broken.json", b"{");
+
+        let mut png = vec![];
+        image::RgbaImage::new(16, 24)
+            .write_to(&mut std::io::Cursor::new(&mut png), image::ImageFormat::Png)
+            .unwrap();
+        root.write("assets/minecraft/textures/block/lava.png", &png);
+        root.write(
+            "assets/minecraft/textures/block/lava.png.mcmeta",
+            br#"{"animation": {}}"#,
+        );
+
+        let mut resource_pack = ResourcePack::open(root.path()).unwrap();
+        let problems = validate(&mut resource_pack).unwrap();
+        assert_eq!(problems.len(), 7);
+        assert_eq!(
+            problems[..4],
+            [
+                Problem::InvalidWhen {
+                    path: "assets/minecraft/blockstates/fence.json".to_string(),
+                    reason: "unknown operator XOR".to_string()
+                },
+                Problem::InvalidWhen {
+                    path: "assets/minecraft/blockstates/fence.json".to_string(),
+                    reason: "empty value for east".to_string()
+                },
+                Problem::UnresolvedTexture {
+                    path: "assets/minecraft/models/block/fence.json".to_string(),
+                    variable: "#bottom".to_string()
+                },
+                Problem::MissingModel {
+                    path: "assets/minecraft/blockstates/fence.json".to_string(),
+                    model: "minecraft:block/fence_side".to_string()
+                },
+            ]
+        );
+        assert!(
+            matches!(&problems[4], Problem::Invalid { path, .. } if path.ends_with("item/broken.json"))
+        );
+        assert_eq!(
+            problems[5],
+            Problem::MissingParent {
+                path: "assets/minecraft/models/item/fence.json".to_string(),
+                parent: "minecraft:block/fence_inventory".to_string()
+            }
+        );
+        assert_eq!(
+            problems[6].to_string(),
+            "assets/minecraft/textures/block/lava.png: 16x24 texture doesn't split into 16x16 frames"
+        );
+    }
+
+    #[test]
+    fn base() {
+        let base = TempDir::new();
+        base.write(
+            "assets/minecraft/models/block/cube_all.json",
+            br##"{"elements": [{"from": [0, 0, 0], "to": [16, 16, 16], "faces": {
+                "up": {"texture": "#all"}
+            }}]}"##,
+        );
+        base.write(
+            "assets/minecraft/models/block/fence_inventory.json",
+            br#"{"parent": "block/cube_all", "textures": {"all": "block/oak_planks"}}"#,
+        );
+        // files of the bases are not validated
+        base.write("assets/minecraft/models/block/broken.json", b"{");
+
+        let root = TempDir::new();
+        root.write(
+            "assets/minecraft/blockstates/stone.json",
+            br#"{"variants": {"": {"model": "block/stone"}}}"#,
+        );
+        root.write(
+            "assets/minecraft/models/block/stone.json",
+            br#"{"parent": "block/cube_all", "textures": {"all": "block/stone"}}"#,
+        );
+        root.write(
+            "assets/minecraft/models/item/fence.json",
+            br#"{"parent": "block/fence_inventory"}"#,
+        );
+        root.write(
+            "assets/minecraft/models/item/other.json",
+            br#"{"parent": "block/other"}"#,
+        );
+
+        let mut resource_pack = ResourcePack::open(root.path())
+            .unwrap()
+            .with_base(ResourcePack::open(base.path()).unwrap());
+        let problems = validate(&mut resource_pack).unwrap();
+        // models, parents and their texture variables resolve through the base
+        assert_eq!(
+            problems,
+            [Problem::MissingParent {
+                path: "assets/minecraft/models/item/other.json".to_string(),
+                parent: "minecraft:block/other".to_string()
+            }]
+        );
+        // and the pack alone misses them
+        let problems = validate(&mut ResourcePack::open(root.path()).unwrap()).unwrap();
+        assert_eq!(problems.len(), 3);
+    }
+}
@@ -0,0 +1,815 @@
+use std::{
+    borrow::Cow,
+    collections::{BTreeMap, HashMap},
+    net::IpAddr,
+    sync::{
+        atomic::{AtomicI32, Ordering},
+        Arc, Mutex, RwLock,
+    },
+    time::Duration,
+};
+
+use glam::DVec3;
+use serde_value::Value;
+use tokio::{
+    io::{AsyncRead, AsyncWrite},
+    net::TcpListener,
+    sync::mpsc,
+};
+use uuid::Uuid;
+
+use crate::{
+    channel::LoginQueries,
+    codec::Compression,
+    connection::{self, Connection},
+    forwarding::{ForwardedPlayer, Forwarding, VELOCITY_CHANNEL, VELOCITY_VERSION},
+    keep_alive::KeepAliveSender,
+    packet::{
+        c2s,
+        s2c::{
+            self,
+            game::{
+                LevelChunkPacketData, LightUpdatePacketData, PlayerInfoUpdatePacket,
+                PlayerInfoUpdatePacketEntry,
+            },
+        },
+    },
+    types::{
+        Component, GameType, Intention, Json, Nbt, PalettedContainer, Registries, Status,
+        StatusPlayers, StatusPlayersSample, StatusVersion, User, VarI32,
+    },
+    Encode, Error, Result, PROTOCOL_VERSION, VERSION,
+};
+
+pub struct ServerConfig {
+    /// Status sent in response to server list pings, players and version are
+    /// filled in by the server.
+    pub status: Status,
+    pub max_players: i32,
+    pub compression_threshold: Option<u16>,
+    pub keep_alive_interval: Duration,
+    pub keep_alive_timeout: Duration,
+    /// Has to match the forwarding of the proxy, if the server is behind one.
+    pub forwarding: Forwarding,
+
+    pub registries: Registries<'static>,
+    pub dimension_type: String,
+    pub dimension: String,
+    pub game_type: GameType,
+    pub spawn_pos: DVec3,
+    pub chunk_radius: i32,
+}
+
+/// Hooks into the server, all methods are called from the task of the
+/// respective connection.
+#[allow(unused_variables)]
+pub trait Plugin: Send + Sync + 'static {
+    fn status(&self, server: &Server, status: &mut Status) {}
+
+    /// Called before the game profile is sent, returning an error disconnects
+    /// the user with the given reason.
+    #[allow(clippy::result_large_err)]
+    fn login(&self, server: &Server, user: &mut User) -> std::result::Result<(), Component> {
+        Ok(())
+    }
+
+    /// Provides the chunk at the given chunk coordinates, the first plugin
+    /// returning a chunk wins, otherwise an empty chunk is sent.
+    fn chunk(&self, server: &Server, x: i32, z: i32) -> Option<LevelChunkPacketData<'static>> {
+        None
+    }
+
+    fn join(&self, server: &Server, player: &Arc<Player>) {}
+
+    fn packet(&self, server: &Server, player: &Arc<Player>, packet: &c2s::GamePacket) {}
+
+    fn leave(&self, server: &Server, player: &Arc<Player>) {}
+}
+
+pub struct Player {
+    entity_id: i32,
+    user: User,
+    address: Option<IpAddr>,
+    pos: Mutex<DVec3>,
+    latency: AtomicI32,
+    sender: mpsc::UnboundedSender<s2c::GamePacket<'static>>,
+}
+
+impl Player {
+    pub fn entity_id(&self) -> i32 {
+        self.entity_id
+    }
+
+    pub fn user(&self) -> &User {
+        &self.user
+    }
+
+    /// Real address of the player, only known if forwarded by a proxy.
+    pub fn address(&self) -> Option<IpAddr> {
+        self.address
+    }
+
+    pub fn pos(&self) -> DVec3 {
+        *self.pos.lock().unwrap()
+    }
+
+    /// Smoothed round-trip time of keep alives in milliseconds.
+    pub fn latency(&self) -> i32 {
+        self.latency.load(Ordering::Relaxed)
+    }
+
+    /// Queues a packet to be sent, packets sent to already disconnected players
+    /// are discarded.
+    pub fn send(&self, packet: s2c::GamePacket<'static>) {
+        let _ = self.sender.send(packet);
+    }
+
+    /// Sends a disconnect packet and closes the connection afterwards.
+    pub fn disconnect(&self, reason: &Component) {
+        self.send(s2c::GamePacket::Disconnect {
+            reason: Json(reason.clone()),
+        });
+    }
+}
+
+pub struct Server {
+    config: ServerConfig,
+    plugins: Vec<Box<dyn Plugin>>,
+
+    players: RwLock<HashMap<Uuid, Arc<Player>>>,
+    next_entity_id: AtomicI32,
+}
+
+impl Server {
+    pub fn new(config: ServerConfig) -> Self {
+        Self {
+            config,
+            plugins: vec![],
+            players: Default::default(),
+            next_entity_id: AtomicI32::new(1),
+        }
+    }
+
+    pub fn add_plugin(&mut self, plugin: impl Plugin) {
+        self.plugins.push(Box::new(plugin));
+    }
+
+    pub fn config(&self) -> &ServerConfig {
+        &self.config
+    }
+
+    pub fn players(&self) -> Vec<Arc<Player>> {
+        self.players.read().unwrap().values().cloned().collect()
+    }
+
+    pub fn player(&self, id: &Uuid) -> Option<Arc<Player>> {
+        self.players.read().unwrap().get(id).cloned()
+    }
+
+    /// Sends a packet to all players in game.
+    pub fn broadcast(&self, packet: &s2c::GamePacket<'static>) {
+        for player in self.players.read().unwrap().values() {
+            player.send(packet.clone());
+        }
+    }
+
+    pub fn status(&self) -> Status {
+        let mut status = self.config.status.clone();
+        status.version = Some(StatusVersion::default());
+        {
+            let players = self.players.read().unwrap();
+            status.players = Some(StatusPlayers {
+                max: self.config.max_players,
+                online: players.len() as i32,
+                sample: players
+                    .values()
+                    .take(12)
+                    .map(|player| StatusPlayersSample {
+                        id: player.user.id.hyphenated().to_string(),
+                        name: player.user.name.clone(),
+                    })
+                    .collect(),
+            });
+        }
+        for plugin in &self.plugins {
+            plugin.status(self, &mut status);
+        }
+        status
+    }
+
+    /// Accepts connections forever, each connection is handled in its own
+    /// task.
+    pub async fn run(self: Arc<Self>, listener: TcpListener) {
+        loop {
+            let (socket, _) = connection::accept(&listener).await;
+            let server = self.clone();
+            tokio::spawn(async move {
+                let _ = server.handle(socket).await;
+            });
+        }
+    }
+
+    /// Handles a single connection, starting from the handshake.
+    pub async fn handle<S: AsyncRead + AsyncWrite + Unpin>(&self, stream: S) -> Result<()> {
+        let mut connection = Connection::new(stream);
+        let c2s::HandshakePacket::Intention {
+            protocol_version,
+            host_name,
+            intention,
+            ..
+        } = connection.receive().await?.decode()?;
+        match intention {
+            Intention::Status => self.handle_status(connection).await,
+            Intention::Login => {
+                if protocol_version != PROTOCOL_VERSION {
+                    connection
+                        .send(&s2c::LoginPacket::LoginDisconnect {
+                            reason: Json(Component::Literal(format!(
+                                "Outdated client! Please use {VERSION}"
+                            ))),
+                        })
+                        .await?;
+                    return Err(Error::Unexpected);
+                }
+                self.handle_login(connection, &host_name).await
+            }
+            Intention::Game => Err(Error::Unexpected),
+        }
+    }
+
+    async fn handle_status<S: AsyncRead + AsyncWrite + Unpin>(
+        &self,
+        mut connection: Connection<S>,
+    ) -> Result<()> {
+        loop {
+            match connection.receive().await?.decode()? {
+                c2s::StatusPacket::StatusRequest => {
+                    connection
+                        .send(&s2c::StatusPacket::StatusResponse {
+                            status: Json(self.status()),
+                        })
+                        .await?;
+                }
+                c2s::StatusPacket::PingRequest { time } => {
+                    connection
+                        .send(&s2c::StatusPacket::PongResponse { time })
+                        .await?;
+                    return Ok(());
+                }
+            }
+        }
+    }
+
+    async fn handle_login<S: AsyncRead + AsyncWrite + Unpin>(
+        &self,
+        mut connection: Connection<S>,
+        host_name: &str,
+    ) -> Result<()> {
+        let c2s::LoginPacket::Hello { name, uuid } = connection.receive().await?.decode()? else {
+            return Err(Error::Unexpected);
+        };
+        let forwarded_player = match &self.config.forwarding {
+            Forwarding::None => Ok(None),
+            Forwarding::BungeeCord => ForwardedPlayer::from_bungeecord(host_name, name.clone())
+                .map(|(_, player)| Some(player))
+                .map_err(|_| {
+                    Component::Literal(
+                        "If you wish to use IP forwarding, please enable it in your BungeeCord \
+                         config as well!"
+                            .to_string(),
+                    )
+                }),
+            Forwarding::Velocity { secret } => {
+                let mut queries = LoginQueries::default();
+                connection
+                    .send(&queries.request_raw(VELOCITY_CHANNEL, vec![VELOCITY_VERSION]))
+                    .await?;
+                let packet = connection.receive().await?;
+                let c2s::LoginPacket::CustomQuery {
+                    transaction_id,
+                    data,
+                } = packet.decode()?
+                else {
+                    return Err(Error::Unexpected);
+                };
+                queries.response(transaction_id)?;
+                data.and_then(|data| ForwardedPlayer::from_velocity(secret, &data.0).ok())
+                    .map(Some)
+                    .ok_or_else(|| {
+                        Component::Literal(
+                            "This server requires you to connect with Velocity.".to_string(),
+                        )
+                    })
+            }
+        };
+        let forwarded_player = match forwarded_player {
+            Ok(forwarded_player) => forwarded_player,
+            Err(reason) => {
+                connection
+                    .send(&s2c::LoginPacket::LoginDisconnect {
+                        reason: Json(reason),
+                    })
+                    .await?;
+                return Ok(());
+            }
+        };
+        let (mut user, address) = match forwarded_player {
+            Some(player) => (player.user, Some(player.address)),
+            None => (
+                User {
+                    id: uuid.unwrap_or_else(Uuid::new_v4),
+                    name,
+                    properties: vec![],
+                },
+                None,
+            ),
+        };
+        let mut login_result = Ok(());
+        for plugin in &self.plugins {
+            if login_result.is_err() {
+                break;
+            }
+            login_result = plugin.login(self, &mut user);
+        }
+        if let Err(reason) = login_result {
+            connection
+                .send(&s2c::LoginPacket::LoginDisconnect {
+                    reason: Json(reason),
+                })
+                .await?;
+            return Ok(());
+        }
+
+        // joins before spawning, packets sent to the player are queued until
+        // then
+        let (sender, mut receiver) = mpsc::unbounded_channel();
+        let player = Arc::new(Player {
+            entity_id: self.next_entity_id.fetch_add(1, Ordering::Relaxed),
+            user,
+            address,
+            pos: Mutex::new(self.config.spawn_pos),
+            latency: AtomicI32::new(0),
+            sender,
+        });
+        if !self.join(&player) {
+            connection
+                .send(&s2c::LoginPacket::LoginDisconnect {
+                    reason: Json(Component::Literal("You are already connected".to_string())),
+                })
+                .await?;
+            return Ok(());
+        }
+        let result = self.start(&mut connection, &player, &mut receiver).await;
+        self.leave(&player);
+        result
+    }
+
+    async fn start<S: AsyncRead + AsyncWrite + Unpin>(
+        &self,
+        connection: &mut Connection<S>,
+        player: &Arc<Player>,
+        receiver: &mut mpsc::UnboundedReceiver<s2c::GamePacket<'static>>,
+    ) -> Result<()> {
+        if let Some(compression_threshold) = self.config.compression_threshold {
+            connection
+                .send(&s2c::LoginPacket::LoginCompression {
+                    compression_threshold: compression_threshold as i32,
+                })
+                .await?;
+            connection
+                .codec_mut()
+                .enable_compression(Compression::default(), compression_threshold);
+        }
+        connection
+            .send(&s2c::LoginPacket::GameProfile {
+                game_profile: player.user.clone(),
+            })
+            .await?;
+        self.spawn(connection, player).await?;
+        self.play(connection, player, receiver).await
+    }
+
+    async fn spawn<S: AsyncRead + AsyncWrite + Unpin>(
+        &self,
+        connection: &mut Connection<S>,
+        player: &Player,
+    ) -> Result<()> {
+        let registries = &self.config.registries;
+        let section_count = registries
+            .dimension_type
+            .value
+            .iter()
+            .find(|entry| entry.name == self.config.dimension_type)
+            .map_or(24, |entry| entry.element.height / 16);
+        connection
+            .send(&s2c::GamePacket::Login {
+                player_id: player.entity_id,
+                hardcore: false,
+                game_type: self.config.game_type,
+                previous_game_type: -1,
+                levels: vec![self.config.dimension.clone()],
+                registry_holder: Nbt(Registries {
+                    dimension_type: Cow::Borrowed(&registries.dimension_type),
+                    biome: Cow::Borrowed(&registries.biome),
+                    chat_type: Cow::Borrowed(&registries.chat_type),
+                    damage_type: Cow::Borrowed(&registries.damage_type),
+                }),
+                dimension_type: self.config.dimension_type.clone(),
+                dimension: self.config.dimension.clone(),
+                seed: 0,
+                max_players: self.config.max_players,
+                chunk_radius: self.config.chunk_radius,
+                simulation_distance: self.config.chunk_radius,
+                reduced_debug_info: false,
+                show_death_screen: true,
+                is_debug: false,
+                is_flat: false,
+                last_death_location: None,
+            })
+            .await?;
+        let spawn_pos = self.config.spawn_pos;
+        connection
+            .send(&s2c::GamePacket::SetDefaultSpawnPosition {
+                pos: spawn_pos.floor().as_ivec3(),
+                yaw: 0.0,
+            })
+            .await?;
+
+        let center_x = spawn_pos.x.floor() as i32 >> 4;
+        let center_z = spawn_pos.z.floor() as i32 >> 4;
+        connection
+            .send(&s2c::GamePacket::SetChunkCacheCenter {
+                x: center_x,
+                z: center_z,
+            })
+            .await?;
+        let chunk_radius = self.config.chunk_radius;
+        for x in center_x - chunk_radius..=center_x + chunk_radius {
+            for z in center_z - chunk_radius..=center_z + chunk_radius {
+                let chunk_data = match self
+                    .plugins
+                    .iter()
+                    .find_map(|plugin| plugin.chunk(self, x, z))
+                {
+                    Some(chunk_data) => chunk_data,
+                    None => empty_chunk(section_count)?,
+                };
+                connection
+                    .send(&s2c::GamePacket::LevelChunkWithLight {
+                        x,
+                        z,
+                        chunk_data,
+                        light_data: LightUpdatePacketData {
+                            trust_edges: true,
+                            sky_y_mask: vec![],
+                            block_y_mask: vec![],
+                            empty_sky_y_mask: vec![],
+                            empty_block_y_mask: vec![],
+                            sky_updates: vec![],
+                            block_updates: vec![],
+                        },
+                    })
+                    .await?;
+            }
+        }
+
+        connection
+            .send(&s2c::GamePacket::PlayerPosition {
+                pos: spawn_pos,
+                yaw: 0.0,
+                pitch: 0.0,
+                relative_arguments: 0,
+                id: 0,
+            })
+            .await
+    }
+
+    /// Adds the player, returns false if a player with the same id is
+    /// already connected.
+    fn join(&self, player: &Arc<Player>) -> bool {
+        {
+            let mut players = self.players.write().unwrap();
+            if players.contains_key(&player.user.id) {
+                return false;
+            }
+
+            // introduce all players to the joining player, including itself
+            player.send(s2c::GamePacket::PlayerInfoUpdate(PlayerInfoUpdatePacket {
+                entries: players
+                    .values()
+                    .chain(std::iter::once(player))
+                    .map(|player| self.player_info(player))
+                    .collect(),
+            }));
+            for other_player in players.values() {
+                player.send(add_player(other_player));
+            }
+
+            // introduce the joining player to all other players
+            let player_info = PlayerInfoUpdatePacket {
+                entries: vec![self.player_info(player)],
+            };
+            for other_player in players.values() {
+                other_player.send(s2c::GamePacket::PlayerInfoUpdate(player_info.clone()));
+                other_player.send(add_player(player));
+            }
+
+            players.insert(player.user.id, player.clone());
+        }
+
+        for plugin in &self.plugins {
+            plugin.join(self, player);
+        }
+        true
+    }
+
+    fn leave(&self, player: &Arc<Player>) {
+        {
+            let mut players = self.players.write().unwrap();
+            // only removes the entry if it's still the one of this connection
+            match players.get(&player.user.id) {
+                Some(entry) if Arc::ptr_eq(entry, player) => {
+                    players.remove(&player.user.id);
+                }
+                _ => return,
+            }
+        }
+
+        self.broadcast(&s2c::GamePacket::PlayerInfoRemove {
+            profile_ids: vec![player.user.id],
+        });
+        self.broadcast(&s2c::GamePacket::RemoveEntities {
+            entity_ids: vec![VarI32(player.entity_id)],
+        });
+
+        for plugin in &self.plugins {
+            plugin.leave(self, player);
+        }
+    }
+
+    async fn play<S: AsyncRead + AsyncWrite + Unpin>(
+        &self,
+        connection: &mut Connection<S>,
+        player: &Arc<Player>,
+        receiver: &mut mpsc::UnboundedReceiver<s2c::GamePacket<'static>>,
+    ) -> Result<()> {
+        let mut keep_alive = KeepAliveSender::new(
+            self.config.keep_alive_interval,
+            self.config.keep_alive_timeout,
+        );
+        loop {
+            tokio::select! {
+                packet = connection.receive() => {
+                    let packet = packet?;
+                    let packet = packet.decode::<c2s::GamePacket>()?;
+                    match &packet {
+                        c2s::GamePacket::KeepAlive { id } => {
+                            if !keep_alive.receive(*id) {
+                                return Err(Error::Unexpected);
+                            }
+                            player
+                                .latency
+                                .store(keep_alive.latency_millis(), Ordering::Relaxed);
+                            self.broadcast(&s2c::GamePacket::PlayerInfoUpdate(
+                                PlayerInfoUpdatePacket {
+                                    entries: vec![PlayerInfoUpdatePacketEntry {
+                                        profile_id: player.user.id,
+                                        profile: None,
+                                        chat_session: None,
+                                        game_mode: None,
+                                        listed: None,
+                                        latency: Some(VarI32(player.latency())),
+                                        display_name: None,
+                                    }],
+                                },
+                            ));
+                        }
+                        c2s::GamePacket::MovePlayerPos { x, y, z, .. }
+                        | c2s::GamePacket::MovePlayerPosRot { x, y, z, .. } => {
+                            *player.pos.lock().unwrap() = DVec3::new(*x, *y, *z);
+                        }
+                        _ => {}
+                    }
+                    for plugin in &self.plugins {
+                        plugin.packet(self, player, &packet);
+                    }
+                }
+                packet = receiver.recv() => {
+                    let Some(packet) = packet else {
+                        return Ok(());
+                    };
+                    connection.send(&packet).await?;
+                    if matches!(packet, s2c::GamePacket::Disconnect { .. }) {
+                        return Ok(());
+                    }
+                }
+                id = keep_alive.tick() => {
+                    match id {
+                        Ok(id) => connection.send(&s2c::GamePacket::KeepAlive { id }).await?,
+                        Err(error) => {
+                            connection
+                                .send(&s2c::GamePacket::Disconnect {
+                                    reason: Json(Component::Literal("Timed out".to_string())),
+                                })
+                                .await?;
+                            return Err(error);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    fn player_info(&self, player: &Player) -> PlayerInfoUpdatePacketEntry {
+        PlayerInfoUpdatePacketEntry {
+            profile_id: player.user.id,
+            profile: Some(player.user.clone()),
+            chat_session: None,
+            game_mode: Some(self.config.game_type),
+            listed: Some(true),
+            latency: Some(VarI32(player.latency())),
+            display_name: Some(None),
+        }
+    }
+}
+
+fn add_player(player: &Player) -> s2c::GamePacket<'static> {
+    s2c::GamePacket::AddPlayer {
+        entity_id: player.entity_id,
+        player_id: player.user.id,
+        pos: player.pos(),
+        yaw: 0.0,
+        pitch: 0.0,
+    }
+}
+
+/// Creates a chunk only consisting of air sections in the first biome.
+pub fn empty_chunk(section_count: u32) -> Result<LevelChunkPacketData<'static>> {
+    let mut buffer = Vec::new();
+    for _ in 0..section_count {
+        // non-empty block count
+        0i16.encode(&mut buffer)?;
+        PalettedContainer::<4096, 4, 8, 15>::Single(0).encode(&mut buffer)?;
+        PalettedContainer::<64, 1, 3, 6>::Single(0).encode(&mut buffer)?;
+    }
+    Ok(LevelChunkPacketData {
+        heightmaps: Nbt(Value::Map(BTreeMap::new())),
+        buffer: buffer.into(),
+        block_entities_data: vec![],
+    })
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use std::{borrow::Cow, sync::Arc, time::Duration};
+
+    use glam::DVec3;
+
+    use crate::{
+        connection::Connection,
+        forwarding::Forwarding,
+        packet::{c2s, s2c},
+        server::{Server, ServerConfig},
+        types::{GameType, Intention, Registries, Registry, Status},
+        PROTOCOL_VERSION,
+    };
+
+    pub(crate) fn server() -> Arc<Server> {
+        fn registry<T: Clone>() -> Cow<'static, Registry<T>> {
+            Cow::Owned(Registry {
+                type_: String::new(),
+                value: vec![],
+            })
+        }
+
+        Arc::new(Server::new(ServerConfig {
+            status: Status::default(),
+            max_players: 20,
+            compression_threshold: Some(256),
+            keep_alive_interval: Duration::from_secs(15),
+            keep_alive_timeout: Duration::from_secs(30),
+            forwarding: Forwarding::None,
+            registries: Registries {
+                dimension_type: registry(),
+                biome: registry(),
+                chat_type: registry(),
+                damage_type: registry(),
+            },
+            dimension_type: "minecraft:overworld".to_string(),
+            dimension: "minecraft:overworld".to_string(),
+            game_type: GameType::Creative,
+            spawn_pos: DVec3::new(0.0, 64.0, 0.0),
+            chunk_radius: 1,
+        }))
+    }
+
+    async fn connect(
+        server: &Arc<Server>,
+        intention: Intention,
+    ) -> Connection<tokio::io::DuplexStream> {
+        let (client, server_stream) = tokio::io::duplex(1 << 16);
+        let server = server.clone();
+        tokio::spawn(async move { server.handle(server_stream).await });
+        let mut connection = Connection::new(client);
+        connection
+            .send(&c2s::HandshakePacket::Intention {
+                protocol_version: PROTOCOL_VERSION,
+                host_name: "localhost".to_string(),
+                port: 25565,
+                intention,
+            })
+            .await
+            .unwrap();
+        connection
+    }
+
+    #[tokio::test]
+    async fn status() {
+        let server = server();
+        let mut connection = connect(&server, Intention::Status).await;
+        connection
+            .send(&c2s::StatusPacket::StatusRequest)
+            .await
+            .unwrap();
+        let s2c::StatusPacket::StatusResponse { status } =
+            connection.receive().await.unwrap().decode().unwrap()
+        else {
+            panic!()
+        };
+        assert_eq!(status.0.version.unwrap().protocol, PROTOCOL_VERSION);
+        assert_eq!(status.0.players.unwrap().online, 0);
+    }
+
+    #[tokio::test]
+    async fn login_and_broadcast() {
+        let server = server();
+
+        async fn login(server: &Arc<Server>, name: &str) -> Connection<tokio::io::DuplexStream> {
+            let mut connection = connect(server, Intention::Login).await;
+            connection
+                .send(&c2s::LoginPacket::Hello {
+                    name: name.to_string(),
+                    uuid: None,
+                })
+                .await
+                .unwrap();
+            let s2c::LoginPacket::LoginCompression {
+                compression_threshold,
+            } = connection.receive().await.unwrap().decode().unwrap()
+            else {
+                panic!()
+            };
+            connection
+                .codec_mut()
+                .enable_compression(Default::default(), compression_threshold as u16);
+            let s2c::LoginPacket::GameProfile { game_profile } =
+                connection.receive().await.unwrap().decode().unwrap()
+            else {
+                panic!()
+            };
+            assert_eq!(game_profile.name, name);
+            connection
+        }
+
+        async fn next_add_player(connection: &mut Connection<tokio::io::DuplexStream>) -> i32 {
+            loop {
+                if let s2c::GamePacket::AddPlayer { entity_id, .. } =
+                    connection.receive().await.unwrap().decode().unwrap()
+                {
+                    return entity_id;
+                }
+            }
+        }
+
+        let mut first = login(&server, "first").await;
+        let mut second = login(&server, "second").await;
+        let first_entity_id = next_add_player(&mut second).await;
+        let second_entity_id = next_add_player(&mut first).await;
+        assert_ne!(first_entity_id, second_entity_id);
+        assert_eq!(server.players().len(), 2);
+
+        // the same id can't join twice
+        let first_player = server
+            .players()
+            .into_iter()
+            .find(|player| player.user().name == "first")
+            .unwrap();
+        let mut duplicate = connect(&server, Intention::Login).await;
+        duplicate
+            .send(&c2s::LoginPacket::Hello {
+                name: "first".to_string(),
+                uuid: Some(first_player.user().id),
+            })
+            .await
+            .unwrap();
+        let packet = duplicate.receive().await.unwrap();
+        assert!(matches!(
+            packet.decode().unwrap(),
+            s2c::LoginPacket::LoginDisconnect { .. }
+        ));
+        assert!(Arc::ptr_eq(
+            &server.player(&first_player.user().id).unwrap(),
+            &first_player
+        ));
+    }
+}
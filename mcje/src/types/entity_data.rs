@@ -1,18 +1,32 @@
-use std::{collections::HashMap, io::Write};
+use std::io::Write;
 
 use glam::{IVec3, Quat, Vec3};
+use indexmap::IndexMap;
 use uuid::Uuid;
 
 use crate::{
     types::{Direction, ItemStack, Nbt, Pose, VarI32, VarI64},
-    Decode, Encode, Result,
+    Decode, DecodeAs, Encode, EncodeAs, Result,
 };
 
-pub struct EntityData(pub HashMap<u8, EntityDataValue>);
+/// Entity data by index, in the order it has been received.
+pub struct EntityData(pub IndexMap<u8, EntityDataValue>);
 
 impl Encode for EntityData {
     fn encode(&self, output: &mut impl Write) -> Result<()> {
-        for (&index, value) in &self.0 {
+        EncodeAs::<EntityData>::encode_as(&self.0, output)
+    }
+}
+
+impl Decode<'_> for EntityData {
+    fn decode(input: &mut &'_ [u8]) -> Result<Self> {
+        Ok(EntityData(DecodeAs::<EntityData>::decode_as(input)?))
+    }
+}
+
+impl EncodeAs<EntityData> for IndexMap<u8, EntityDataValue> {
+    fn encode_as(&self, output: &mut impl Write) -> Result<()> {
+        for (&index, value) in self {
             index.encode(output)?;
             value.encode(output)?;
         }
@@ -20,9 +34,9 @@ impl Encode for EntityData {
     }
 }
 
-impl Decode<'_> for EntityData {
-    fn decode(input: &mut &'_ [u8]) -> Result<Self> {
-        let mut fields = HashMap::new();
+impl DecodeAs<'_, EntityData> for IndexMap<u8, EntityDataValue> {
+    fn decode_as(input: &mut &'_ [u8]) -> Result<Self> {
+        let mut fields = IndexMap::new();
         loop {
             let index = u8::decode(input)?;
             if index == 0xFF {
@@ -30,11 +44,13 @@ impl Decode<'_> for EntityData {
             }
             fields.insert(index, EntityDataValue::decode(input)?);
         }
-        Ok(EntityData(fields))
+        Ok(fields)
     }
 }
 
 #[derive(Encode, Decode, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "arbitrary", derive(crate::arbitrary::Arbitrary))]
 pub enum EntityDataValue {
     Byte(u8),
     Int(#[using(VarI32)] i32),
@@ -13,18 +13,26 @@ pub use chat::{Component, ComponentContents};
 pub use entity_data::{EntityData, EntityDataValue};
 pub use mojang_session_api::models::{User, UserProperty};
 pub use paletted_container::PalettedContainer;
+pub use status::{
+    Favicon, Status, StatusBuilder, StatusPlayers, StatusPlayersSample, StatusVersion,
+};
 
-use crate::{Decode, Encode, Error, Result};
+use crate::{Decode, DecodeAs, Encode, EncodeAs, Error, Result};
 
 mod bit_storage;
 mod chat;
 mod entity_data;
 mod paletted_container;
+mod status;
 
 impl Encode for bool {
     fn encode(&self, output: &mut impl Write) -> Result<()> {
         if *self { 1u8 } else { 0u8 }.encode(output)
     }
+
+    fn encoded_len(&self) -> Result<usize> {
+        Ok(1)
+    }
 }
 
 impl Decode<'_> for bool {
@@ -42,6 +50,10 @@ impl Encode for u8 {
         output.write_u8(*self)?;
         Ok(())
     }
+
+    fn encoded_len(&self) -> Result<usize> {
+        Ok(1)
+    }
 }
 
 impl Decode<'_> for u8 {
@@ -55,6 +67,10 @@ impl Encode for i8 {
         output.write_i8(*self)?;
         Ok(())
     }
+
+    fn encoded_len(&self) -> Result<usize> {
+        Ok(1)
+    }
 }
 
 impl Decode<'_> for i8 {
@@ -68,6 +84,10 @@ impl Encode for u16 {
         output.write_u16::<BigEndian>(*self)?;
         Ok(())
     }
+
+    fn encoded_len(&self) -> Result<usize> {
+        Ok(2)
+    }
 }
 
 impl Decode<'_> for u16 {
@@ -81,6 +101,10 @@ impl Encode for i16 {
         output.write_i16::<BigEndian>(*self)?;
         Ok(())
     }
+
+    fn encoded_len(&self) -> Result<usize> {
+        Ok(2)
+    }
 }
 
 impl Decode<'_> for i16 {
@@ -91,6 +115,8 @@ impl Decode<'_> for i16 {
 
 #[derive(Copy, Clone, Debug)]
 #[repr(transparent)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(transparent))]
+#[cfg_attr(feature = "arbitrary", derive(crate::arbitrary::Arbitrary))]
 pub struct VarI21(pub i32);
 
 impl VarI21 {
@@ -114,13 +140,17 @@ impl Encode for VarI21 {
             value >>= 7;
         }
     }
+
+    fn encoded_len(&self) -> Result<usize> {
+        Ok(self.len())
+    }
 }
 
 impl Decode<'_> for VarI21 {
     fn decode(input: &mut &'_ [u8]) -> Result<Self> {
         let mut value = 0;
         let mut shift = 0;
-        while shift <= 21 {
+        while shift < 21 {
             let head = input.read_u8()?;
             value |= (head as i32 & 0b01111111) << shift;
             if head & 0b10000000 == 0 {
@@ -137,6 +167,10 @@ impl Encode for i32 {
         output.write_i32::<BigEndian>(*self)?;
         Ok(())
     }
+
+    fn encoded_len(&self) -> Result<usize> {
+        Ok(4)
+    }
 }
 
 impl Decode<'_> for i32 {
@@ -147,6 +181,8 @@ impl Decode<'_> for i32 {
 
 #[derive(Copy, Clone, Debug)]
 #[repr(transparent)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(transparent))]
+#[cfg_attr(feature = "arbitrary", derive(crate::arbitrary::Arbitrary))]
 pub struct VarI32(pub i32);
 
 impl VarI32 {
@@ -170,13 +206,17 @@ impl Encode for VarI32 {
             value >>= 7;
         }
     }
+
+    fn encoded_len(&self) -> Result<usize> {
+        Ok(self.len())
+    }
 }
 
 impl Decode<'_> for VarI32 {
     fn decode(input: &mut &'_ [u8]) -> Result<Self> {
         let mut value = 0;
         let mut shift = 0;
-        while shift <= 35 {
+        while shift < 35 {
             let head = input.read_u8()?;
             value |= (head as i32 & 0b01111111) << shift;
             if head & 0b10000000 == 0 {
@@ -193,6 +233,10 @@ impl Encode for i64 {
         output.write_i64::<BigEndian>(*self)?;
         Ok(())
     }
+
+    fn encoded_len(&self) -> Result<usize> {
+        Ok(8)
+    }
 }
 
 impl Decode<'_> for i64 {
@@ -206,6 +250,10 @@ impl Encode for u64 {
         output.write_u64::<BigEndian>(*self)?;
         Ok(())
     }
+
+    fn encoded_len(&self) -> Result<usize> {
+        Ok(8)
+    }
 }
 
 impl Decode<'_> for u64 {
@@ -216,6 +264,8 @@ impl Decode<'_> for u64 {
 
 #[derive(Clone, Debug)]
 #[repr(transparent)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(transparent))]
+#[cfg_attr(feature = "arbitrary", derive(crate::arbitrary::Arbitrary))]
 pub struct VarI64(pub i64);
 
 impl VarI64 {
@@ -239,13 +289,17 @@ impl Encode for VarI64 {
             value >>= 7;
         }
     }
+
+    fn encoded_len(&self) -> Result<usize> {
+        Ok(self.len())
+    }
 }
 
 impl Decode<'_> for VarI64 {
     fn decode(input: &mut &'_ [u8]) -> Result<Self> {
         let mut value = 0;
         let mut shift = 0;
-        while shift <= 70 {
+        while shift < 70 {
             let head = input.read_u8()?;
             value |= (head as i64 & 0b01111111) << shift;
             if head & 0b10000000 == 0 {
@@ -262,6 +316,10 @@ impl Encode for f32 {
         output.write_f32::<BigEndian>(*self)?;
         Ok(())
     }
+
+    fn encoded_len(&self) -> Result<usize> {
+        Ok(4)
+    }
 }
 
 impl Decode<'_> for f32 {
@@ -275,6 +333,10 @@ impl Encode for f64 {
         output.write_f64::<BigEndian>(*self)?;
         Ok(())
     }
+
+    fn encoded_len(&self) -> Result<usize> {
+        Ok(8)
+    }
 }
 
 impl Decode<'_> for f64 {
@@ -294,6 +356,11 @@ macro_rules! tuple {
                 )*
                 Ok(())
             }
+
+            fn encoded_len(&self) -> Result<usize> {
+                let ($($ty,)*) = self;
+                Ok(0 $(+ $ty.encoded_len()?)*)
+            }
         }
 
         impl<'a, $($ty: Decode<'a>,)*> Decode<'a> for ($($ty,)*) {
@@ -325,6 +392,10 @@ impl<T: Encode, const N: usize> Encode for [T; N] {
         }
         Ok(())
     }
+
+    fn encoded_len(&self) -> Result<usize> {
+        self.iter().map(Encode::encoded_len).sum()
+    }
 }
 
 impl<'a, T: Decode<'a>, const N: usize> Decode<'a> for [T; N] {
@@ -339,22 +410,70 @@ impl Encode for &[u8] {
         output.write_all(self)?;
         Ok(())
     }
+
+    fn encoded_len(&self) -> Result<usize> {
+        Ok(VarI32(self.len() as i32).len() + self.len())
+    }
 }
 
 impl<'a> Decode<'a> for &'a [u8] {
     fn decode(input: &mut &'a [u8]) -> Result<Self> {
-        let length = VarI32::decode(input)?.0 as usize;
-        let (bytes, input_) = input.split_at(length);
-        *input = input_;
-        Ok(bytes)
+        let length = decode_length(input, usize::MAX)?;
+        take(input, length)
+    }
+}
+
+/// Decodes a length prefix, which must neither be negative nor exceed the
+/// maximum.
+pub(crate) fn decode_length(input: &mut &[u8], max_length: usize) -> Result<usize> {
+    usize::try_from(VarI32::decode(input)?.0)
+        .ok()
+        .filter(|&length| length <= max_length)
+        .ok_or(Error::InvalidLength)
+}
+
+/// Splits off the given number of bytes from the input.
+pub(crate) fn take<'a>(input: &mut &'a [u8], length: usize) -> Result<&'a [u8]> {
+    if input.len() < length {
+        return Err(Error::UnexpectedEnd);
+    }
+    let (bytes, input_) = input.split_at(length);
+    *input = input_;
+    Ok(bytes)
+}
+
+/// Returns the capacity to preallocate for the given number of items, as the
+/// length is untrusted and each item takes at least one byte of the input.
+pub(crate) fn capacity<T>(length: usize, input: &[u8]) -> usize {
+    const MAX_PREALLOCATION: usize = 64 * 1024;
+
+    length
+        .min(input.len())
+        .min(MAX_PREALLOCATION / std::mem::size_of::<T>().max(1))
+}
+
+impl Encode for Cow<'_, [u8]> {
+    fn encode(&self, output: &mut impl Write) -> Result<()> {
+        self.as_ref().encode(output)
+    }
+
+    fn encoded_len(&self) -> Result<usize> {
+        self.as_ref().encoded_len()
+    }
+}
+
+impl<'a> Decode<'a> for Cow<'a, [u8]> {
+    fn decode(input: &mut &'a [u8]) -> Result<Self> {
+        Ok(Cow::Borrowed(Decode::decode(input)?))
     }
 }
 
 #[derive(Clone, Debug)]
 #[repr(transparent)]
-pub struct TrailingBytes<const N: usize>(pub Vec<u8>);
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(transparent))]
+pub struct TrailingBytes<'a, const N: usize>(pub Cow<'a, [u8]>);
 
-impl<const N: usize> Encode for TrailingBytes<N> {
+impl<const N: usize> Encode for TrailingBytes<'_, N> {
     fn encode(&self, output: &mut impl Write) -> Result<()> {
         if self.0.len() > N {
             return Err(Error::InvalidLength);
@@ -362,16 +481,21 @@ impl<const N: usize> Encode for TrailingBytes<N> {
         output.write_all(&self.0)?;
         Ok(())
     }
+
+    fn encoded_len(&self) -> Result<usize> {
+        if self.0.len() > N {
+            return Err(Error::InvalidLength);
+        }
+        Ok(self.0.len())
+    }
 }
 
-impl<const N: usize> Decode<'_> for TrailingBytes<N> {
-    fn decode(input: &mut &'_ [u8]) -> Result<Self> {
+impl<'a, const N: usize> Decode<'a> for TrailingBytes<'a, N> {
+    fn decode(input: &mut &'a [u8]) -> Result<Self> {
         if input.len() > N {
             return Err(Error::InvalidLength);
         }
-        let value = input.to_vec();
-        *input = &input[input.len()..];
-        Ok(TrailingBytes(value))
+        Ok(TrailingBytes(Cow::Borrowed(take(input, input.len())?)))
     }
 }
 
@@ -385,6 +509,13 @@ impl<T: Encode> Encode for Option<T> {
             }
         }
     }
+
+    fn encoded_len(&self) -> Result<usize> {
+        match self {
+            None => Ok(1),
+            Some(value) => Ok(1 + value.encoded_len()?),
+        }
+    }
 }
 
 impl<'a, T: Decode<'a>> Decode<'a> for Option<T> {
@@ -404,12 +535,20 @@ impl<T: Encode> Encode for Vec<T> {
         }
         Ok(())
     }
+
+    default fn encoded_len(&self) -> Result<usize> {
+        Ok(VarI32(self.len() as i32).len()
+            + self
+                .iter()
+                .map(Encode::encoded_len)
+                .sum::<Result<usize>>()?)
+    }
 }
 
 impl<'a, T: Decode<'a>> Decode<'a> for Vec<T> {
     default fn decode(input: &mut &'a [u8]) -> Result<Self> {
-        let length = VarI32::decode(input)?.0 as usize;
-        let mut value = Vec::with_capacity(length);
+        let length = decode_length(input, usize::MAX)?;
+        let mut value = Vec::with_capacity(capacity::<T>(length, input));
         for _ in 0..length {
             value.push(Decode::decode(input)?);
         }
@@ -417,8 +556,108 @@ impl<'a, T: Decode<'a>> Decode<'a> for Vec<T> {
     }
 }
 
+macro_rules! impl_using {
+    ($($type:ty => $using:ident),*) => {
+        $(
+            impl EncodeAs<$using> for $type {
+                fn encode_as(&self, output: &mut impl Write) -> Result<()> {
+                    $using(*self).encode(output)
+                }
+
+                fn encoded_len_as(&self) -> Result<usize> {
+                    $using(*self).encoded_len()
+                }
+            }
+
+            impl DecodeAs<'_, $using> for $type {
+                fn decode_as(input: &mut &'_ [u8]) -> Result<Self> {
+                    Ok($using::decode(input)?.0)
+                }
+            }
+        )*
+    };
+}
+
+impl_using!(i32 => VarI21, i32 => VarI32, i64 => VarI64, f32 => Angle);
+
+/// Length prefix of collections with a `#[length(..)]` attribute, which are
+/// prefixed by a VarInt otherwise.
+pub trait LengthPrefix: Sized {
+    fn from_length(length: usize) -> Option<Self>;
+
+    fn to_length(self) -> Option<usize>;
+}
+
+macro_rules! impl_length_prefix {
+    ($($type:ty),*) => {
+        $(
+            impl LengthPrefix for $type {
+                fn from_length(length: usize) -> Option<Self> {
+                    length.try_into().ok()
+                }
+
+                fn to_length(self) -> Option<usize> {
+                    self.try_into().ok()
+                }
+            }
+        )*
+    };
+}
+
+impl_length_prefix!(u8, i8, u16, i16, i32, i64, u64);
+
+impl LengthPrefix for VarI32 {
+    fn from_length(length: usize) -> Option<Self> {
+        i32::from_length(length).map(VarI32)
+    }
+
+    fn to_length(self) -> Option<usize> {
+        self.0.to_length()
+    }
+}
+
+/// Encodes the values prefixed by their length as `L`.
+pub fn encode_with_length<L: LengthPrefix + Encode, T: Encode>(
+    values: &[T],
+    output: &mut impl Write,
+) -> Result<()> {
+    L::from_length(values.len())
+        .ok_or(Error::InvalidLength)?
+        .encode(output)?;
+    for value in values {
+        value.encode(output)?;
+    }
+    Ok(())
+}
+
+/// Returns the number of bytes written by [`encode_with_length`].
+pub fn encoded_len_with_length<L: LengthPrefix + Encode, T: Encode>(values: &[T]) -> Result<usize> {
+    let length = L::from_length(values.len())
+        .ok_or(Error::InvalidLength)?
+        .encoded_len()?;
+    Ok(length
+        + values
+            .iter()
+            .map(Encode::encoded_len)
+            .sum::<Result<usize>>()?)
+}
+
+/// Decodes values prefixed by their length as `L`.
+pub fn decode_with_length<'a, L: LengthPrefix + Decode<'a>, T: Decode<'a>>(
+    input: &mut &'a [u8],
+) -> Result<Vec<T>> {
+    let length = L::decode(input)?.to_length().ok_or(Error::InvalidLength)?;
+    let mut values = Vec::with_capacity(capacity::<T>(length, input));
+    for _ in 0..length {
+        values.push(Decode::decode(input)?);
+    }
+    Ok(values)
+}
+
 trait VecEncode<T> {
     fn encode_special(value: &[T], output: &mut impl Write) -> Result<()>;
+
+    fn encoded_len_special(value: &[T]) -> Result<usize>;
 }
 
 trait VecDecode<T> {
@@ -431,11 +670,15 @@ impl<T: Encode + VecEncode<T>> Encode for Vec<T> {
         T::encode_special(self, output)?;
         Ok(())
     }
+
+    fn encoded_len(&self) -> Result<usize> {
+        Ok(VarI32(self.len() as i32).len() + T::encoded_len_special(self)?)
+    }
 }
 
 impl<'a, T: Decode<'a> + VecDecode<T>> Decode<'a> for Vec<T> {
     default fn decode(input: &mut &'a [u8]) -> Result<Self> {
-        let length = VarI32::decode(input)?.0 as usize;
+        let length = decode_length(input, usize::MAX)?;
         T::decode_special(input, length)
     }
 }
@@ -445,39 +688,115 @@ impl VecEncode<u8> for u8 {
         output.write_all(value)?;
         Ok(())
     }
+
+    fn encoded_len_special(value: &[u8]) -> Result<usize> {
+        Ok(value.len())
+    }
 }
 
 impl VecDecode<u8> for u8 {
     fn decode_special(input: &mut &[u8], length: usize) -> Result<Vec<u8>> {
-        let (value, input_) = input.split_at(length);
-        *input = input_;
-        Ok(value.to_vec())
+        Ok(take(input, length)?.to_vec())
     }
 }
 
+/// Maximum length of strings in UTF-16 code units.
+pub const MAX_STRING_LENGTH: usize = 32767;
+
+/// Maximum length of JSON strings in UTF-16 code units.
+pub const MAX_JSON_LENGTH: usize = 262144;
+
 impl Encode for String {
     fn encode(&self, output: &mut impl Write) -> Result<()> {
-        let bytes = self.as_bytes();
-        VarI32(bytes.len() as i32).encode(output)?;
-        output.write_all(bytes)?;
-        Ok(())
+        encode_string(self, MAX_STRING_LENGTH, output)
+    }
+
+    fn encoded_len(&self) -> Result<usize> {
+        encoded_string_len(self, MAX_STRING_LENGTH)
     }
 }
 
 impl Decode<'_> for String {
     fn decode(input: &mut &'_ [u8]) -> Result<Self> {
-        let length = VarI32::decode(input)?.0 as usize;
-        let (bytes, input_) = input.split_at(length);
-        *input = input_;
-        Ok(from_utf8(bytes)?.to_string())
+        Ok(decode_string(input, MAX_STRING_LENGTH)?.to_string())
     }
 }
 
+impl Encode for &str {
+    fn encode(&self, output: &mut impl Write) -> Result<()> {
+        encode_string(self, MAX_STRING_LENGTH, output)
+    }
+
+    fn encoded_len(&self) -> Result<usize> {
+        encoded_string_len(self, MAX_STRING_LENGTH)
+    }
+}
+
+impl<'a> Decode<'a> for &'a str {
+    fn decode(input: &mut &'a [u8]) -> Result<Self> {
+        decode_string(input, MAX_STRING_LENGTH)
+    }
+}
+
+impl Encode for Cow<'_, str> {
+    fn encode(&self, output: &mut impl Write) -> Result<()> {
+        encode_string(self, MAX_STRING_LENGTH, output)
+    }
+
+    fn encoded_len(&self) -> Result<usize> {
+        encoded_string_len(self, MAX_STRING_LENGTH)
+    }
+}
+
+impl<'a> Decode<'a> for Cow<'a, str> {
+    fn decode(input: &mut &'a [u8]) -> Result<Self> {
+        Ok(Cow::Borrowed(decode_string(input, MAX_STRING_LENGTH)?))
+    }
+}
+
+/// Encodes a string, which must not exceed the maximum length in UTF-16 code
+/// units.
+pub(crate) fn encode_string(value: &str, max_length: usize, output: &mut impl Write) -> Result<()> {
+    check_string_length(value, max_length)?;
+    value.as_bytes().encode(output)
+}
+
+/// Returns the number of bytes written by [`encode_string`].
+pub(crate) fn encoded_string_len(value: &str, max_length: usize) -> Result<usize> {
+    check_string_length(value, max_length)?;
+    value.as_bytes().encoded_len()
+}
+
+fn check_string_length(value: &str, max_length: usize) -> Result<()> {
+    // each code unit takes at least one byte, counting is only required for
+    // strings which are longer in bytes
+    if value.len() > max_length && value.encode_utf16().count() > max_length {
+        return Err(Error::InvalidLength);
+    }
+    Ok(())
+}
+
+/// Decodes a string, which must not exceed the maximum length in UTF-16 code
+/// units.
+pub(crate) fn decode_string<'a>(input: &mut &'a [u8], max_length: usize) -> Result<&'a str> {
+    // each code unit takes at most three bytes
+    let length = decode_length(input, max_length * 3)?;
+    let value = from_utf8(take(input, length)?)?;
+    if value.len() > max_length && value.encode_utf16().count() > max_length {
+        return Err(Error::InvalidLength);
+    }
+    Ok(value)
+}
+
 impl Encode for Uuid {
     fn encode(&self, output: &mut impl Write) -> Result<()> {
         output.write_u128::<BigEndian>(self.as_u128())?;
         Ok(())
     }
+
+    fn encoded_len(&self) -> Result<usize> {
+        Ok(16)
+    }
 }
 
 impl Decode<'_> for Uuid {
@@ -516,6 +835,10 @@ impl Encode for Vec3 {
         self.y.encode(output)?;
         self.z.encode(output)
     }
+
+    fn encoded_len(&self) -> Result<usize> {
+        Ok(12)
+    }
 }
 
 impl Decode<'_> for Vec3 {
@@ -534,6 +857,10 @@ impl Encode for DVec3 {
         self.y.encode(output)?;
         self.z.encode(output)
     }
+
+    fn encoded_len(&self) -> Result<usize> {
+        Ok(24)
+    }
 }
 
 impl Decode<'_> for DVec3 {
@@ -553,6 +880,10 @@ impl Encode for Quat {
         self.z.encode(output)?;
         self.w.encode(output)
     }
+
+    fn encoded_len(&self) -> Result<usize> {
+        Ok(16)
+    }
 }
 
 impl Decode<'_> for Quat {
@@ -568,6 +899,8 @@ impl Decode<'_> for Quat {
 
 #[derive(Clone, Debug)]
 #[repr(transparent)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(transparent))]
+#[cfg_attr(feature = "arbitrary", derive(crate::arbitrary::Arbitrary))]
 pub struct Angle(pub f32);
 
 impl From<f32> for Angle {
@@ -590,17 +923,25 @@ impl From<Angle> for f32 {
 
 impl Encode for Angle {
     fn encode(&self, output: &mut impl Write) -> Result<()> {
-        ((self.0.rem_euclid(360.0) / 360.0 * u8::MAX as f32).round() as u8).encode(output)
+        // a full turn has 256 steps, like in the vanilla client, 360° wraps
+        // around to 0
+        ((self.0 / 360.0 * 256.0).round().rem_euclid(256.0) as u8).encode(output)
+    }
+
+    fn encoded_len(&self) -> Result<usize> {
+        Ok(1)
     }
 }
 
 impl Decode<'_> for Angle {
     fn decode(input: &mut &'_ [u8]) -> Result<Self> {
-        Ok(Self(u8::decode(input)? as f32 / u8::MAX as f32 * 360.0))
+        Ok(Self(u8::decode(input)? as f32 / 256.0 * 360.0))
     }
 }
 
 #[derive(Encode, Decode, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "arbitrary", derive(crate::arbitrary::Arbitrary))]
 pub struct Advancement {
     pub parent_id: Option<String>,
     pub display: Option<AdvancementDisplayInfo>,
@@ -609,6 +950,8 @@ pub struct Advancement {
 }
 
 #[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "arbitrary", derive(crate::arbitrary::Arbitrary))]
 pub struct AdvancementDisplayInfo {
     pub title: Json<Component>,
     pub description: Json<Component>,
@@ -676,6 +1019,8 @@ impl Decode<'_> for AdvancementDisplayInfo {
 }
 
 #[derive(Encode, Decode, Copy, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "arbitrary", derive(crate::arbitrary::Arbitrary))]
 pub enum AdvancementFrameType {
     Task,
     Challenge,
@@ -683,12 +1028,15 @@ pub enum AdvancementFrameType {
 }
 
 #[derive(Encode, Decode, Copy, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "arbitrary", derive(crate::arbitrary::Arbitrary))]
 pub enum Anchor {
     Feet,
     Eyes,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
+#[cfg_attr(feature = "arbitrary", derive(crate::arbitrary::Arbitrary))]
 pub struct Biome {
     pub has_precipitation: bool,
     pub temperature: f32,
@@ -698,6 +1046,7 @@ pub struct Biome {
 }
 
 #[derive(Serialize, Deserialize, Copy, Clone, Debug)]
+#[cfg_attr(feature = "arbitrary", derive(crate::arbitrary::Arbitrary))]
 pub enum BiomeTemperatureModifier {
     #[serde(rename = "none")]
     None,
@@ -706,6 +1055,7 @@ pub enum BiomeTemperatureModifier {
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
+#[cfg_attr(feature = "arbitrary", derive(crate::arbitrary::Arbitrary))]
 pub struct BiomeEffects {
     pub fog_color: u32,
     pub water_color: u32,
@@ -721,6 +1071,7 @@ pub struct BiomeEffects {
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
+#[cfg_attr(feature = "arbitrary", derive(crate::arbitrary::Arbitrary))]
 pub struct BiomeEffectsMusic {
     pub sound: String,
     pub min_delay: u32,
@@ -729,12 +1080,14 @@ pub struct BiomeEffectsMusic {
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
+#[cfg_attr(feature = "arbitrary", derive(crate::arbitrary::Arbitrary))]
 pub struct BiomeEffectsAdditionsSound {
     pub sound: String,
     pub tick_chance: f64,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
+#[cfg_attr(feature = "arbitrary", derive(crate::arbitrary::Arbitrary))]
 pub struct BiomeEffectsMoodSound {
     pub sound: String,
     pub tick_delay: u32,
@@ -743,6 +1096,8 @@ pub struct BiomeEffectsMoodSound {
 }
 
 #[derive(Encode, Decode, Copy, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "arbitrary", derive(crate::arbitrary::Arbitrary))]
 pub enum BossEventColor {
     Pink,
     Blue,
@@ -754,6 +1109,8 @@ pub enum BossEventColor {
 }
 
 #[derive(Encode, Decode, Copy, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "arbitrary", derive(crate::arbitrary::Arbitrary))]
 pub enum BossEventOverlay {
     Progress,
     Notched6,
@@ -763,6 +1120,8 @@ pub enum BossEventOverlay {
 }
 
 #[derive(Encode, Decode, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "arbitrary", derive(crate::arbitrary::Arbitrary))]
 pub struct ChatSession {
     pub session_id: Uuid,
     pub expires_at: i64,
@@ -771,12 +1130,15 @@ pub struct ChatSession {
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
+#[cfg_attr(feature = "arbitrary", derive(crate::arbitrary::Arbitrary))]
 pub struct ChatType {
     pub chat: ChatTypeDecoration,
     pub narration: ChatTypeDecoration,
 }
 
 #[derive(Encode, Decode, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "arbitrary", derive(crate::arbitrary::Arbitrary))]
 pub struct ChatTypeBound {
     #[using(VarI32)]
     pub chat_type: i32,
@@ -785,12 +1147,15 @@ pub struct ChatTypeBound {
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
+#[cfg_attr(feature = "arbitrary", derive(crate::arbitrary::Arbitrary))]
 pub struct ChatTypeDecoration {
     pub translation_key: String,
     pub parameters: Vec<String>,
 }
 
 #[derive(Encode, Decode, Copy, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "arbitrary", derive(crate::arbitrary::Arbitrary))]
 pub enum ChatVisibility {
     Full,
     System,
@@ -798,6 +1163,8 @@ pub enum ChatVisibility {
 }
 
 #[derive(Encode, Decode, Copy, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "arbitrary", derive(crate::arbitrary::Arbitrary))]
 pub enum ClickType {
     Pickup,
     QuickMove,
@@ -810,6 +1177,8 @@ pub enum ClickType {
 
 #[derive(Encode, Decode, Copy, Clone, Debug)]
 #[using(u8)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "arbitrary", derive(crate::arbitrary::Arbitrary))]
 pub enum Difficulty {
     Peaceful,
     Easy,
@@ -818,6 +1187,7 @@ pub enum Difficulty {
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
+#[cfg_attr(feature = "arbitrary", derive(crate::arbitrary::Arbitrary))]
 pub struct DimensionType {
     pub fixed_time: Option<u64>,
     pub has_skylight: bool,
@@ -841,12 +1211,14 @@ pub struct DimensionType {
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
 #[serde(untagged)]
+#[cfg_attr(feature = "arbitrary", derive(crate::arbitrary::Arbitrary))]
 pub enum MonsterSpawnLightLevel {
     Scalar(i32),
     Custom(MonsterSpawnLightLevelCustom),
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
+#[cfg_attr(feature = "arbitrary", derive(crate::arbitrary::Arbitrary))]
 pub struct MonsterSpawnLightLevelCustom {
     #[serde(rename = "type")]
     type_: String,
@@ -854,6 +1226,7 @@ pub struct MonsterSpawnLightLevelCustom {
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
+#[cfg_attr(feature = "arbitrary", derive(crate::arbitrary::Arbitrary))]
 pub struct MonsterSpawnLightLevelCustomValue {
     min_inclusive: i32,
     max_inclusive: i32,
@@ -861,6 +1234,8 @@ pub struct MonsterSpawnLightLevelCustomValue {
 
 #[derive(Encode, Decode, Copy, Clone, Debug)]
 #[using(u8)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "arbitrary", derive(crate::arbitrary::Arbitrary))]
 pub enum Direction {
     Down,
     Up,
@@ -885,6 +1260,8 @@ impl Direction {
 
 #[derive(Copy, Clone, Debug, Eq, PartialEq, Hash, IntoPrimitive, TryFromPrimitive)]
 #[repr(u8)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "arbitrary", derive(crate::arbitrary::Arbitrary))]
 pub enum EquipmentSlot {
     MainHand,
     OffHand,
@@ -895,6 +1272,8 @@ pub enum EquipmentSlot {
 }
 
 #[derive(Encode, Decode, Copy, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "arbitrary", derive(crate::arbitrary::Arbitrary))]
 pub enum GameType {
     Survival,
     Creative,
@@ -903,12 +1282,16 @@ pub enum GameType {
 }
 
 #[derive(Encode, Decode, Copy, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "arbitrary", derive(crate::arbitrary::Arbitrary))]
 pub enum Hand {
     MainHand,
     OffHand,
 }
 
 #[derive(Encode, Decode, Copy, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "arbitrary", derive(crate::arbitrary::Arbitrary))]
 pub enum Intention {
     Game,
     Status,
@@ -916,6 +1299,8 @@ pub enum Intention {
 }
 
 #[derive(Encode, Decode, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "arbitrary", derive(crate::arbitrary::Arbitrary))]
 pub struct ItemStack {
     #[using(VarI32)]
     pub item: i32,
@@ -925,22 +1310,27 @@ pub struct ItemStack {
 
 #[derive(Clone, Debug)]
 #[repr(transparent)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(transparent))]
 pub struct Json<T>(pub T);
 
 impl<T: Serialize> Encode for Json<T> {
     fn encode(&self, output: &mut impl Write) -> Result<()> {
-        serde_json::to_string(&self.0)?.encode(output)
+        encode_string(&serde_json::to_string(&self.0)?, MAX_JSON_LENGTH, output)
     }
 }
 
 impl<'a, T: DeserializeOwned> Decode<'a> for Json<T> {
     fn decode(input: &mut &'a [u8]) -> Result<Self> {
-        let value = String::decode(input)?;
-        Ok(Json(serde_json::from_str(value.as_str())?))
+        Ok(Json(serde_json::from_str(decode_string(
+            input,
+            MAX_JSON_LENGTH,
+        )?)?))
     }
 }
 
 #[derive(Encode, Decode, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "arbitrary", derive(crate::arbitrary::Arbitrary))]
 pub struct LastSeenMessages {
     #[using(VarI32)]
     pub offset: i32,
@@ -948,12 +1338,16 @@ pub struct LastSeenMessages {
 }
 
 #[derive(Encode, Decode, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "arbitrary", derive(crate::arbitrary::Arbitrary))]
 pub enum MainHand {
     Left,
     Right,
 }
 
 #[derive(Encode, Decode, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "arbitrary", derive(crate::arbitrary::Arbitrary))]
 pub struct MapDecoration {
     pub type_: MapDecorationType,
     pub x: i8,
@@ -963,6 +1357,8 @@ pub struct MapDecoration {
 }
 
 #[derive(Encode, Decode, Copy, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "arbitrary", derive(crate::arbitrary::Arbitrary))]
 pub enum MapDecorationType {
     Player,
     Frame,
@@ -993,21 +1389,26 @@ pub enum MapDecorationType {
     RedX,
 }
 
+/// Changed area of a map.
 #[derive(Clone, Derivative)]
 #[derivative(Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "arbitrary", derive(crate::arbitrary::Arbitrary))]
 pub struct MapPatch {
     pub width: u8,
     pub height: u8,
     pub start_x: u8,
     pub start_y: u8,
-    #[derivative(Debug = "ignore")]
+    #[derivative(Debug(format_with = "debug_bytes"))]
     pub map_colors: Vec<u8>,
 }
 
+// the width is used instead of a presence flag, and is 0 if nothing changed
 impl Encode for Option<MapPatch> {
     fn encode(&self, output: &mut impl Write) -> Result<()> {
         match self {
             None => 0u8.encode(output),
+            Some(value) if value.width == 0 => Err(Error::InvalidLength),
             Some(value) => {
                 value.width.encode(output)?;
                 value.height.encode(output)?;
@@ -1037,6 +1438,8 @@ impl Decode<'_> for Option<MapPatch> {
 }
 
 #[derive(Encode, Decode, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "arbitrary", derive(crate::arbitrary::Arbitrary))]
 pub struct MerchantOffer {
     pub base_cost_a: Option<ItemStack>,
     pub result: Option<ItemStack>,
@@ -1052,6 +1455,7 @@ pub struct MerchantOffer {
 
 #[derive(Clone, Debug)]
 #[repr(transparent)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(transparent))]
 pub struct Nbt<T>(pub T);
 
 impl<T: Serialize> Encode for Nbt<T> {
@@ -1068,6 +1472,8 @@ impl<T: DeserializeOwned> Decode<'_> for Nbt<T> {
 }
 
 #[derive(Encode, Decode, Copy, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "arbitrary", derive(crate::arbitrary::Arbitrary))]
 pub enum Pose {
     Standing,
     FallFlying,
@@ -1086,6 +1492,8 @@ pub enum Pose {
 }
 
 #[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "arbitrary", derive(crate::arbitrary::Arbitrary))]
 pub enum Recipe {
     Shaped {
         id: String,
@@ -1177,6 +1585,10 @@ impl Encode for Recipe {
                 result,
                 show_notification,
             } => {
+                // the ingredients are decoded by the size of the grid
+                if width.checked_mul(*height) != Some(ingredients.len() as i32) {
+                    return Err(Error::InvalidLength);
+                }
                 id.encode(output)?;
                 VarI32(*width).encode(output)?;
                 VarI32(*height).encode(output)?;
@@ -1253,8 +1665,13 @@ impl Decode<'_> for Recipe {
                 let height = VarI32::decode(input)?.0;
                 let group = Decode::decode(input)?;
                 let category = VarI32::decode(input)?.0;
-                let ingredient_count = width * height;
-                let mut ingredients = Vec::with_capacity(ingredient_count as usize);
+                let ingredient_count = usize::try_from(width)
+                    .ok()
+                    .zip(usize::try_from(height).ok())
+                    .and_then(|(width, height)| width.checked_mul(height))
+                    .ok_or(Error::InvalidLength)?;
+                let mut ingredients =
+                    Vec::with_capacity(capacity::<Vec<Option<ItemStack>>>(ingredient_count, input));
                 for _ in 0..ingredient_count {
                     ingredients.push(Decode::decode(input)?);
                 }
@@ -1329,6 +1746,8 @@ impl Decode<'_> for Recipe {
 }
 
 #[derive(Encode, Decode, Copy, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "arbitrary", derive(crate::arbitrary::Arbitrary))]
 pub enum RecipeBookType {
     Crafting,
     Furnace,
@@ -1337,6 +1756,7 @@ pub enum RecipeBookType {
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
+#[cfg_attr(feature = "arbitrary", derive(crate::arbitrary::Arbitrary))]
 pub struct Registries<'a> {
     #[serde(rename = "minecraft:dimension_type")]
     pub dimension_type: Cow<'a, Registry<DimensionType>>,
@@ -1349,6 +1769,7 @@ pub struct Registries<'a> {
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
+#[cfg_attr(feature = "arbitrary", derive(crate::arbitrary::Arbitrary))]
 pub struct DamageType {
     pub message_id: String,
     pub exhaustion: f32,
@@ -1358,6 +1779,7 @@ pub struct DamageType {
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
+#[cfg_attr(feature = "arbitrary", derive(crate::arbitrary::Arbitrary))]
 pub struct Registry<T> {
     #[serde(rename = "type")]
     pub type_: String,
@@ -1365,6 +1787,7 @@ pub struct Registry<T> {
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
+#[cfg_attr(feature = "arbitrary", derive(crate::arbitrary::Arbitrary))]
 pub struct RegistryEntry<T> {
     pub name: String,
     pub id: u32,
@@ -1372,6 +1795,8 @@ pub struct RegistryEntry<T> {
 }
 
 #[derive(Encode, Decode, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "arbitrary", derive(crate::arbitrary::Arbitrary))]
 pub struct SimpleCooking {
     pub id: String,
     pub group: String,
@@ -1385,6 +1810,8 @@ pub struct SimpleCooking {
 }
 
 #[derive(Encode, Decode, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "arbitrary", derive(crate::arbitrary::Arbitrary))]
 pub struct SimpleRecipe {
     pub id: String,
     #[using(VarI32)]
@@ -1392,6 +1819,7 @@ pub struct SimpleRecipe {
 }
 
 #[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum Sound {
     Id(i32),
     Name(String),
@@ -1400,7 +1828,13 @@ pub enum Sound {
 impl Encode for Sound {
     fn encode(&self, output: &mut impl Write) -> Result<()> {
         match self {
-            Sound::Id(id) => VarI32(id + 1).encode(output),
+            // 0 is reserved for named sounds
+            Sound::Id(id) => VarI32(
+                id.checked_add(1)
+                    .filter(|id| *id > 0)
+                    .ok_or(Error::UnknownVariant(*id))?,
+            )
+            .encode(output),
             Sound::Name(name) => {
                 VarI32(0).encode(output)?;
                 name.encode(output)
@@ -1420,7 +1854,21 @@ impl Decode<'_> for Sound {
     }
 }
 
+/// Ids are sent incremented by one, so the largest id can't be sent.
+#[cfg(feature = "arbitrary")]
+impl crate::arbitrary::Arbitrary for Sound {
+    fn arbitrary(input: &mut crate::arbitrary::Unstructured) -> crate::arbitrary::Result<Self> {
+        Ok(if input.arbitrary()? {
+            Sound::Id(input.int_in_range(0..=i32::MAX - 1)?)
+        } else {
+            Sound::Name(input.arbitrary()?)
+        })
+    }
+}
+
 #[derive(Encode, Decode, Copy, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "arbitrary", derive(crate::arbitrary::Arbitrary))]
 pub enum SoundSource {
     Master,
     Music,
@@ -1434,38 +1882,6 @@ pub enum SoundSource {
     Voice,
 }
 
-#[derive(Serialize, Deserialize, Clone, Debug)]
-pub struct Status {
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub description: Option<Component>,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub players: Option<StatusPlayers>,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub version: Option<StatusVersion>,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub favicon: Option<String>,
-}
-
-#[derive(Serialize, Deserialize, Clone, Debug)]
-pub struct StatusVersion {
-    pub name: String,
-    pub protocol: i32,
-}
-
-#[derive(Serialize, Deserialize, Clone, Debug)]
-pub struct StatusPlayers {
-    pub max: i32,
-    pub online: i32,
-    #[serde(skip_serializing_if = "Vec::is_empty", default)]
-    pub sample: Vec<StatusPlayersSample>,
-}
-
-#[derive(Serialize, Deserialize, Clone, Debug)]
-pub struct StatusPlayersSample {
-    pub id: String,
-    pub name: String,
-}
-
 impl Encode for User {
     fn encode(&self, output: &mut impl Write) -> Result<()> {
         self.id.encode(output)?;
@@ -1501,3 +1917,241 @@ impl Decode<'_> for UserProperty {
         })
     }
 }
+
+/// Formats only the length of byte buffers, which are too large and not
+/// meaningful to print.
+pub(crate) fn debug_bytes(value: &[u8], formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+    write!(formatter, "<{} bytes>", value.len())
+}
+
+pub(crate) fn debug_byte_lists(
+    value: &[Cow<[u8]>],
+    formatter: &mut std::fmt::Formatter,
+) -> std::fmt::Result {
+    struct Bytes<'a>(&'a [u8]);
+
+    impl std::fmt::Debug for Bytes<'_> {
+        fn fmt(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+            debug_bytes(self.0, formatter)
+        }
+    }
+
+    formatter
+        .debug_list()
+        .entries(value.iter().map(|value| Bytes(value)))
+        .finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::borrow::Cow;
+
+    use crate::{
+        packet::PacketName,
+        types::{Angle, MapPatch, Recipe, Sound, TrailingBytes, VarI32, MAX_STRING_LENGTH},
+        Decode, Encode, Error,
+    };
+
+    #[test]
+    fn angle() {
+        fn encode(degrees: f32) -> u8 {
+            let mut data = vec![];
+            Angle(degrees).encode(&mut data).unwrap();
+            data[0]
+        }
+
+        assert_eq!(encode(0.0), 0);
+        assert_eq!(encode(90.0), 64);
+        assert_eq!(encode(180.0), 128);
+        assert_eq!(encode(358.0), 255);
+        assert_eq!(encode(360.0), 0);
+        assert_eq!(encode(-90.0), 192);
+        assert_eq!(encode(810.0), 64);
+        assert_eq!(encode(f32::NAN), 0);
+
+        for (step, degrees) in [(0, 0.0), (64, 90.0), (128, 180.0), (255, 358.59375)] {
+            assert_eq!(Angle::decode(&mut [step].as_slice()).unwrap().0, degrees);
+        }
+    }
+
+    #[test]
+    fn map_patch() {
+        let mut data = vec![];
+        None::<MapPatch>.encode(&mut data).unwrap();
+        assert_eq!(data, [0]);
+        assert!(matches!(
+            Option::<MapPatch>::decode(&mut data.as_slice()),
+            Ok(None)
+        ));
+
+        let patch = MapPatch {
+            width: 1,
+            height: 2,
+            start_x: 3,
+            start_y: 4,
+            map_colors: vec![5, 6],
+        };
+        let mut data = vec![];
+        Some(patch.clone()).encode(&mut data).unwrap();
+        assert_eq!(data, [1, 2, 3, 4, 2, 5, 6]);
+        let mut input = data.as_slice();
+        let decoded = Option::<MapPatch>::decode(&mut input).unwrap().unwrap();
+        assert!(input.is_empty());
+        assert_eq!(
+            (decoded.width, decoded.height, decoded.map_colors),
+            (1, 2, vec![5, 6])
+        );
+
+        // a width of 0 would be decoded as no patch
+        assert!(matches!(
+            Some(MapPatch { width: 0, ..patch }).encode(&mut vec![]),
+            Err(Error::InvalidLength)
+        ));
+    }
+
+    #[test]
+    fn reject_undecodable_values() {
+        // ids are sent incremented by one, as 0 is reserved for named sounds
+        let mut data = vec![];
+        Sound::Id(4).encode(&mut data).unwrap();
+        assert_eq!(data, [5]);
+        for id in [-1, i32::MAX] {
+            assert!(matches!(
+                Sound::Id(id).encode(&mut vec![]),
+                Err(Error::UnknownVariant(_))
+            ));
+        }
+
+        let shaped = |width, height, ingredients| Recipe::Shaped {
+            id: "minecraft:stick".to_string(),
+            width,
+            height,
+            group: String::new(),
+            category: 0,
+            ingredients: vec![vec![]; ingredients],
+            result: None,
+            show_notification: false,
+        };
+        let mut data = vec![];
+        shaped(1, 2, 2).encode(&mut data).unwrap();
+        assert!(matches!(
+            Recipe::decode(&mut data.as_slice()),
+            Ok(Recipe::Shaped { ingredients, .. }) if ingredients.len() == 2
+        ));
+        assert!(matches!(
+            shaped(1, 2, 3).encode(&mut vec![]),
+            Err(Error::InvalidLength)
+        ));
+        assert!(matches!(
+            shaped(i32::MAX, 2, 0).encode(&mut vec![]),
+            Err(Error::InvalidLength)
+        ));
+    }
+
+    #[test]
+    fn decode_borrowed() {
+        let mut data = vec![];
+        "Hello".encode(&mut data).unwrap();
+        [1u8, 2, 3].as_slice().encode(&mut data).unwrap();
+        data.extend_from_slice(&[4, 5]);
+
+        let mut input = data.as_slice();
+        let Cow::Borrowed(string) = Cow::<str>::decode(&mut input).unwrap() else {
+            panic!("string is not borrowed");
+        };
+        assert_eq!(string, "Hello");
+        let Cow::Borrowed(bytes) = Cow::<[u8]>::decode(&mut input).unwrap() else {
+            panic!("bytes are not borrowed");
+        };
+        assert_eq!(bytes, [1, 2, 3]);
+        let TrailingBytes::<16>(Cow::Borrowed(bytes)) = TrailingBytes::decode(&mut input).unwrap()
+        else {
+            panic!("trailing bytes are not borrowed");
+        };
+        assert_eq!(bytes, [4, 5]);
+        assert!(input.is_empty());
+    }
+
+    #[test]
+    fn decode_invalid_lengths() {
+        // negative length
+        let mut data = vec![];
+        VarI32(-1).encode(&mut data).unwrap();
+        assert!(matches!(
+            Vec::<u8>::decode(&mut data.as_slice()),
+            Err(Error::InvalidLength)
+        ));
+
+        // length exceeding the input, without preallocating it
+        let mut data = vec![];
+        VarI32(i32::MAX).encode(&mut data).unwrap();
+        assert!(matches!(
+            <&[u8]>::decode(&mut data.as_slice()),
+            Err(Error::UnexpectedEnd)
+        ));
+        assert!(Vec::<u64>::decode(&mut data.as_slice()).is_err());
+
+        // string exceeding the maximum length
+        let value = "a".repeat(MAX_STRING_LENGTH + 1);
+        assert!(matches!(
+            value.encode(&mut vec![]),
+            Err(Error::InvalidLength)
+        ));
+        let mut data = vec![];
+        value.as_bytes().encode(&mut data).unwrap();
+        assert!(matches!(
+            String::decode(&mut data.as_slice()),
+            Err(Error::InvalidLength)
+        ));
+
+        // multi-byte characters count as one code unit
+        let value = "ä".repeat(MAX_STRING_LENGTH);
+        let mut data = vec![];
+        value.encode(&mut data).unwrap();
+        assert_eq!(String::decode(&mut data.as_slice()).unwrap(), value);
+
+        // overlong VarI32
+        assert!(matches!(
+            VarI32::decode(&mut [0xFF; 6].as_slice()),
+            Err(Error::InvalidLength)
+        ));
+    }
+
+    #[test]
+    fn derive_attributes() {
+        #[derive(Encode, Decode, Debug, PartialEq)]
+        struct Values(bool, #[length(u8)] Vec<u16>);
+
+        #[derive(Encode, Decode, PacketName, Debug, PartialEq)]
+        enum Packet {
+            First,
+            #[id = 0x2A]
+            Values(Values),
+            Tuple(#[using(VarI32)] i32, bool),
+        }
+
+        fn round_trip(packet: Packet, expected: &[u8]) {
+            let mut data = vec![];
+            packet.encode(&mut data).unwrap();
+            assert_eq!(data, expected);
+            assert_eq!(packet.encoded_len().unwrap(), data.len());
+            assert_eq!(Packet::decode(&mut data.as_slice()).unwrap(), packet);
+        }
+
+        round_trip(Packet::First, &[0x00]);
+        round_trip(
+            Packet::Values(Values(true, vec![1, 2])),
+            &[0x2A, 0x01, 0x02, 0x00, 0x01, 0x00, 0x02],
+        );
+        round_trip(Packet::Tuple(300, true), &[0x2B, 0xAC, 0x02, 0x01]);
+
+        assert!(matches!(
+            Values(false, vec![0; 256]).encode(&mut vec![]),
+            Err(Error::InvalidLength)
+        ));
+
+        assert_eq!(Packet::Tuple(0, false).name(), "Tuple");
+        assert_eq!(Packet::NAMES.len(), 0x2C);
+        assert_eq!(Packet::NAMES[1], "");
+    }
+}
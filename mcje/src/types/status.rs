@@ -0,0 +1,237 @@
+use base64::{engine::general_purpose::STANDARD, Engine};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::{types::Component, Error, Result, PROTOCOL_VERSION, VERSION};
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+#[cfg_attr(feature = "arbitrary", derive(crate::arbitrary::Arbitrary))]
+pub struct Status {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<Component>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub players: Option<StatusPlayers>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub version: Option<StatusVersion>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub favicon: Option<String>,
+}
+
+impl Status {
+    pub fn builder() -> StatusBuilder {
+        StatusBuilder::default()
+    }
+
+    /// Decodes the favicon, if any.
+    pub fn favicon(&self) -> Option<Result<Favicon>> {
+        self.favicon.as_deref().map(Favicon::from_data_url)
+    }
+
+    pub fn set_favicon(&mut self, favicon: Option<&Favicon>) {
+        self.favicon = favicon.map(Favicon::to_data_url);
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[cfg_attr(feature = "arbitrary", derive(crate::arbitrary::Arbitrary))]
+pub struct StatusVersion {
+    pub name: String,
+    pub protocol: i32,
+}
+
+/// Defaults to the version implemented by this crate.
+impl Default for StatusVersion {
+    fn default() -> Self {
+        Self {
+            name: VERSION.to_string(),
+            protocol: PROTOCOL_VERSION,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[cfg_attr(feature = "arbitrary", derive(crate::arbitrary::Arbitrary))]
+pub struct StatusPlayers {
+    pub max: i32,
+    pub online: i32,
+    #[serde(skip_serializing_if = "Vec::is_empty", default)]
+    pub sample: Vec<StatusPlayersSample>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[cfg_attr(feature = "arbitrary", derive(crate::arbitrary::Arbitrary))]
+pub struct StatusPlayersSample {
+    pub id: String,
+    pub name: String,
+}
+
+/// Builds a status with the version implemented by this crate.
+pub struct StatusBuilder {
+    status: Status,
+}
+
+impl Default for StatusBuilder {
+    fn default() -> Self {
+        Self {
+            status: Status {
+                version: Some(StatusVersion::default()),
+                ..Default::default()
+            },
+        }
+    }
+}
+
+impl StatusBuilder {
+    pub fn description(mut self, description: Component) -> Self {
+        self.status.description = Some(description);
+        self
+    }
+
+    pub fn players(mut self, online: i32, max: i32) -> Self {
+        let players = self.status.players.get_or_insert(StatusPlayers {
+            max,
+            online,
+            sample: vec![],
+        });
+        players.online = online;
+        players.max = max;
+        self
+    }
+
+    /// Adds a player to the sample shown when hovering over the player count,
+    /// the name can be any text as it's not checked by the client.
+    pub fn sample(mut self, id: Uuid, name: impl Into<String>) -> Self {
+        self.status
+            .players
+            .get_or_insert(StatusPlayers {
+                max: 0,
+                online: 0,
+                sample: vec![],
+            })
+            .sample
+            .push(StatusPlayersSample {
+                id: id.hyphenated().to_string(),
+                name: name.into(),
+            });
+        self
+    }
+
+    /// Overrides the version, e.g. to show a custom text instead of the player
+    /// count, by using a protocol version which is not supported by the client.
+    pub fn version(mut self, name: impl Into<String>, protocol: i32) -> Self {
+        self.status.version = Some(StatusVersion {
+            name: name.into(),
+            protocol,
+        });
+        self
+    }
+
+    pub fn favicon(mut self, favicon: &Favicon) -> Self {
+        self.status.set_favicon(Some(favicon));
+        self
+    }
+
+    pub fn build(self) -> Status {
+        self.status
+    }
+}
+
+/// PNG image shown next to the server in the server list, which has to be
+/// 64x64 pixels.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Favicon(Vec<u8>);
+
+impl Favicon {
+    pub const SIZE: u32 = 64;
+
+    const DATA_URL_PREFIX: &'static str = "data:image/png;base64,";
+
+    /// Validates that the data is a 64x64 PNG image.
+    pub fn from_png(png: Vec<u8>) -> Result<Self> {
+        const SIGNATURE: [u8; 8] = [0x89, b'P', b'N', b'G', 0x0D, 0x0A, 0x1A, 0x0A];
+
+        // the IHDR chunk has to come first, and starts with the width and height
+        if png.len() < 24 || png[..8] != SIGNATURE || &png[12..16] != b"IHDR" {
+            return Err(Error::InvalidImage);
+        }
+        let width = u32::from_be_bytes(png[16..20].try_into().unwrap());
+        let height = u32::from_be_bytes(png[20..24].try_into().unwrap());
+        if width != Self::SIZE || height != Self::SIZE {
+            return Err(Error::InvalidImage);
+        }
+        Ok(Self(png))
+    }
+
+    /// Decodes the favicon from the format used in the status, a base64 data
+    /// URL, the prefix is optional.
+    pub fn from_data_url(data_url: &str) -> Result<Self> {
+        let base64 = data_url
+            .strip_prefix(Self::DATA_URL_PREFIX)
+            .unwrap_or(data_url);
+        // some servers wrap the base64 encoded data
+        let base64 = base64.replace(['\n', '\r'], "");
+        Self::from_png(STANDARD.decode(base64)?)
+    }
+
+    pub fn to_data_url(&self) -> String {
+        format!("{}{}", Self::DATA_URL_PREFIX, STANDARD.encode(&self.0))
+    }
+
+    pub fn png(&self) -> &[u8] {
+        &self.0
+    }
+
+    pub fn into_png(self) -> Vec<u8> {
+        self.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use image::{ImageFormat, RgbaImage};
+
+    use crate::{
+        types::{Favicon, Status},
+        Error,
+    };
+
+    fn png(width: u32, height: u32) -> Vec<u8> {
+        let mut png = vec![];
+        RgbaImage::new(width, height)
+            .write_to(&mut Cursor::new(&mut png), ImageFormat::Png)
+            .unwrap();
+        png
+    }
+
+    #[test]
+    fn favicon() {
+        let favicon = Favicon::from_png(png(64, 64)).unwrap();
+        let status = Status::builder().favicon(&favicon).build();
+        let data_url = status.favicon.as_deref().unwrap();
+        assert!(data_url.starts_with("data:image/png;base64,"));
+        assert_eq!(status.favicon().unwrap().unwrap(), favicon);
+
+        // wrapped base64 without the prefix
+        let base64 = data_url.strip_prefix("data:image/png;base64,").unwrap();
+        let (start, end) = base64.split_at(16);
+        assert_eq!(
+            Favicon::from_data_url(&format!("{start}\r\n{end}")).unwrap(),
+            favicon
+        );
+
+        assert!(matches!(
+            Favicon::from_png(png(32, 64)),
+            Err(Error::InvalidImage)
+        ));
+        assert!(matches!(
+            Favicon::from_png(b"GIF89a\x40\x00\x40\x00".to_vec()),
+            Err(Error::InvalidImage)
+        ));
+        assert!(matches!(
+            Favicon::from_png(vec![]),
+            Err(Error::InvalidImage)
+        ));
+    }
+}
@@ -1,193 +1,364 @@
-use proc_macro2::{Ident, Span, TokenStream};
-use quote::{quote, quote_spanned, ToTokens};
+use proc_macro2::{Ident, TokenStream};
+use quote::{format_ident, quote, quote_spanned, ToTokens};
 use syn::{
-    parse_macro_input, parse_quote, spanned::Spanned, Data, DeriveInput, Field, Fields,
-    GenericParam, Lifetime, LifetimeParam,
+    parse_macro_input, parse_quote, spanned::Spanned, Data, DataEnum, DeriveInput, Error, Expr,
+    ExprLit, Field, Fields, GenericParam, Index, Lifetime, LifetimeParam, Lit, Result, Type,
 };
 
-#[proc_macro_derive(Encode, attributes(using))]
-pub fn derive_encode(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
-    fn field_encode(field: &Field, field_ref: TokenStream, references: bool) -> TokenStream {
-        if let Some(using) = field
-            .attrs
-            .iter()
-            .find(|attr| attr.path().is_ident("using"))
-            .map(|attr| attr.parse_args::<Ident>().unwrap())
+/// Field attributes, which change how the field is encoded.
+///
+/// - `#[using(T)]` encodes the field as `T`, which requires the field type to
+///   implement `EncodeAs<T>` and `DecodeAs<T>`
+/// - `#[length(T)]` prefixes the collection with its length as `T` instead of a
+///   VarInt
+#[derive(Default)]
+struct FieldAttrs {
+    using: Option<Type>,
+    length: Option<Type>,
+}
+
+impl FieldAttrs {
+    fn new(field: &Field) -> Result<Self> {
+        fn set<T>(value: &mut Option<T>, attr: &syn::Attribute) -> Result<()>
+        where
+            T: syn::parse::Parse,
         {
-            if references {
+            if value.is_some() {
+                return Err(Error::new_spanned(attr, "duplicate attribute"));
+            }
+            *value = Some(attr.parse_args()?);
+            Ok(())
+        }
+
+        let mut attrs = Self::default();
+        for attr in &field.attrs {
+            if attr.path().is_ident("using") {
+                set(&mut attrs.using, attr)?;
+            } else if attr.path().is_ident("length") {
+                set(&mut attrs.length, attr)?;
+            } else if attr.path().is_ident("id") {
+                return Err(Error::new_spanned(
+                    attr,
+                    "`id` can only be used on variants",
+                ));
+            }
+        }
+        if let (Some(_), Some(length)) = (&attrs.using, &attrs.length) {
+            return Err(Error::new_spanned(
+                length,
+                "`using` and `length` can't be combined",
+            ));
+        }
+        Ok(attrs)
+    }
+}
+
+/// Returns the ids of the variants, which are counted up from the previous id,
+/// or set explicitly using `#[id = 0x2A]`.
+fn variant_ids(data: &DataEnum) -> Result<Vec<i32>> {
+    let mut variant_ids = Vec::<i32>::new();
+    for variant in &data.variants {
+        let mut variant_id = match variant_ids.last() {
+            Some(previous_variant_id) => previous_variant_id
+                .checked_add(1)
+                .ok_or_else(|| Error::new_spanned(variant, "id is out of range"))?,
+            None => 0,
+        };
+        for attr in &variant.attrs {
+            if !attr.path().is_ident("id") {
+                continue;
+            }
+            let Expr::Lit(ExprLit {
+                lit: Lit::Int(id), ..
+            }) = &attr.meta.require_name_value()?.value
+            else {
+                return Err(Error::new_spanned(
+                    attr,
+                    "expected `#[id = ..]` with an integer",
+                ));
+            };
+            variant_id = id.base10_parse()?;
+        }
+        if let Some(i) = variant_ids.iter().position(|id| *id == variant_id) {
+            return Err(Error::new_spanned(
+                variant,
+                format!(
+                    "id {variant_id} is already used by `{}`",
+                    data.variants[i].ident
+                ),
+            ));
+        }
+        variant_ids.push(variant_id);
+    }
+    Ok(variant_ids)
+}
+
+fn field_names(fields: &Fields) -> Vec<Ident> {
+    fields
+        .iter()
+        .enumerate()
+        .map(|(i, field)| field.ident.clone().unwrap_or_else(|| format_ident!("_{i}")))
+        .collect()
+}
+
+#[proc_macro_derive(Encode, attributes(using, length, id))]
+pub fn derive_encode(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    encode(input)
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
+
+fn encode(input: DeriveInput) -> Result<TokenStream> {
+    // generates either the encode, or the encoded length, which is summed up in
+    // `encoded_len`
+    fn value_encode(
+        attrs: &FieldAttrs,
+        value: TokenStream,
+        references: bool,
+        len: bool,
+    ) -> TokenStream {
+        if let Some(using) = &attrs.using {
+            let value = if references { value } else { quote!(&#value) };
+            if len {
                 quote_spanned! {
-                    field.span() => unsafe { std::mem::transmute::<_, &#using>(#field_ref).encode(output) }
+                    using.span() => <_ as crate::EncodeAs<#using>>::encoded_len_as(#value)
                 }
             } else {
                 quote_spanned! {
-                    field.span() => #using(#field_ref).encode(output)
+                    using.span() => <_ as crate::EncodeAs<#using>>::encode_as(#value, output)
+                }
+            }
+        } else if let Some(length) = &attrs.length {
+            if len {
+                quote! {
+                    crate::types::encoded_len_with_length::<#length, _>(&#value)
+                }
+            } else {
+                quote! {
+                    crate::types::encode_with_length::<#length, _>(&#value, output)
                 }
             }
+        } else if len {
+            quote! {
+                #value.encoded_len()
+            }
         } else {
-            quote_spanned! {
-                field.span() => #field_ref.encode(output)
+            quote! {
+                #value.encode(output)
             }
         }
     }
 
-    let input = parse_macro_input!(input as DeriveInput);
-
-    let name = input.ident;
-    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    fn field_encode(
+        field: &Field,
+        field_ref: TokenStream,
+        references: bool,
+        len: bool,
+    ) -> Result<TokenStream> {
+        let attrs = FieldAttrs::new(field)?;
+        let accumulate = len.then(|| quote!(encoded_len +=));
+        let value_encode = value_encode(&attrs, field_ref, references, len);
+        Ok(quote_spanned! {
+            field.span() => #accumulate #value_encode?;
+        })
+    }
 
-    let body = match &input.data {
-        Data::Struct(data) => match &data.fields {
-            Fields::Named(fields) => {
-                if !fields.named.is_empty() {
-                    let mut field_encodes = fields.named.iter().map(|field| {
-                        field_encode(
-                            field,
-                            {
-                                let field_name = field.ident.as_ref().unwrap();
-                                quote! {
-                                    self.#field_name
-                                }
-                            },
-                            false,
-                        )
-                    });
-                    let first_field_encode = field_encodes.next().unwrap();
-                    quote! {
-                        #first_field_encode #(?;#field_encodes)*
-                    }
-                } else {
-                    quote! {
-                        Ok(())
-                    }
+    fn body(name: &Ident, data: &Data, len: bool) -> Result<TokenStream> {
+        let (init, result) = if len {
+            (quote!(let mut encoded_len = 0;), quote!(Ok(encoded_len)))
+        } else {
+            (quote!(), quote!(Ok(())))
+        };
+        Ok(match data {
+            Data::Struct(data) => {
+                if let Fields::Unit = &data.fields {
+                    return Err(Error::new_spanned(
+                        name,
+                        "unit structs are not supported, as they have no data",
+                    ));
                 }
-            }
-            Fields::Unit => quote! {
-                Ok(())
-            },
-            _ => todo!(),
-        },
-        Data::Enum(data) => {
-            let index_only = data
-                .variants
-                .iter()
-                .all(|variant| matches!(variant.fields, Fields::Unit));
-            let match_arms = data.variants.iter().enumerate().map(|(i, variant)| {
-                let variant_index = i as i32;
-                let variant_name = &variant.ident;
-                match &variant.fields {
-                    Fields::Named(fields) => {
-                        if !fields.named.is_empty() {
-                            let field_names = fields
-                                .named
-                                .iter()
-                                .map(|field| field.ident.as_ref().unwrap());
-                            let mut field_encodes = fields.named.iter().map(|field| field_encode(field, field.ident.as_ref().unwrap().into_token_stream(), true));
-                            let first_field_encode = field_encodes.next().unwrap();
+                let field_encodes = data
+                    .fields
+                    .iter()
+                    .enumerate()
+                    .map(|(i, field)| {
+                        let field_ref = if let Some(field_name) = &field.ident {
                             quote! {
-                                Self::#variant_name { #(#field_names,)* } => {
-                                    crate::types::VarI32(#variant_index).encode(output)?;
-                                    #first_field_encode #(?;#field_encodes)*
-                                }
+                                self.#field_name
                             }
                         } else {
+                            let field_index = Index::from(i);
                             quote! {
-                                Self::#variant_name {} => crate::types::VarI32(#variant_index).encode(output),
+                                self.#field_index
                             }
-                        }
-                    }
-                    Fields::Unnamed(fields) => {
-                        if !fields.unnamed.is_empty() {
-                            let field_names = (0..fields.unnamed.len()).map(|i| Ident::new(&format!("_{i}"), Span::call_site()));
-                            let mut field_encodes = fields.unnamed.iter().enumerate().map(|(i, field)| field_encode(field, Ident::new(&format!("_{i}"), Span::call_site()).into_token_stream(), true));
-                            let first_field_encode = field_encodes.next().unwrap();
-                            quote! {
-                                Self::#variant_name(#(#field_names,)*) => {
-                                    crate::types::VarI32(#variant_index).encode(output)?;
-                                    #first_field_encode #(?;#field_encodes)*
-                                }
+                        };
+                        field_encode(field, field_ref, false, len)
+                    })
+                    .collect::<Result<Vec<_>>>()?;
+                quote! {
+                    #init
+                    #(#field_encodes)*
+                    #result
+                }
+            }
+            Data::Enum(data) => {
+                let index_only = data
+                    .variants
+                    .iter()
+                    .all(|variant| matches!(variant.fields, Fields::Unit));
+                let match_arms = data
+                    .variants
+                    .iter()
+                    .zip(variant_ids(data)?)
+                    .map(|(variant, variant_id)| {
+                        let variant_name = &variant.ident;
+                        let field_names = field_names(&variant.fields);
+                        let field_encodes = variant
+                            .fields
+                            .iter()
+                            .zip(&field_names)
+                            .map(|(field, field_name)| {
+                                field_encode(field, field_name.to_token_stream(), true, len)
+                            })
+                            .collect::<Result<Vec<_>>>()?;
+                        let fields = match &variant.fields {
+                            Fields::Named(_) => quote! {
+                                { #(#field_names,)* }
+                            },
+                            Fields::Unnamed(_) => quote! {
+                                (#(#field_names,)*)
+                            },
+                            Fields::Unit => {
+                                return Ok(if index_only {
+                                    quote! {
+                                        Self::#variant_name => #variant_id,
+                                    }
+                                } else if len {
+                                    quote! {
+                                        Self::#variant_name => Ok(crate::types::VarI32(#variant_id).len()),
+                                    }
+                                } else {
+                                    quote! {
+                                        Self::#variant_name => crate::types::VarI32(#variant_id).encode(output),
+                                    }
+                                });
                             }
+                        };
+                        let id_encode = if len {
+                            quote!(let mut encoded_len = crate::types::VarI32(#variant_id).len();)
                         } else {
-                            quote! {
-                                Self::#variant_name() => crate::types::VarI32(#variant_index).encode(output),
+                            quote!(crate::types::VarI32(#variant_id).encode(output)?;)
+                        };
+                        Ok(quote! {
+                            Self::#variant_name #fields => {
+                                #id_encode
+                                #(#field_encodes)*
+                                #result
                             }
-                        }
+                        })
+                    })
+                    .collect::<Result<Vec<_>>>()?;
+                if match_arms.is_empty() {
+                    quote! {
+                        unreachable!()
                     }
-                    Fields::Unit => {
-                        if index_only {
-                            quote! {
-                                Self::#variant_name => #variant_index,
-                            }
-                        } else {
-                            quote! {
-                                Self::#variant_name => crate::types::VarI32(#variant_index).encode(output),
-                            }
-                        }
+                } else if index_only {
+                    let encode = if len {
+                        quote!(Ok(id.len()))
+                    } else {
+                        quote!(id.encode(output))
+                    };
+                    quote! {
+                        let id = crate::types::VarI32(match self {
+                            #(#match_arms)*
+                        });
+                        #encode
                     }
-                }
-            });
-            if match_arms.len() == 0 {
-                quote! {
-                    unreachable!()
-                }
-            } else if index_only {
-                quote! {
-                    crate::types::VarI32(match self {
-                        #(#match_arms)*
-                    }).encode(output)
-                }
-            } else {
-                quote! {
-                    match self {
-                        #(#match_arms)*
+                } else {
+                    quote! {
+                        match self {
+                            #(#match_arms)*
+                        }
                     }
                 }
             }
-        }
-        _ => todo!(),
-    };
+            Data::Union(data) => {
+                return Err(Error::new_spanned(
+                    data.union_token,
+                    "unions are not supported",
+                ))
+            }
+        })
+    }
+
+    let name = input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    let encode = body(&name, &input.data, false)?;
+    let encoded_len = body(&name, &input.data, true)?;
 
-    proc_macro::TokenStream::from(quote! {
+    Ok(quote! {
         impl #impl_generics Encode for #name #ty_generics
         #where_clause
         {
             fn encode(&self, output: &mut impl std::io::Write) -> crate::Result<()> {
-                #body
+                #encode
+            }
+
+            fn encoded_len(&self) -> crate::Result<usize> {
+                #encoded_len
             }
         }
     })
 }
 
-#[proc_macro_derive(Decode, attributes(using))]
+#[proc_macro_derive(Decode, attributes(using, length, id))]
 pub fn derive_decode(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
-    fn field_decode(field: &Field) -> TokenStream {
-        let field_name = field.ident.as_ref();
-        if let Some(using) = field
-            .attrs
-            .iter()
-            .find(|attr| attr.path().is_ident("using"))
-            .map(|attr| attr.parse_args::<Ident>().unwrap())
-        {
-            if let Some(field_name) = field_name {
-                quote_spanned! {
-                    field.span() => #field_name: #using::decode(input)?.0
-                }
-            } else {
-                quote_spanned! {
-                    field.span() => #using::decode(input)?.0
-                }
-            }
-        } else if let Some(field_name) = field_name {
+    let input = parse_macro_input!(input as DeriveInput);
+    decode(input)
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
+
+fn decode(mut input: DeriveInput) -> Result<TokenStream> {
+    fn value_decode(attrs: &FieldAttrs) -> TokenStream {
+        if let Some(using) = &attrs.using {
             quote_spanned! {
-                field.span() => #field_name: Decode::decode(input)?
+                using.span() => <_ as crate::DecodeAs<#using>>::decode_as(input)?
+            }
+        } else if let Some(length) = &attrs.length {
+            quote! {
+                crate::types::decode_with_length::<#length, _>(input)?
             }
         } else {
-            quote_spanned! {
-                field.span() => Decode::decode(input)?
+            quote! {
+                Decode::decode(input)?
             }
         }
     }
 
-    let mut input = parse_macro_input!(input as DeriveInput);
+    fn fields_decode(fields: &Fields, path: TokenStream) -> Result<TokenStream> {
+        let field_decodes = fields
+            .iter()
+            .map(|field| {
+                let field_name = field.ident.as_ref().map(|field_name| quote!(#field_name:));
+                let decode = value_decode(&FieldAttrs::new(field)?);
+                Ok(quote_spanned! {
+                    field.span() => #field_name #decode
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+        Ok(match fields {
+            Fields::Named(_) => quote! {
+                #path { #(#field_decodes,)* }
+            },
+            Fields::Unnamed(_) => quote! {
+                #path(#(#field_decodes,)*)
+            },
+            Fields::Unit => path,
+        })
+    }
 
     let name = input.ident;
     let (_, ty_generics, _) = input.generics.split_for_impl();
@@ -205,44 +376,29 @@ pub fn derive_decode(input: proc_macro::TokenStream) -> proc_macro::TokenStream
     let (impl_generics, _, where_clause) = input.generics.split_for_impl();
 
     let body = match &input.data {
-        Data::Struct(data) => match &data.fields {
-            Fields::Named(fields) => {
-                let field_decodes = fields.named.iter().map(field_decode);
-                quote! {
-                    Self {
-                        #(#field_decodes,)*
-                    }
-                }
+        Data::Struct(data) => {
+            if let Fields::Unit = &data.fields {
+                return Err(Error::new_spanned(
+                    name,
+                    "unit structs are not supported, as they have no data",
+                ));
             }
-            Fields::Unit => quote! {
-                Self
-            },
-            _ => todo!(),
-        },
+            fields_decode(&data.fields, quote!(Self))?
+        }
         Data::Enum(data) => {
-            let match_arms = data.variants.iter().enumerate().map(|(i, variant)| {
-                let variant_index = i as i32;
-                let variant_name = &variant.ident;
-                match &variant.fields {
-                    Fields::Named(fields) => {
-                        let field_decodes = fields.named.iter().map(field_decode);
-                        quote! {
-                            #variant_index => Self::#variant_name {
-                                #(#field_decodes,)*
-                            }
-                        }
-                    }
-                    Fields::Unnamed(fields) => {
-                        let field_decodes = fields.unnamed.iter().map(field_decode);
-                        quote! {
-                            #variant_index => Self::#variant_name(#(#field_decodes,)*)
-                        }
-                    }
-                    Fields::Unit => quote! {
-                        #variant_index => Self::#variant_name
-                    },
-                }
-            });
+            let match_arms = data
+                .variants
+                .iter()
+                .zip(variant_ids(data)?)
+                .map(|(variant, variant_id)| {
+                    let variant_name = &variant.ident;
+                    let fields_decode =
+                        fields_decode(&variant.fields, quote!(Self::#variant_name))?;
+                    Ok(quote! {
+                        #variant_id => #fields_decode
+                    })
+                })
+                .collect::<Result<Vec<_>>>()?;
             quote! {
                 match crate::types::VarI32::decode(input)?.0 {
                     #(#match_arms,)*
@@ -250,10 +406,15 @@ pub fn derive_decode(input: proc_macro::TokenStream) -> proc_macro::TokenStream
                 }
             }
         }
-        _ => todo!(),
+        Data::Union(data) => {
+            return Err(Error::new_spanned(
+                data.union_token,
+                "unions are not supported",
+            ))
+        }
     };
 
-    proc_macro::TokenStream::from(quote! {
+    Ok(quote! {
         impl #impl_generics Decode<#lifetime> for #name #ty_generics
         #where_clause
         {
@@ -263,3 +424,158 @@ pub fn derive_decode(input: proc_macro::TokenStream) -> proc_macro::TokenStream
         }
     })
 }
+
+/// Largest id of a packet, names are stored in a table indexed by id.
+const MAX_PACKET_ID: i32 = 0xFF;
+
+#[proc_macro_derive(PacketName, attributes(id))]
+pub fn derive_packet_name(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    packet_name(input)
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
+
+fn packet_name(input: DeriveInput) -> Result<TokenStream> {
+    let name = input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    let Data::Enum(data) = &input.data else {
+        return Err(Error::new_spanned(
+            name,
+            "`PacketName` can only be derived for enums",
+        ));
+    };
+    let variant_ids = variant_ids(data)?;
+    for (variant, variant_id) in data.variants.iter().zip(&variant_ids) {
+        if *variant_id > MAX_PACKET_ID {
+            return Err(Error::new_spanned(
+                variant,
+                format!("id {variant_id} is out of range, packet ids go up to {MAX_PACKET_ID}"),
+            ));
+        }
+    }
+    // unassigned ids have an empty name
+    let mut names = vec![String::new(); variant_ids.iter().max().map_or(0, |id| *id as usize + 1)];
+    for (variant, variant_id) in data.variants.iter().zip(&variant_ids) {
+        names[*variant_id as usize] = variant.ident.to_string();
+    }
+    let match_arms = data
+        .variants
+        .iter()
+        .zip(&variant_ids)
+        .map(|(variant, variant_id)| {
+            let variant_name = &variant.ident;
+            quote! {
+                Self::#variant_name { .. } => #variant_id
+            }
+        });
+
+    Ok(quote! {
+        impl #impl_generics PacketName for #name #ty_generics
+        #where_clause
+        {
+            const NAMES: &'static [&'static str] = &[#(#names,)*];
+
+            fn id(&self) -> i32 {
+                match self {
+                    #(#match_arms,)*
+                }
+            }
+        }
+    })
+}
+
+#[proc_macro_derive(Arbitrary, attributes(using, length, id))]
+pub fn derive_arbitrary(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    arbitrary(input)
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
+
+fn arbitrary(mut input: DeriveInput) -> Result<TokenStream> {
+    fn field_arbitrary(field: &Field) -> Result<TokenStream> {
+        let field_name = field.ident.as_ref().map(|field_name| quote!(#field_name:));
+        let attrs = FieldAttrs::new(field)?;
+        if let Some(using) = &attrs.using {
+            Ok(quote_spanned! {
+                field.span() => #field_name <#using as crate::arbitrary::Arbitrary>::arbitrary(input)?.0
+            })
+        } else {
+            Ok(quote_spanned! {
+                field.span() => #field_name crate::arbitrary::Arbitrary::arbitrary(input)?
+            })
+        }
+    }
+
+    fn fields_arbitrary(fields: &Fields) -> Result<TokenStream> {
+        let field_arbitraries = fields
+            .iter()
+            .map(field_arbitrary)
+            .collect::<Result<Vec<_>>>()?;
+        Ok(match fields {
+            Fields::Named(_) => quote! {
+                {
+                    #(#field_arbitraries,)*
+                }
+            },
+            Fields::Unnamed(_) => quote! {
+                (#(#field_arbitraries,)*)
+            },
+            Fields::Unit => quote! {},
+        })
+    }
+
+    let name = input.ident;
+    for param in input.generics.type_params_mut() {
+        param.bounds.push(parse_quote!(crate::arbitrary::Arbitrary));
+    }
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    let body = match &input.data {
+        Data::Struct(data) => {
+            let fields = fields_arbitrary(&data.fields)?;
+            quote! {
+                Self #fields
+            }
+        }
+        Data::Enum(data) => {
+            let variant_count = data.variants.len();
+            let match_arms = data
+                .variants
+                .iter()
+                .enumerate()
+                .map(|(i, variant)| {
+                    let variant_name = &variant.ident;
+                    let fields = fields_arbitrary(&variant.fields)?;
+                    Ok(quote! {
+                        #i => Self::#variant_name #fields
+                    })
+                })
+                .collect::<Result<Vec<_>>>()?;
+            quote! {
+                match input.choose_index(#variant_count)? {
+                    #(#match_arms,)*
+                    _ => unreachable!()
+                }
+            }
+        }
+        Data::Union(data) => {
+            return Err(Error::new_spanned(
+                data.union_token,
+                "unions are not supported",
+            ))
+        }
+    };
+
+    Ok(quote! {
+        impl #impl_generics crate::arbitrary::Arbitrary for #name #ty_generics
+        #where_clause
+        {
+            fn arbitrary(input: &mut crate::arbitrary::Unstructured) -> crate::arbitrary::Result<Self> {
+                Ok(#body)
+            }
+        }
+    })
+}
@@ -0,0 +1,8 @@
+// the derives refer to items of iokum_mcje through `crate::`, which are
+// therefore imported at the root of each test case that expands them
+#[test]
+fn ui() {
+    let tests = trybuild::TestCases::new();
+    tests.pass("tests/ui/pass/*.rs");
+    tests.compile_fail("tests/ui/fail/*.rs");
+}
@@ -0,0 +1,10 @@
+use iokum_mcje::Encode;
+
+#[derive(Encode)]
+struct Duplicate {
+    #[using(VarI32)]
+    #[using(VarI64)]
+    id: i32,
+}
+
+fn main() {}
@@ -0,0 +1,11 @@
+use iokum_mcje::Encode;
+
+#[derive(Encode)]
+enum DuplicateId {
+    First,
+    Second,
+    #[id = 1]
+    Third,
+}
+
+fn main() {}
@@ -0,0 +1,9 @@
+use iokum_mcje::Encode;
+
+#[derive(Encode)]
+enum NotInteger {
+    #[id = "first"]
+    First,
+}
+
+fn main() {}
@@ -0,0 +1,9 @@
+use iokum_mcje::Encode;
+
+#[derive(Encode)]
+struct IdOnField {
+    #[id = 1]
+    value: i32,
+}
+
+fn main() {}
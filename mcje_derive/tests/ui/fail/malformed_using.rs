@@ -0,0 +1,9 @@
+use iokum_mcje::Encode;
+
+#[derive(Encode)]
+struct Malformed {
+    #[using = VarI32]
+    id: i32,
+}
+
+fn main() {}
@@ -0,0 +1,10 @@
+use iokum_mcje::packet::PacketName;
+
+#[derive(PacketName)]
+enum Packet {
+    First,
+    #[id = 0x7FFFFFFF]
+    Last,
+}
+
+fn main() {}
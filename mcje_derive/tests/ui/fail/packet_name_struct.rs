@@ -0,0 +1,8 @@
+use iokum_mcje::packet::PacketName;
+
+#[derive(PacketName)]
+struct NotEnum {
+    id: i32,
+}
+
+fn main() {}
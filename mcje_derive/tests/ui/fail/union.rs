@@ -0,0 +1,9 @@
+use iokum_mcje::Encode;
+
+#[derive(Encode)]
+union Union {
+    a: u32,
+    b: f32,
+}
+
+fn main() {}
@@ -0,0 +1,6 @@
+use iokum_mcje::Decode;
+
+#[derive(Decode)]
+struct Unit;
+
+fn main() {}
@@ -0,0 +1,10 @@
+use iokum_mcje::Encode;
+
+#[derive(Encode)]
+struct UsingAndLength {
+    #[using(VarI32)]
+    #[length(u8)]
+    ids: Vec<i32>,
+}
+
+fn main() {}
@@ -0,0 +1,9 @@
+use iokum_mcje::{types::VarI32, Encode, EncodeAs, Result};
+
+#[derive(Encode)]
+struct WithoutConversion {
+    #[using(VarI32)]
+    name: String,
+}
+
+fn main() {}
@@ -0,0 +1,85 @@
+use std::{borrow::Cow, io::Write};
+
+use iokum_mcje::{
+    packet::PacketName,
+    types::{self, VarI32},
+    Decode, DecodeAs, Encode, EncodeAs, Error, Result,
+};
+
+// custom representation used by `#[using(Flags)]`
+struct Flags;
+
+impl EncodeAs<Flags> for [bool; 8] {
+    fn encode_as(&self, output: &mut impl Write) -> Result<()> {
+        let flags = self
+            .iter()
+            .enumerate()
+            .fold(0u8, |flags, (i, flag)| flags | (*flag as u8) << i);
+        flags.encode(output)
+    }
+}
+
+impl DecodeAs<'_, Flags> for [bool; 8] {
+    fn decode_as(input: &mut &'_ [u8]) -> Result<Self> {
+        let flags = u8::decode(input)?;
+        Ok(std::array::from_fn(|i| flags & 1 << i != 0))
+    }
+}
+
+#[derive(Encode, Decode, Debug, PartialEq)]
+struct Named<'a> {
+    #[using(VarI32)]
+    id: i32,
+    name: Cow<'a, str>,
+    #[length(u8)]
+    values: Vec<u16>,
+    #[using(Flags)]
+    flags: [bool; 8],
+}
+
+#[derive(Encode, Decode, Debug, PartialEq)]
+struct Tuple(#[using(VarI32)] i32, String);
+
+// prefixed by the id of the variant as VarInt
+#[derive(Encode, Decode, PacketName, Debug, PartialEq)]
+enum Packet<'a> {
+    Unit,
+    #[id = 0x2A]
+    Named {
+        named: Named<'a>,
+    },
+    Tuple(Tuple),
+}
+
+// only consists of the id of the variant
+#[derive(Encode, Decode, Debug, PartialEq)]
+enum Kind {
+    First,
+    #[id = 3]
+    Second,
+}
+
+fn main() -> Result<()> {
+    let packet = Packet::Named {
+        named: Named {
+            id: 1,
+            name: "name".into(),
+            values: vec![2, 3],
+            flags: [true, false, true, false, false, false, false, false],
+        },
+    };
+    let mut data = vec![];
+    packet.encode(&mut data)?;
+    assert_eq!(Packet::decode(&mut data.as_slice())?, packet);
+    assert_eq!(packet.name(), "Named");
+    assert_eq!(Packet::Tuple(Tuple(4, String::new())).id(), 0x2B);
+
+    let mut data = vec![];
+    Kind::Second.encode(&mut data)?;
+    assert_eq!(data, [3]);
+    assert!(matches!(
+        Kind::decode(&mut [1].as_slice()),
+        Err(Error::UnknownVariant(1))
+    ));
+    Ok(())
+}
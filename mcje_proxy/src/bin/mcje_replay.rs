@@ -0,0 +1,174 @@
+use std::{path::PathBuf, sync::Arc, time::Duration};
+
+use clap::{Parser, Subcommand};
+use tokio::{
+    net::{TcpListener, TcpStream},
+    time::Instant,
+};
+
+use iokum_mcje::{
+    capture::{CaptureReader, CaptureRecord},
+    codec::Compression,
+    connection::{self, Connection},
+    inspect::{inspect, Filter},
+    packet::{c2s, s2c, Direction, State},
+    types::{Component, Intention, Json, Status},
+    Decode, Error, Result,
+};
+
+#[derive(Parser)]
+#[command(about)]
+struct Arguments {
+    /// Path to the capture
+    capture: PathBuf,
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Decodes and prints all packets
+    Dump {
+        /// Prints one JSON object per packet instead of a tree
+        #[arg(long)]
+        json: bool,
+        /// Names of the packets to print, may contain * as wildcard
+        #[arg(long)]
+        include: Vec<String>,
+        /// Names of the packets to skip, may contain * as wildcard
+        #[arg(long)]
+        exclude: Vec<String>,
+        /// Number of items lists are truncated to in the tree
+        #[arg(long, default_value_t = 16)]
+        max_items: usize,
+    },
+    /// Serves the capture to clients, as if they were connected to the
+    /// recorded server
+    Serve {
+        /// Address to bind to
+        #[arg(long, default_value = "127.0.0.1:25565")]
+        addr: String,
+        /// Playback speed
+        #[arg(long, default_value_t = 1.0)]
+        speed: f64,
+    },
+}
+
+#[tokio::main]
+async fn main() -> std::result::Result<(), Box<dyn std::error::Error>> {
+    // parse arguments and read capture
+    let arguments = Arguments::parse();
+    let records = CaptureReader::open(&arguments.capture)?.collect::<Result<Vec<_>>>()?;
+    match arguments.command {
+        Command::Dump {
+            json,
+            include,
+            exclude,
+            max_items,
+        } => {
+            let filter = Filter::new(include, exclude);
+            for record in &records {
+                if !filter.matches_data(record.direction, record.state, &record.data) {
+                    continue;
+                }
+                let inspection = inspect(record.direction, record.state, &record.data);
+                if json {
+                    let mut json = inspection.to_json();
+                    json["time"] = record.time.into();
+                    println!("{json}");
+                } else {
+                    print!(
+                        "{:>12.3} {}",
+                        record.time as f64 / 1000.0,
+                        inspection.to_tree(max_items)
+                    );
+                }
+            }
+        }
+        Command::Serve { addr, speed } => {
+            let records = Arc::new(records);
+            let listener = TcpListener::bind(addr).await?;
+            loop {
+                let (socket, _) = connection::accept(&listener).await;
+                let records = records.clone();
+                tokio::spawn(async move {
+                    if let Err(error) = serve(socket, &records, speed).await {
+                        eprintln!("{error}");
+                    }
+                });
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Sends all recorded packets of the server with their original timing, the
+/// packets sent by the client are discarded.
+async fn serve(socket: TcpStream, records: &[CaptureRecord], speed: f64) -> Result<()> {
+    let mut connection = Connection::new(socket);
+    let c2s::HandshakePacket::Intention { intention, .. } = connection.receive().await?.decode()?;
+    match intention {
+        Intention::Status => {
+            let status = Status::builder()
+                .description(Component::Literal("Replay".to_string()))
+                .build();
+            connection.receive().await?;
+            connection
+                .send(&s2c::StatusPacket::StatusResponse {
+                    status: Json(status),
+                })
+                .await?;
+            let c2s::StatusPacket::PingRequest { time } = connection.receive().await?.decode()?
+            else {
+                return Err(Error::Unexpected);
+            };
+            connection
+                .send(&s2c::StatusPacket::PongResponse { time })
+                .await?;
+            return Ok(());
+        }
+        Intention::Login => {}
+        Intention::Game => return Err(Error::Unexpected),
+    }
+    connection.receive().await?;
+
+    let start = Instant::now();
+    for record in records {
+        if record.direction != Direction::S2c || !matches!(record.state, State::Login | State::Game)
+        {
+            continue;
+        }
+        let deadline = start + Duration::from_micros(record.time as u64).div_f64(speed);
+        loop {
+            tokio::select! {
+                _ = tokio::time::sleep_until(deadline) => break,
+                packet = connection.receive() => {
+                    packet?;
+                }
+            }
+        }
+
+        if record.state == State::Login {
+            match s2c::LoginPacket::decode(&mut record.data.as_slice())? {
+                // the capture is decrypted already
+                s2c::LoginPacket::Hello { .. } | s2c::LoginPacket::CustomQuery { .. } => {}
+                packet @ s2c::LoginPacket::LoginCompression {
+                    compression_threshold,
+                } => {
+                    connection.send(&packet).await?;
+                    connection
+                        .codec_mut()
+                        .enable_compression(Compression::default(), compression_threshold as u16);
+                }
+                packet => connection.send(&packet).await?,
+            }
+        } else {
+            connection.send_raw(&record.data).await?;
+        }
+    }
+
+    // keep the connection open until the client disconnects
+    loop {
+        connection.receive().await?;
+    }
+}
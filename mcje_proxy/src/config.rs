@@ -0,0 +1,120 @@
+use std::{collections::HashMap, path::PathBuf, time::Duration};
+
+use serde::Deserialize;
+
+use iokum_mcje::{codec::MAX_PACKET_LENGTH, forwarding::Forwarding};
+
+#[derive(Deserialize, Clone, Debug)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub bind: String,
+    /// Authenticates players with the session server, which requires the
+    /// servers to be in offline mode.
+    pub online_mode: bool,
+    pub compression_threshold: Option<u16>,
+    /// Intercepted packets and packets sent by plugins which are longer are
+    /// dropped, in bytes before compression. Packets which aren't intercepted
+    /// are passed through unchecked.
+    pub max_packet_length: usize,
+    pub forwarding: ForwardingConfig,
+    /// Timeout for connecting to servers, in seconds.
+    pub connect_timeout: u64,
+    /// Time given to players to be disconnected on shutdown, in seconds.
+    pub shutdown_timeout: u64,
+    /// Directory the connections of players are recorded to, one capture per
+    /// connection.
+    pub capture_dir: Option<PathBuf>,
+    /// Prints the game packets of all connections.
+    pub inspect: Option<InspectConfig>,
+
+    /// Addresses of the servers by name.
+    pub servers: HashMap<String, String>,
+    /// Server players are connected to if no forced host matches, and which
+    /// they fall back to when kicked.
+    pub default_server: String,
+    /// Servers by host name.
+    pub forced_hosts: HashMap<String, String>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            bind: "0.0.0.0:25565".to_string(),
+            online_mode: true,
+            compression_threshold: Some(256),
+            max_packet_length: MAX_PACKET_LENGTH,
+            forwarding: ForwardingConfig::None,
+            connect_timeout: 5,
+            shutdown_timeout: 10,
+            capture_dir: None,
+            inspect: None,
+            servers: Default::default(),
+            default_server: "lobby".to_string(),
+            forced_hosts: Default::default(),
+        }
+    }
+}
+
+impl Config {
+    /// Returns the server for the host name the player connected with.
+    pub fn route(&self, host_name: &str) -> &str {
+        self.forced_hosts
+            .get(host_name)
+            .unwrap_or(&self.default_server)
+    }
+
+    pub fn connect_timeout(&self) -> Duration {
+        Duration::from_secs(self.connect_timeout)
+    }
+
+    pub fn shutdown_timeout(&self) -> Duration {
+        Duration::from_secs(self.shutdown_timeout)
+    }
+}
+
+#[derive(Deserialize, Clone, Debug)]
+#[serde(default, deny_unknown_fields)]
+pub struct InspectConfig {
+    /// Prints one JSON object per packet instead of a tree.
+    pub json: bool,
+    /// Names of the packets to print, may contain `*` as wildcard.
+    pub include: Vec<String>,
+    /// Names of the packets to skip, may contain `*` as wildcard.
+    pub exclude: Vec<String>,
+    /// Number of items lists are truncated to in the tree.
+    pub max_items: usize,
+}
+
+impl Default for InspectConfig {
+    fn default() -> Self {
+        Self {
+            json: false,
+            include: vec![],
+            exclude: vec![],
+            max_items: 16,
+        }
+    }
+}
+
+#[derive(Deserialize, Clone, Debug, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum ForwardingConfig {
+    #[default]
+    None,
+    BungeeCord,
+    Velocity {
+        secret: String,
+    },
+}
+
+impl From<ForwardingConfig> for Forwarding {
+    fn from(value: ForwardingConfig) -> Self {
+        match value {
+            ForwardingConfig::None => Forwarding::None,
+            ForwardingConfig::BungeeCord => Forwarding::BungeeCord,
+            ForwardingConfig::Velocity { secret } => Forwarding::Velocity {
+                secret: secret.into_bytes(),
+            },
+        }
+    }
+}
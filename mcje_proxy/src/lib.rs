@@ -0,0 +1,1130 @@
+use std::{
+    collections::{hash_map::Entry, HashMap, HashSet},
+    future::Future,
+    io::ErrorKind,
+    net::SocketAddr,
+    path::Path,
+    sync::{Arc, Mutex, RwLock},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use rand::{rngs::OsRng, Rng};
+use rsa::{pkcs8::EncodePublicKey, Pkcs1v15Encrypt, RsaPrivateKey};
+use sha1::{Digest, Sha1};
+use thiserror::Error;
+use tokio::{
+    io::{AsyncRead, AsyncWrite},
+    net::{TcpListener, TcpStream},
+    sync::mpsc,
+    task::JoinSet,
+};
+use uuid::Uuid;
+
+use iokum_mcje::{
+    capture::CaptureWriter,
+    channel::{BungeeCord, Payload},
+    codec::Compression,
+    connection::{self, Connection, Packet},
+    forwarding::{ForwardedPlayer, Forwarding},
+    inspect::{inspect, Filter, Inspection},
+    packet::{c2s, packet_name, s2c, Direction, PacketName, State},
+    ping::ping_stream,
+    types::{Component, Intention, Json, Status, User},
+    Decode, Encode, PROTOCOL_VERSION, VERSION,
+};
+use mojang_session_api::apis::{configuration::Configuration, default_api::has_joined_server};
+
+pub use config::{Config, ForwardingConfig, InspectConfig};
+
+mod config;
+
+#[derive(Error, Debug)]
+pub enum Error {
+    #[error("IO error")]
+    Io(#[from] std::io::Error),
+    #[error("Protocol error")]
+    Protocol(#[from] iokum_mcje::Error),
+    #[error("RSA error")]
+    Rsa(#[from] rsa::Error),
+
+    #[error("Unknown server: {0}")]
+    UnknownServer(String),
+    #[error("Disconnected: {0:?}")]
+    Disconnected(Box<Component>),
+    #[error("Authentication failed")]
+    Authentication,
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+/// Hooks into the proxy, all methods are called from the task of the
+/// respective connection.
+#[allow(unused_variables)]
+pub trait Plugin: Send + Sync + 'static {
+    fn status(&self, proxy: &Proxy, status: &mut Status) {}
+
+    /// Called after the player has been authenticated, the server the player
+    /// will be connected to can be changed, and returning an error disconnects
+    /// the player with the given reason.
+    #[allow(clippy::result_large_err)]
+    fn login(
+        &self,
+        proxy: &Proxy,
+        user: &User,
+        server: &mut String,
+    ) -> std::result::Result<(), Component> {
+        Ok(())
+    }
+
+    /// Called after the player has been connected to a server, including
+    /// switching servers.
+    fn connect(&self, proxy: &Proxy, session: &Arc<Session>, server: &str) {}
+
+    /// Returns whether the plugin has to see packets with the given id, all
+    /// other packets are passed through without being decoded.
+    fn intercepts(&self, direction: Direction, id: i32) -> bool {
+        true
+    }
+
+    /// Called for each decodable packet sent by the client, the packet can be
+    /// rewritten, and returning false drops it.
+    fn c2s(&self, proxy: &Proxy, session: &Arc<Session>, packet: &mut c2s::GamePacket) -> bool {
+        true
+    }
+
+    /// Called for each decodable packet sent by the server, the packet can be
+    /// rewritten, and returning false drops it.
+    fn s2c(&self, proxy: &Proxy, session: &Arc<Session>, packet: &mut s2c::GamePacket) -> bool {
+        true
+    }
+
+    fn disconnect(&self, proxy: &Proxy, session: &Arc<Session>) {}
+
+    /// Called if a connection ends with an error, or capturing it fails.
+    fn error(&self, proxy: &Proxy, address: SocketAddr, error: &Error) {}
+
+    /// Called for packets which are dropped for exceeding the maximum packet
+    /// length.
+    fn dropped(&self, proxy: &Proxy, session: &Arc<Session>, name: &str, length: usize) {}
+
+    /// Called for each game packet matching the inspect filter, if inspection
+    /// is enabled.
+    fn inspect(&self, proxy: &Proxy, session: &Arc<Session>, inspection: &Inspection) {}
+}
+
+enum Command {
+    Switch(String),
+    Send(Box<s2c::GamePacket<'static>>),
+    SendToServer(c2s::GamePacket<'static>),
+    Disconnect(Component),
+}
+
+/// Player connected to the proxy.
+pub struct Session {
+    user: User,
+    address: SocketAddr,
+    server: Mutex<String>,
+    commands: mpsc::UnboundedSender<Command>,
+}
+
+impl Session {
+    pub fn user(&self) -> &User {
+        &self.user
+    }
+
+    pub fn address(&self) -> SocketAddr {
+        self.address
+    }
+
+    /// Name of the server the player is connected to.
+    pub fn server(&self) -> String {
+        self.server.lock().unwrap().clone()
+    }
+
+    /// Connects the player to another server, the player stays on the current
+    /// server if that fails.
+    pub fn switch(&self, server: impl Into<String>) {
+        let _ = self.commands.send(Command::Switch(server.into()));
+    }
+
+    /// Queues a packet to be sent to the client.
+    pub fn send(&self, packet: s2c::GamePacket<'static>) {
+        let _ = self.commands.send(Command::Send(Box::new(packet)));
+    }
+
+    /// Queues a packet to be sent to the server, as if it was sent by the
+    /// client.
+    pub fn send_to_server(&self, packet: c2s::GamePacket<'static>) {
+        let _ = self.commands.send(Command::SendToServer(packet));
+    }
+
+    pub fn disconnect(&self, reason: Component) {
+        let _ = self.commands.send(Command::Disconnect(reason));
+    }
+}
+
+pub struct Proxy {
+    config: Config,
+    forwarding: Forwarding,
+    /// Key pair used for encryption in online mode, the public key is DER
+    /// encoded.
+    key: Option<(RsaPrivateKey, Vec<u8>)>,
+    inspect_filter: Option<Filter>,
+    plugins: Vec<Box<dyn Plugin>>,
+
+    sessions: RwLock<HashMap<Uuid, Arc<Session>>>,
+}
+
+impl Proxy {
+    /// Validates the config, and generates the key pair in online mode.
+    pub fn new(config: Config) -> Result<Self> {
+        for server in std::iter::once(&config.default_server).chain(config.forced_hosts.values()) {
+            if !config.servers.contains_key(server) {
+                return Err(Error::UnknownServer(server.clone()));
+            }
+        }
+        let key = if config.online_mode {
+            let private_key = RsaPrivateKey::new(&mut OsRng, 1024)?;
+            let public_key = private_key
+                .to_public_key()
+                .to_public_key_der()
+                .unwrap()
+                .into_vec();
+            Some((private_key, public_key))
+        } else {
+            None
+        };
+        let inspect_filter = config
+            .inspect
+            .as_ref()
+            .map(|inspect| Filter::new(inspect.include.clone(), inspect.exclude.clone()));
+        Ok(Self {
+            forwarding: config.forwarding.clone().into(),
+            config,
+            key,
+            inspect_filter,
+            plugins: vec![],
+            sessions: Default::default(),
+        })
+    }
+
+    pub fn add_plugin(&mut self, plugin: impl Plugin) {
+        self.plugins.push(Box::new(plugin));
+    }
+
+    pub fn config(&self) -> &Config {
+        &self.config
+    }
+
+    pub fn sessions(&self) -> Vec<Arc<Session>> {
+        self.sessions.read().unwrap().values().cloned().collect()
+    }
+
+    pub fn session(&self, id: &Uuid) -> Option<Arc<Session>> {
+        self.sessions.read().unwrap().get(id).cloned()
+    }
+
+    /// Accepts connections until the shutdown future completes, afterwards all
+    /// players are disconnected, and the remaining connections are closed
+    /// after the shutdown timeout.
+    pub async fn run(
+        self: Arc<Self>,
+        listener: TcpListener,
+        shutdown: impl Future<Output = ()>,
+    ) -> Result<()> {
+        let mut tasks = JoinSet::new();
+        tokio::pin!(shutdown);
+        loop {
+            tokio::select! {
+                (socket, address) = connection::accept(&listener) => {
+                    let proxy = self.clone();
+                    tasks.spawn(async move {
+                        if let Err(error) = proxy.handle(socket, address).await {
+                            for plugin in &proxy.plugins {
+                                plugin.error(&proxy, address, &error);
+                            }
+                        }
+                    });
+                }
+                Some(_) = tasks.join_next() => {}
+                _ = &mut shutdown => break,
+            }
+        }
+        drop(listener);
+
+        for session in self.sessions() {
+            session.disconnect(Component::Literal("Proxy is shutting down".to_string()));
+        }
+        let closed = tokio::time::timeout(self.config.shutdown_timeout(), async {
+            while tasks.join_next().await.is_some() {}
+        })
+        .await;
+        if closed.is_err() {
+            tasks.shutdown().await;
+        }
+        Ok(())
+    }
+
+    /// Handles a single connection, starting from the handshake.
+    pub async fn handle<S: AsyncRead + AsyncWrite + Unpin>(
+        &self,
+        stream: S,
+        address: SocketAddr,
+    ) -> Result<()> {
+        let mut connection = Connection::new(stream);
+        let handshake = connection.receive().await?;
+        let c2s::HandshakePacket::Intention {
+            protocol_version,
+            host_name,
+            intention,
+            ..
+        } = handshake.decode()?;
+        // modded clients append NUL separated data, and the host name might be
+        // fully qualified
+        let host_name = host_name
+            .split('\0')
+            .next()
+            .unwrap_or_default()
+            .trim_end_matches('.')
+            .to_ascii_lowercase();
+        match intention {
+            Intention::Status => self.handle_status(connection, &host_name).await,
+            Intention::Login => {
+                if protocol_version != PROTOCOL_VERSION {
+                    return login_disconnect(
+                        &mut connection,
+                        Component::Literal(format!("Outdated client! Please use {VERSION}")),
+                    )
+                    .await;
+                }
+                if let Some(capture_dir) = &self.config.capture_dir {
+                    if let Err(error) = capture(&mut connection, capture_dir, address, &handshake) {
+                        for plugin in &self.plugins {
+                            plugin.error(self, address, &error);
+                        }
+                    }
+                }
+                self.handle_login(connection, &host_name, address).await
+            }
+            Intention::Game => Err(iokum_mcje::Error::Unexpected.into()),
+        }
+    }
+
+    async fn handle_status<S: AsyncRead + AsyncWrite + Unpin>(
+        &self,
+        mut connection: Connection<S>,
+        host_name: &str,
+    ) -> Result<()> {
+        loop {
+            match connection.receive().await?.decode()? {
+                c2s::StatusPacket::StatusRequest => {
+                    let mut status = match self.ping(self.config.route(host_name)).await {
+                        Ok(status) => status,
+                        Err(_) => Status::builder()
+                            .description(Component::Literal("Can't connect to server".to_string()))
+                            .build(),
+                    };
+                    for plugin in &self.plugins {
+                        plugin.status(self, &mut status);
+                    }
+                    connection
+                        .send(&s2c::StatusPacket::StatusResponse {
+                            status: Json(status),
+                        })
+                        .await?;
+                }
+                c2s::StatusPacket::PingRequest { time } => {
+                    connection
+                        .send(&s2c::StatusPacket::PongResponse { time })
+                        .await?;
+                    return Ok(());
+                }
+            }
+        }
+    }
+
+    async fn handle_login<S: AsyncRead + AsyncWrite + Unpin>(
+        &self,
+        mut connection: Connection<S>,
+        host_name: &str,
+        address: SocketAddr,
+    ) -> Result<()> {
+        let c2s::LoginPacket::Hello { name, uuid } = connection.receive().await?.decode()? else {
+            return Err(iokum_mcje::Error::Unexpected.into());
+        };
+        let user = if let Some((private_key, public_key)) = &self.key {
+            let nonce = OsRng.gen::<[u8; 4]>();
+            connection
+                .send(&s2c::LoginPacket::Hello {
+                    server_id: String::new(),
+                    public_key: public_key.clone(),
+                    nonce: nonce.to_vec(),
+                })
+                .await?;
+            let c2s::LoginPacket::Key {
+                key,
+                nonce: encrypted_nonce,
+            } = connection.receive().await?.decode()?
+            else {
+                return Err(iokum_mcje::Error::Unexpected.into());
+            };
+            if private_key.decrypt(Pkcs1v15Encrypt, &encrypted_nonce)? != nonce {
+                return Err(Error::Authentication);
+            }
+            let key = private_key.decrypt(Pkcs1v15Encrypt, &key)?;
+            if key.len() != 16 {
+                return Err(Error::Authentication);
+            }
+            connection.codec_mut().enable_encryption(&key);
+
+            match has_joined_server(
+                &Configuration::new(),
+                &name,
+                &server_hash("", &key, public_key),
+                None,
+            )
+            .await
+            {
+                Ok(user) => user,
+                Err(_) => {
+                    return login_disconnect(
+                        &mut connection,
+                        Component::Literal("Failed to verify username!".to_string()),
+                    )
+                    .await;
+                }
+            }
+        } else {
+            User {
+                id: uuid.unwrap_or_else(Uuid::new_v4),
+                name,
+                properties: vec![],
+            }
+        };
+
+        let mut server = self.config.route(host_name).to_string();
+        let (sender, mut receiver) = mpsc::unbounded_channel();
+        let session = Arc::new(Session {
+            user,
+            address,
+            server: Mutex::new(server.clone()),
+            commands: sender,
+        });
+        // checked and reserved at once, so only one of two concurrent logins
+        // with the same id gets through
+        let reserved = match self.sessions.write().unwrap().entry(session.user.id) {
+            Entry::Occupied(_) => false,
+            Entry::Vacant(entry) => {
+                entry.insert(session.clone());
+                true
+            }
+        };
+        if !reserved {
+            return login_disconnect(
+                &mut connection,
+                Component::Literal("You are already connected to this proxy!".to_string()),
+            )
+            .await;
+        }
+
+        let result = async {
+            for plugin in &self.plugins {
+                if let Err(reason) = plugin.login(self, &session.user, &mut server) {
+                    return login_disconnect(&mut connection, reason).await;
+                }
+            }
+            *session.server.lock().unwrap() = server.clone();
+            let backend = match self.connect(&session, &server).await {
+                Ok(backend) => backend,
+                Err(error) => {
+                    return login_disconnect(&mut connection, disconnect_reason(&server, error))
+                        .await;
+                }
+            };
+
+            if let Some(compression_threshold) = self.config.compression_threshold {
+                connection
+                    .send(&s2c::LoginPacket::LoginCompression {
+                        compression_threshold: compression_threshold as i32,
+                    })
+                    .await?;
+                connection
+                    .codec_mut()
+                    .enable_compression(Compression::default(), compression_threshold);
+            }
+            connection
+                .send(&s2c::LoginPacket::GameProfile {
+                    game_profile: session.user.clone(),
+                })
+                .await?;
+            connection.set_state(State::Game);
+
+            for plugin in &self.plugins {
+                plugin.connect(self, &session, &server);
+            }
+            let result = self
+                .play(&mut connection, &session, backend, &mut receiver)
+                .await;
+            self.remove_session(&session);
+            for plugin in &self.plugins {
+                plugin.disconnect(self, &session);
+            }
+            result
+        }
+        .await;
+        // the login may have ended early
+        self.remove_session(&session);
+        result
+    }
+
+    fn remove_session(&self, session: &Arc<Session>) {
+        let mut sessions = self.sessions.write().unwrap();
+        // only removes the entry if it's still the one of this connection
+        if let Some(existing) = sessions.get(&session.user.id) {
+            if Arc::ptr_eq(existing, session) {
+                sessions.remove(&session.user.id);
+            }
+        }
+    }
+
+    async fn play<S: AsyncRead + AsyncWrite + Unpin>(
+        &self,
+        client: &mut Connection<S>,
+        session: &Arc<Session>,
+        mut backend: Connection<TcpStream>,
+        receiver: &mut mpsc::UnboundedReceiver<Command>,
+    ) -> Result<()> {
+        // players added to the tab list by the current server, which have to be
+        // removed when switching servers
+        let mut tab_list = HashSet::new();
+        // set after switching servers, until the login of the new server has been
+        // forwarded
+        let mut switching = false;
+        loop {
+            tokio::select! {
+                frame = client.receive_frame() => {
+                    let frame = frame?;
+                    if !self.intercepts(Direction::C2s, frame.id()) {
+                        // packets belong to the previous server
+                        if !switching {
+                            backend.send_frame(&frame).await?;
+                        }
+                        continue;
+                    }
+                    let packet = Packet(frame.decompress()?);
+                    self.inspect(session, Direction::C2s, &packet);
+                    // packets belong to the previous server
+                    if switching {
+                        continue;
+                    }
+                    match packet.decode::<c2s::GamePacket>() {
+                        Ok(mut decoded) => {
+                            if self.plugins.iter().all(|plugin| plugin.c2s(self, session, &mut decoded))
+                                && self.fits(session, &decoded)?
+                            {
+                                backend.send(&decoded).await?;
+                            }
+                        }
+                        // packets which can't be decoded are passed through
+                        Err(_) => backend.send_raw(&packet.0).await?,
+                    }
+                }
+                frame = backend.receive_frame() => {
+                    let frame = match frame {
+                        Ok(frame) => frame,
+                        Err(_) => {
+                            let reason = Component::Literal("Lost connection to server".to_string());
+                            if self.fall_back(client, session, &mut backend, &mut tab_list, &reason).await? {
+                                switching = true;
+                                continue;
+                            }
+                            client.send(&s2c::GamePacket::Disconnect {
+                                reason: Json(reason),
+                            }).await?;
+                            return Ok(());
+                        }
+                    };
+                    if !self.intercepts(Direction::S2c, frame.id()) {
+                        client.send_frame(&frame).await?;
+                        continue;
+                    }
+                    let packet = Packet(frame.decompress()?);
+                    self.inspect(session, Direction::S2c, &packet);
+                    let mut decoded = match packet.decode::<s2c::GamePacket>() {
+                        Ok(decoded) => decoded,
+                        Err(_) => {
+                            client.send_raw(&packet.0).await?;
+                            continue;
+                        }
+                    };
+                    if !self.plugins.iter().all(|plugin| plugin.s2c(self, session, &mut decoded))
+                        || !self.fits(session, &decoded)?
+                    {
+                        continue;
+                    }
+                    match &decoded {
+                        s2c::GamePacket::Login {
+                            game_type,
+                            previous_game_type,
+                            dimension_type,
+                            dimension,
+                            seed,
+                            is_debug,
+                            is_flat,
+                            last_death_location,
+                            ..
+                        } if switching => {
+                            // the client accepts another login, but only reloads the level on
+                            // respawn
+                            let respawn = s2c::GamePacket::Respawn {
+                                dimension_type: dimension_type.clone(),
+                                dimension: dimension.clone(),
+                                seed: *seed,
+                                player_game_type: *game_type,
+                                previous_player_game_type: *previous_game_type,
+                                is_debug: *is_debug,
+                                is_flat: *is_flat,
+                                keep_all_player_data: false,
+                                last_death_location: last_death_location.clone(),
+                            };
+                            client.send(&decoded).await?;
+                            client.send(&respawn).await?;
+                            switching = false;
+                            continue;
+                        }
+                        s2c::GamePacket::CustomPayload { identifier, data }
+                            if identifier == BungeeCord::IDENTIFIER || identifier == "BungeeCord" =>
+                        {
+                            // messages are addressed to the proxy
+                            if let Ok(message) = BungeeCord::decode(&mut data.0.as_ref()) {
+                                if message.subchannel == "Connect" {
+                                    if let Some(server) = message.arguments()?.into_iter().next() {
+                                        session.switch(server);
+                                    }
+                                }
+                            }
+                            continue;
+                        }
+                        s2c::GamePacket::PlayerInfoUpdate(packet) => {
+                            for entry in &packet.entries {
+                                if entry.profile.is_some() {
+                                    tab_list.insert(entry.profile_id);
+                                }
+                            }
+                        }
+                        s2c::GamePacket::PlayerInfoRemove { profile_ids } => {
+                            for profile_id in profile_ids {
+                                tab_list.remove(profile_id);
+                            }
+                        }
+                        s2c::GamePacket::Disconnect { reason } => {
+                            if self.fall_back(client, session, &mut backend, &mut tab_list, &reason.0).await? {
+                                switching = true;
+                                continue;
+                            }
+                            client.send(&decoded).await?;
+                            return Ok(());
+                        }
+                        _ => {}
+                    }
+                    client.send(&decoded).await?;
+                }
+                command = receiver.recv() => match command.unwrap() {
+                    Command::Switch(server) => {
+                        if server == session.server() {
+                            send_message(client, Component::Literal(format!(
+                                "You are already connected to {server}"
+                            ))).await?;
+                            continue;
+                        }
+                        match self.switch(client, session, &mut backend, &mut tab_list, &server).await {
+                            Ok(()) => switching = true,
+                            Err(error) => send_message(client, disconnect_reason(&server, error)).await?,
+                        }
+                    }
+                    Command::Send(packet) => {
+                        if self.fits(session, &*packet)? {
+                            client.send(&*packet).await?;
+                        }
+                    }
+                    Command::SendToServer(packet) => {
+                        if self.fits(session, &packet)? {
+                            backend.send(&packet).await?;
+                        }
+                    }
+                    Command::Disconnect(reason) => {
+                        client.send(&s2c::GamePacket::Disconnect {
+                            reason: Json(reason),
+                        }).await?;
+                        return Ok(());
+                    }
+                }
+            }
+        }
+    }
+
+    /// Connects to the given server and logs in, on success the server is in
+    /// game state.
+    async fn connect(&self, session: &Session, server: &str) -> Result<Connection<TcpStream>> {
+        let socket = self.connect_socket(server).await?;
+        let peer_addr = socket.peer_addr()?;
+        let player = ForwardedPlayer {
+            address: session.address.ip(),
+            user: session.user.clone(),
+        };
+
+        let mut connection = Connection::new(socket);
+        connection
+            .send(&c2s::HandshakePacket::Intention {
+                protocol_version: PROTOCOL_VERSION,
+                host_name: self
+                    .forwarding
+                    .host_name(&peer_addr.ip().to_string(), &player)?,
+                port: peer_addr.port(),
+                intention: Intention::Login,
+            })
+            .await?;
+        connection
+            .send(&c2s::LoginPacket::Hello {
+                name: session.user.name.clone(),
+                uuid: Some(session.user.id),
+            })
+            .await?;
+        loop {
+            match connection.receive().await?.decode()? {
+                s2c::LoginPacket::LoginDisconnect { reason } => {
+                    return Err(Error::Disconnected(Box::new(reason.0)))
+                }
+                s2c::LoginPacket::Hello { .. } => {
+                    return Err(Error::Disconnected(Box::new(Component::Literal(
+                        "Server is in online mode".to_string(),
+                    ))))
+                }
+                s2c::LoginPacket::GameProfile { .. } => return Ok(connection),
+                s2c::LoginPacket::LoginCompression {
+                    compression_threshold,
+                } => {
+                    // compressing smaller packets than the threshold is not allowed
+                    let compression_threshold = u16::try_from(compression_threshold)
+                        .map_err(|_| iokum_mcje::Error::Unexpected)?;
+                    connection
+                        .codec_mut()
+                        .enable_compression(Compression::default(), compression_threshold);
+                }
+                s2c::LoginPacket::CustomQuery {
+                    transaction_id,
+                    identifier,
+                    data,
+                } => {
+                    let response = self
+                        .forwarding
+                        .respond(&player, transaction_id, &identifier, &data.0)?
+                        .unwrap_or(c2s::LoginPacket::CustomQuery {
+                            transaction_id,
+                            data: None,
+                        });
+                    connection.send(&response).await?;
+                }
+            }
+        }
+    }
+
+    async fn connect_socket(&self, server: &str) -> Result<TcpStream> {
+        let addr = self
+            .config
+            .servers
+            .get(server)
+            .ok_or_else(|| Error::UnknownServer(server.to_string()))?;
+        let socket = tokio::time::timeout(self.config.connect_timeout(), TcpStream::connect(addr))
+            .await
+            .map_err(|_| std::io::Error::from(ErrorKind::TimedOut))??;
+        socket.set_nodelay(true)?;
+        Ok(socket)
+    }
+
+    async fn ping(&self, server: &str) -> Result<Status> {
+        let socket = self.connect_socket(server).await?;
+        let peer_addr = socket.peer_addr()?;
+        Ok(
+            ping_stream(socket, &peer_addr.ip().to_string(), peer_addr.port())
+                .await?
+                .status,
+        )
+    }
+
+    /// Replaces the connection to the current server, the login of the new
+    /// server has to be followed by a respawn.
+    async fn switch<S: AsyncRead + AsyncWrite + Unpin>(
+        &self,
+        client: &mut Connection<S>,
+        session: &Arc<Session>,
+        backend: &mut Connection<TcpStream>,
+        tab_list: &mut HashSet<Uuid>,
+        server: &str,
+    ) -> Result<()> {
+        *backend = self.connect(session, server).await?;
+        if !tab_list.is_empty() {
+            client
+                .send(&s2c::GamePacket::PlayerInfoRemove {
+                    profile_ids: tab_list.drain().collect(),
+                })
+                .await?;
+        }
+        *session.server.lock().unwrap() = server.to_string();
+        for plugin in &self.plugins {
+            plugin.connect(self, session, server);
+        }
+        Ok(())
+    }
+
+    /// Switches to the default server after being kicked, returns false if the
+    /// player was already on the default server or the switch failed.
+    async fn fall_back<S: AsyncRead + AsyncWrite + Unpin>(
+        &self,
+        client: &mut Connection<S>,
+        session: &Arc<Session>,
+        backend: &mut Connection<TcpStream>,
+        tab_list: &mut HashSet<Uuid>,
+        reason: &Component,
+    ) -> Result<bool> {
+        let server = &self.config.default_server;
+        if session.server() == *server
+            || self
+                .switch(client, session, backend, tab_list, server)
+                .await
+                .is_err()
+        {
+            return Ok(false);
+        }
+        send_message(client, reason.clone()).await?;
+        Ok(true)
+    }
+
+    /// Returns whether the game packet with the given id has to be decoded,
+    /// as it's handled by the proxy, a plugin or inspected.
+    fn intercepts(&self, direction: Direction, id: i32) -> bool {
+        let name = packet_name(direction, State::Game, id);
+        let handled = direction == Direction::S2c
+            && matches!(
+                name,
+                Some(
+                    "Login"
+                        | "CustomPayload"
+                        | "PlayerInfoUpdate"
+                        | "PlayerInfoRemove"
+                        | "Disconnect"
+                )
+            );
+        handled
+            || self
+                .inspect_filter
+                .as_ref()
+                .is_some_and(|filter| filter.matches(name.unwrap_or_default()))
+            || self
+                .plugins
+                .iter()
+                .any(|plugin| plugin.intercepts(direction, id))
+    }
+
+    /// Returns whether the packet doesn't exceed the maximum length, packets
+    /// which do are dropped instead of closing the connection. Checked for
+    /// every intercepted packet after the plugins ran, whether or not they
+    /// changed it, and for every packet sent by plugins.
+    fn fits(&self, session: &Arc<Session>, packet: &(impl Encode + PacketName)) -> Result<bool> {
+        let length = packet.encoded_len()?;
+        if length > self.config.max_packet_length {
+            for plugin in &self.plugins {
+                plugin.dropped(self, session, packet.name(), length);
+            }
+            return Ok(false);
+        }
+        Ok(true)
+    }
+
+    /// Passes the game packet to the plugins if inspection is enabled and it
+    /// matches the filter.
+    fn inspect(&self, session: &Arc<Session>, direction: Direction, packet: &Packet) {
+        let Some(filter) = &self.inspect_filter else {
+            return;
+        };
+        if !filter.matches_data(direction, State::Game, &packet.0) {
+            return;
+        }
+        let inspection = inspect(direction, State::Game, &packet.0);
+        for plugin in &self.plugins {
+            plugin.inspect(self, session, &inspection);
+        }
+    }
+}
+
+async fn login_disconnect<S: AsyncRead + AsyncWrite + Unpin>(
+    connection: &mut Connection<S>,
+    reason: Component,
+) -> Result<()> {
+    connection
+        .send(&s2c::LoginPacket::LoginDisconnect {
+            reason: Json(reason),
+        })
+        .await?;
+    Ok(())
+}
+
+async fn send_message<S: AsyncRead + AsyncWrite + Unpin>(
+    connection: &mut Connection<S>,
+    message: Component,
+) -> Result<()> {
+    connection
+        .send(&s2c::GamePacket::SystemChat {
+            content: Json(message),
+            overlay: false,
+        })
+        .await?;
+    Ok(())
+}
+
+/// Records the connection, starting with the already received handshake.
+fn capture<S: AsyncRead + AsyncWrite + Unpin>(
+    connection: &mut Connection<S>,
+    capture_dir: &Path,
+    address: SocketAddr,
+    handshake: &Packet,
+) -> Result<()> {
+    let time = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_millis();
+    let address = address.to_string().replace(['[', ']', ':'], "_");
+    let mut writer = CaptureWriter::create(capture_dir.join(format!("{time}-{address}.mcjc")))?;
+    writer.write(Direction::C2s, State::Handshake, &handshake.0)?;
+    connection.capture(writer, Direction::C2s, State::Login);
+    Ok(())
+}
+
+fn disconnect_reason(server: &str, error: Error) -> Component {
+    match error {
+        Error::Disconnected(reason) => *reason,
+        _ => Component::Literal(format!("Can't connect to {server}")),
+    }
+}
+
+/// Hash used for authenticating with the session server, which is the SHA-1
+/// digest formatted as signed hexadecimal number.
+fn server_hash(server_id: &str, key: &[u8], public_key: &[u8]) -> String {
+    let mut hash: [u8; 20] = Sha1::new()
+        .chain_update(server_id)
+        .chain_update(key)
+        .chain_update(public_key)
+        .finalize()
+        .into();
+    let negative = hash[0] & 0x80 != 0;
+    if negative {
+        // two's complement
+        let mut carry = true;
+        for byte in hash.iter_mut().rev() {
+            (*byte, carry) = (!*byte).overflowing_add(carry as u8);
+        }
+    }
+    let hash = hex::encode(hash);
+    let hash = hash.trim_start_matches('0');
+    if negative {
+        format!("-{hash}")
+    } else {
+        hash.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{borrow::Cow, sync::Arc, time::Duration};
+
+    use glam::DVec3;
+    use tokio::{io::DuplexStream, net::TcpListener};
+    use uuid::Uuid;
+
+    use iokum_mcje::{
+        connection::Connection,
+        forwarding::Forwarding,
+        packet::{c2s, s2c},
+        server::{Server, ServerConfig},
+        types::{GameType, Intention, Registries, Registry, Status},
+        PROTOCOL_VERSION,
+    };
+
+    use crate::{server_hash, Config, ForwardingConfig, Proxy};
+
+    #[test]
+    fn hash() {
+        fn hash(name: &str) -> String {
+            server_hash(name, &[], &[])
+        }
+
+        assert_eq!(hash("Notch"), "4ed1f46bbe04bc756bcb17c0c7ce3e4632f06a48");
+        assert_eq!(hash("jeb_"), "-7c9d5b0044c130109a5d7b5fb5c317c02b4e28c1");
+        assert_eq!(hash("simon"), "88e16a1019277b15d58faf0541e11910eb756f6");
+    }
+
+    async fn server(dimension: &str) -> String {
+        fn registry<T: Clone>() -> Cow<'static, Registry<T>> {
+            Cow::Owned(Registry {
+                type_: String::new(),
+                value: vec![],
+            })
+        }
+
+        let server = Arc::new(Server::new(ServerConfig {
+            status: Status::default(),
+            max_players: 20,
+            compression_threshold: Some(256),
+            keep_alive_interval: Duration::from_secs(15),
+            keep_alive_timeout: Duration::from_secs(30),
+            forwarding: Forwarding::Velocity {
+                secret: b"secret".to_vec(),
+            },
+            registries: Registries {
+                dimension_type: registry(),
+                biome: registry(),
+                chat_type: registry(),
+                damage_type: registry(),
+            },
+            dimension_type: "minecraft:overworld".to_string(),
+            dimension: dimension.to_string(),
+            game_type: GameType::Creative,
+            spawn_pos: DVec3::new(0.0, 64.0, 0.0),
+            chunk_radius: 1,
+        }));
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap().to_string();
+        tokio::spawn(server.run(listener));
+        addr
+    }
+
+    /// Connects to the proxy and sends the login start.
+    async fn login(
+        proxy: &Arc<Proxy>,
+        host_name: &str,
+        uuid: Option<Uuid>,
+    ) -> Connection<DuplexStream> {
+        let (client, proxy_stream) = tokio::io::duplex(1 << 16);
+        {
+            let proxy = proxy.clone();
+            tokio::spawn(async move {
+                proxy
+                    .handle(proxy_stream, "127.0.0.2:25565".parse().unwrap())
+                    .await
+            });
+        }
+        let mut connection = Connection::new(client);
+        connection
+            .send(&c2s::HandshakePacket::Intention {
+                protocol_version: PROTOCOL_VERSION,
+                host_name: host_name.to_string(),
+                port: 25565,
+                intention: Intention::Login,
+            })
+            .await
+            .unwrap();
+        connection
+            .send(&c2s::LoginPacket::Hello {
+                name: "player".to_string(),
+                uuid,
+            })
+            .await
+            .unwrap();
+        connection
+    }
+
+    #[tokio::test]
+    async fn login_and_switch() {
+        let proxy = Arc::new(
+            Proxy::new(Config {
+                online_mode: false,
+                compression_threshold: None,
+                forwarding: ForwardingConfig::Velocity {
+                    secret: "secret".to_string(),
+                },
+                servers: [
+                    ("lobby".to_string(), server("iokum:lobby").await),
+                    ("survival".to_string(), server("iokum:survival").await),
+                ]
+                .into(),
+                ..Default::default()
+            })
+            .unwrap(),
+        );
+
+        let mut connection = login(&proxy, "localhost", None).await;
+        let s2c::LoginPacket::GameProfile { game_profile } =
+            connection.receive().await.unwrap().decode().unwrap()
+        else {
+            panic!()
+        };
+
+        async fn next_login(connection: &mut Connection<DuplexStream>) -> String {
+            loop {
+                if let s2c::GamePacket::Login { dimension, .. } =
+                    connection.receive().await.unwrap().decode().unwrap()
+                {
+                    return dimension;
+                }
+            }
+        }
+
+        assert_eq!(next_login(&mut connection).await, "iokum:lobby");
+        let session = proxy.session(&game_profile.id).unwrap();
+        assert_eq!(session.server(), "lobby");
+
+        session.switch("survival");
+        assert_eq!(next_login(&mut connection).await, "iokum:survival");
+        let s2c::GamePacket::Respawn { dimension, .. } =
+            connection.receive().await.unwrap().decode().unwrap()
+        else {
+            panic!()
+        };
+        assert_eq!(dimension, "iokum:survival");
+        assert_eq!(session.server(), "survival");
+    }
+
+    #[tokio::test]
+    async fn duplicate_login() {
+        let proxy = Arc::new(
+            Proxy::new(Config {
+                online_mode: false,
+                compression_threshold: None,
+                forwarding: ForwardingConfig::Velocity {
+                    secret: "secret".to_string(),
+                },
+                servers: [
+                    ("lobby".to_string(), server("iokum:lobby").await),
+                    ("survival".to_string(), server("iokum:survival").await),
+                ]
+                .into(),
+                forced_hosts: [("survival".to_string(), "survival".to_string())].into(),
+                ..Default::default()
+            })
+            .unwrap(),
+        );
+
+        // both are logging in at the same time, only one of them gets through,
+        // even though they are routed to different servers
+        let id = Uuid::new_v4();
+        let mut first = login(&proxy, "localhost", Some(id)).await;
+        let mut second = login(&proxy, "survival", Some(id)).await;
+        let frames = tokio::join!(first.receive(), second.receive());
+        let mut logged_in = 0;
+        for frame in [frames.0, frames.1] {
+            match frame.unwrap().decode().unwrap() {
+                s2c::LoginPacket::GameProfile { game_profile } => {
+                    assert_eq!(game_profile.id, id);
+                    logged_in += 1;
+                }
+                s2c::LoginPacket::LoginDisconnect { .. } => {}
+                _ => panic!(),
+            }
+        }
+        assert_eq!(logged_in, 1);
+
+        // the rejected login doesn't remove the session of the other one
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        assert!(proxy.session(&id).is_some());
+    }
+}
@@ -0,0 +1,79 @@
+use std::{net::SocketAddr, path::PathBuf, sync::Arc};
+
+use clap::Parser;
+use tokio::net::TcpListener;
+
+use iokum_mcje::inspect::Inspection;
+use iokum_mcje_proxy::{Config, Error, InspectConfig, Plugin, Proxy, Session};
+
+#[derive(Parser)]
+#[command(about)]
+struct Arguments {
+    /// Path to the config file
+    #[arg(long, default_value = "proxy.toml")]
+    config: PathBuf,
+}
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    // parse arguments and config
+    let arguments = Arguments::parse();
+    let config: Config = toml::from_str(&std::fs::read_to_string(&arguments.config)?)?;
+    // bind listener and run until terminated
+    let listener = TcpListener::bind(&config.bind).await?;
+    let inspect = config.inspect.clone().unwrap_or_default();
+    let mut proxy = Proxy::new(config)?;
+    proxy.add_plugin(Log { inspect });
+    let proxy = Arc::new(proxy);
+    proxy.run(listener, shutdown()).await?;
+    Ok(())
+}
+
+async fn shutdown() {
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{signal, SignalKind};
+
+        let mut terminate = signal(SignalKind::terminate()).unwrap();
+        tokio::select! {
+            _ = tokio::signal::ctrl_c() => {}
+            _ = terminate.recv() => {}
+        }
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = tokio::signal::ctrl_c().await;
+    }
+}
+
+/// Prints errors, dropped packets and inspected packets.
+struct Log {
+    inspect: InspectConfig,
+}
+
+impl Plugin for Log {
+    fn error(&self, _proxy: &Proxy, address: SocketAddr, error: &Error) {
+        eprintln!("{address}: {error}");
+    }
+
+    fn dropped(&self, _proxy: &Proxy, session: &Arc<Session>, name: &str, length: usize) {
+        eprintln!(
+            "{}: Dropped {name} packet of {length} bytes",
+            session.address()
+        );
+    }
+
+    fn inspect(&self, _proxy: &Proxy, session: &Arc<Session>, inspection: &Inspection) {
+        if self.inspect.json {
+            let mut json = inspection.to_json();
+            json["player"] = session.user().name.clone().into();
+            println!("{json}");
+        } else {
+            print!(
+                "{} {}",
+                session.user().name,
+                inspection.to_tree(self.inspect.max_items)
+            );
+        }
+    }
+}
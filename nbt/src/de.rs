@@ -16,11 +16,15 @@ where
     value
 }
 
+/// Maximum depth of nested compounds and lists.
+const MAX_DEPTH: usize = 512;
+
 struct Deserializer<'de> {
     data: &'de [u8],
 
     name: bool,
     current_type: TagType,
+    depth: usize,
 }
 
 impl<'de> Deserializer<'de> {
@@ -29,24 +33,61 @@ impl<'de> Deserializer<'de> {
             data: input,
             name: false,
             current_type: TagType::default(),
+            depth: 0,
         };
         // read first named tag header
-        let type_ = TagType::try_from(_self.data.read_i8()?).unwrap();
+        let type_ = _self.read_type()?;
         if type_ != TagType::End {
-            let name_length = _self.data.read_i16::<BigEndian>()?;
-            let (_name, data) = _self.data.split_at(name_length as usize);
-            _self.data = data;
+            _self.read_str()?;
         }
         _self.current_type = type_;
         Ok(_self)
     }
+
+    fn read_type(&mut self) -> Result<TagType> {
+        let type_ = self.data.read_i8()?;
+        TagType::try_from(type_).map_err(|_| Error::UnknownTagType(type_))
+    }
+
+    fn read_str(&mut self) -> Result<&'de str> {
+        let length = self.data.read_u16::<BigEndian>()? as usize;
+        if self.data.len() < length {
+            return Err(Error::UnexpectedEnd);
+        }
+        let (value, data) = self.data.split_at(length);
+        self.data = data;
+        Ok(std::str::from_utf8(value)?)
+    }
+
+    fn read_seq(&mut self, type_: TagType) -> Result<SeqAccess<'_, 'de>> {
+        let count = self.data.read_i32::<BigEndian>()?;
+        // lists of end tags can't have elements, as they would take no space
+        if count < 0 || (type_ == TagType::End && count != 0) {
+            return Err(Error::InvalidLength);
+        }
+        Ok(SeqAccess {
+            de: self,
+            type_,
+            count: count as u32,
+        })
+    }
+
+    fn nested<T>(&mut self, f: impl FnOnce(&mut Self) -> Result<T>) -> Result<T> {
+        if self.depth == MAX_DEPTH {
+            return Err(Error::DepthLimitExceeded);
+        }
+        self.depth += 1;
+        let value = f(self);
+        self.depth -= 1;
+        value
+    }
 }
 
 impl<'de, 'a> serde::de::Deserializer<'de> for &'a mut Deserializer<'de> {
     type Error = Error;
 
     forward_to_deserialize_any! {
-        i8 i16 i32 i64 u8 u16 u32 u64 f32 f64 char str string bytes byte_buf unit unit_struct
+        i8 i16 i32 i64 f32 f64 char str string bytes byte_buf unit unit_struct
         newtype_struct seq tuple tuple_struct map struct enum identifier ignored_any
     }
 
@@ -56,10 +97,7 @@ impl<'de, 'a> serde::de::Deserializer<'de> for &'a mut Deserializer<'de> {
     {
         if self.name {
             self.name = false;
-            let name_length = self.data.read_i16::<BigEndian>()?;
-            let (name, data) = self.data.split_at(name_length as usize);
-            self.data = data;
-            visitor.visit_str(std::str::from_utf8(name).unwrap())
+            visitor.visit_borrowed_str(self.read_str()?)
         } else {
             match self.current_type {
                 TagType::End => visitor.visit_unit(),
@@ -69,33 +107,15 @@ impl<'de, 'a> serde::de::Deserializer<'de> for &'a mut Deserializer<'de> {
                 TagType::Long => visitor.visit_i64(self.data.read_i64::<BigEndian>()?),
                 TagType::Float => visitor.visit_f32(self.data.read_f32::<BigEndian>()?),
                 TagType::Double => visitor.visit_f64(self.data.read_f64::<BigEndian>()?),
-                TagType::ByteArray => visitor.visit_seq(SeqAccess {
-                    type_: TagType::Byte,
-                    count: self.data.read_i32::<BigEndian>()? as u32,
-                    de: self,
-                }),
-                TagType::String => {
-                    let value_length = self.data.read_i16::<BigEndian>()?;
-                    let (value, data) = self.data.split_at(value_length as usize);
-                    self.data = data;
-                    visitor.visit_str(std::str::from_utf8(value).unwrap())
+                TagType::ByteArray => visitor.visit_seq(self.read_seq(TagType::Byte)?),
+                TagType::String => visitor.visit_borrowed_str(self.read_str()?),
+                TagType::List => {
+                    let type_ = self.read_type()?;
+                    self.nested(|de| visitor.visit_seq(de.read_seq(type_)?))
                 }
-                TagType::List => visitor.visit_seq(SeqAccess {
-                    type_: TagType::try_from(self.data.read_i8()?).unwrap(),
-                    count: self.data.read_i32::<BigEndian>()? as u32,
-                    de: self,
-                }),
-                TagType::Compound => visitor.visit_map(self),
-                TagType::IntArray => visitor.visit_seq(SeqAccess {
-                    type_: TagType::Int,
-                    count: self.data.read_i32::<BigEndian>()? as u32,
-                    de: self,
-                }),
-                TagType::LongArray => visitor.visit_seq(SeqAccess {
-                    type_: TagType::Long,
-                    count: self.data.read_i32::<BigEndian>()? as u32,
-                    de: self,
-                }),
+                TagType::Compound => self.nested(|de| visitor.visit_map(de)),
+                TagType::IntArray => visitor.visit_seq(self.read_seq(TagType::Int)?),
+                TagType::LongArray => visitor.visit_seq(self.read_seq(TagType::Long)?),
             }
         }
     }
@@ -115,6 +135,51 @@ impl<'de, 'a> serde::de::Deserializer<'de> for &'a mut Deserializer<'de> {
         }
     }
 
+    // unsigned integers are serialized as the signed tag of the same size
+    fn deserialize_u8<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: serde::de::Visitor<'de>,
+    {
+        if !self.name && self.current_type == TagType::Byte {
+            visitor.visit_u8(self.data.read_i8()? as u8)
+        } else {
+            self.deserialize_any(visitor)
+        }
+    }
+
+    fn deserialize_u16<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: serde::de::Visitor<'de>,
+    {
+        if !self.name && self.current_type == TagType::Short {
+            visitor.visit_u16(self.data.read_i16::<BigEndian>()? as u16)
+        } else {
+            self.deserialize_any(visitor)
+        }
+    }
+
+    fn deserialize_u32<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: serde::de::Visitor<'de>,
+    {
+        if !self.name && self.current_type == TagType::Int {
+            visitor.visit_u32(self.data.read_i32::<BigEndian>()? as u32)
+        } else {
+            self.deserialize_any(visitor)
+        }
+    }
+
+    fn deserialize_u64<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: serde::de::Visitor<'de>,
+    {
+        if !self.name && self.current_type == TagType::Long {
+            visitor.visit_u64(self.data.read_i64::<BigEndian>()? as u64)
+        } else {
+            self.deserialize_any(visitor)
+        }
+    }
+
     fn deserialize_option<V>(self, visitor: V) -> Result<V::Value>
     where
         V: serde::de::Visitor<'de>,
@@ -164,7 +229,7 @@ impl<'a, 'de> serde::de::MapAccess<'de> for &'a mut Deserializer<'de> {
         K: serde::de::DeserializeSeed<'de>,
     {
         // read named tag header
-        self.current_type = TagType::try_from(self.data.read_i8()?).unwrap();
+        self.current_type = self.read_type()?;
         if !matches!(self.current_type, TagType::End) {
             self.name = true;
             seed.deserialize(&mut **self).map(Some)
@@ -180,3 +245,34 @@ impl<'a, 'de> serde::de::MapAccess<'de> for &'a mut Deserializer<'de> {
         seed.deserialize(&mut **self)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use crate::{de::from_slice, ser::to_vec};
+
+    #[test]
+    fn unsigned() {
+        // values above the signed range are stored as negative numbers
+        fn round_trip<T>(value: T)
+        where
+            T: serde::Serialize + serde::de::DeserializeOwned + PartialEq + std::fmt::Debug,
+        {
+            let compound = BTreeMap::from([("value".to_string(), value)]);
+            let data = to_vec(&compound).unwrap();
+            let mut input = data.as_slice();
+            assert_eq!(
+                from_slice::<BTreeMap<String, T>>(&mut input).unwrap(),
+                compound
+            );
+            assert!(input.is_empty());
+        }
+
+        round_trip(200u8);
+        round_trip(u16::MAX);
+        round_trip(u32::MAX);
+        round_trip(u64::MAX);
+        round_trip(1u64);
+    }
+}
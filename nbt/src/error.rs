@@ -9,6 +9,17 @@ pub enum Error {
 
     #[error("IO error")]
     Io(#[from] std::io::Error),
+    #[error("UTF8 error")]
+    Utf8(#[from] std::str::Utf8Error),
+
+    #[error("Unknown tag type: {0}")]
+    UnknownTagType(i8),
+    #[error("Invalid length")]
+    InvalidLength,
+    #[error("Unexpected end")]
+    UnexpectedEnd,
+    #[error("Depth limit exceeded")]
+    DepthLimitExceeded,
 }
 
 pub type Result<T> = std::result::Result<T, Error>;